@@ -1,9 +1,26 @@
 use std::io::{self, BufRead};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::core::{generate, GeneratorConfig, Secret, VaultManager};
-use clap::{Parser, Subcommand};
-use serdevault::VaultFile;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::core::{
+    audit::{self, Issue},
+    backup,
+    breach::PwnedFile,
+    error::CoreError,
+    export, generate, group,
+    import::{self, ImportFormat},
+    kdf::{self, open_vault_file, KdfParams, MIN_RECOMMENDED_MEMORY_KIB},
+    lock::VaultLock,
+    merge::MergeReport,
+    otp::unix_now,
+    secret::SecretString,
+    strength,
+    sync::{Pulled, Repo},
+    CustomField, EntryKind, FieldKind, GeneratorMode, Otp, Secret, VaultManager,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(
@@ -14,6 +31,18 @@ use serdevault::VaultFile;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Command>,
+
+    /// Lock the TUI after this many seconds without a key press (0 never locks)
+    #[arg(long, global = true, value_name = "SECS", default_value_t = 300)]
+    pub lock_after: u64,
+
+    /// Vault file to open (overrides VALT_VAULT and the config file)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub vault: Option<PathBuf>,
+
+    /// Open the vault of a named profile from the config file
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "vault")]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -22,6 +51,14 @@ pub enum Command {
     List {
         /// Fuzzy search query (e.g. "github")
         query: Option<String>,
+
+        /// Also show each password's strength score (0-4) and label
+        #[arg(long, short)]
+        long: bool,
+
+        /// Only list secrets in this group or its subgroups (e.g. "clients/acme")
+        #[arg(long, short, value_name = "GROUP")]
+        group: Option<String>,
     },
 
     /// Print the password of the best-matching secret to stdout
@@ -29,99 +66,518 @@ pub enum Command {
     /// Matched name and username are printed to stderr so that only the
     /// password reaches stdout, making the command scriptable:
     ///   export TOKEN=$(valt get myapi)
+    /// Other entry types print their main value instead: the number of a
+    /// card, the document number of an identity, the private key of an SSH
+    /// key, the text of a note.
     Get {
         /// Name to search for (fuzzy)
         name: String,
     },
 
     /// Add a new secret
-    Add {
-        /// Secret name
+    Add(AddArgs),
+
+    /// Print the current one-time code of the best-matching secret
+    ///
+    /// The code goes to stdout and its remaining validity to stderr.
+    /// For counter-based (HOTP) seeds the counter is advanced and saved.
+    Otp {
+        /// Name to search for (fuzzy)
         name: String,
+    },
 
-        /// Username / login
+    /// Show previous passwords of the best-matching secret
+    ///
+    /// Passwords are masked unless `--show` is given. `--restore N` makes
+    /// entry N the current password again; the current one joins the history.
+    History {
+        /// Name to search for (fuzzy)
+        name: String,
+
+        /// Print previous passwords in clear
         #[arg(long, short)]
-        username: Option<String>,
+        show: bool,
+
+        /// Restore history entry N (as numbered in the listing)
+        #[arg(long, short, value_name = "N")]
+        restore: Option<usize>,
+    },
+
+    /// Change the master password and re-encrypt the vault
+    ///
+    /// Backups taken before the change stay readable with the old password,
+    /// so that an earlier version can still be restored.
+    Passwd {
+        /// Delete those backups, keeping only a snapshot of the re-encrypted
+        /// vault. Versions committed by `valt sync` stay in git history.
+        #[arg(long)]
+        wipe_backups: bool,
+    },
+
+    /// Show, benchmark and tune the Argon2 key-derivation parameters
+    ///
+    /// Prints the current parameters and unlock time, benchmarks this
+    /// machine and suggests parameters for the target latency. `--apply`
+    /// re-encrypts the vault with the suggestion, or with the explicit
+    /// --memory / --iterations / --parallelism values.
+    Kdf(KdfArgs),
+
+    /// Import secrets exported by another password manager
+    ///
+    /// Folders become groups and extra fields become custom fields. Entries
+    /// whose name and username match an existing secret are reported as
+    /// duplicates and left out unless `--allow-duplicates` is given.
+    Import(ImportArgs),
 
-        /// URL associated with this secret
+    /// Export secrets to JSON, CSV or a separately encrypted vault file
+    ///
+    /// JSON keeps every field; CSV uses Bitwarden's column layout. Both are
+    /// plaintext and ask for confirmation before writing. `encrypted` writes
+    /// a valt vault protected by a new passphrase.
+    Export(ExportArgs),
+
+    /// Check the vault for reused, weak and old passwords, duplicate names
+    /// and missing usernames or URLs
+    Audit {
+        /// Report passwords not changed for more than this many days
+        #[arg(long, value_name = "DAYS", default_value_t = audit::DEFAULT_MAX_AGE_DAYS)]
+        max_age: i64,
+
+        /// Print the findings as JSON
         #[arg(long)]
-        url: Option<String>,
+        json: bool,
+    },
+
+    /// Check every password against a local Pwned Passwords dump
+    ///
+    /// The file is the SHA-1 list "ordered by hash" from haveibeenpwned.com;
+    /// nothing is sent over the network. Compromised secrets are flagged in
+    /// the vault and shown with a warning in the TUI until their password
+    /// changes.
+    Breach {
+        /// Path to pwned-passwords-sha1-ordered-by-hash.txt
+        #[arg(long, value_name = "PATH")]
+        hibp_file: PathBuf,
+    },
 
-        /// Comma-separated tags (e.g. "work,ssh")
+    /// Move the best-matching secret to another group
+    Mv {
+        /// Name to search for (fuzzy)
+        name: String,
+
+        /// Destination group path (e.g. "clients/acme/prod"); "/" for the top level
+        group: String,
+    },
+
+    /// Move the best-matching secret to the trash
+    Rm {
+        /// Name to search for (fuzzy)
+        name: String,
+
+        /// Skip the confirmation prompt
         #[arg(long, short)]
-        tags: Option<String>,
+        yes: bool,
+    },
+
+    /// Merge another copy of the vault into this one
+    ///
+    /// For conflicted copies left by a file-sync service. Secrets are matched
+    /// by id; when both copies changed one, the newer version wins and the
+    /// other password is kept in its history. Deletions are carried over.
+    /// The other file is left untouched.
+    Merge {
+        /// The other vault file (e.g. "vault (1).svlt")
+        other: PathBuf,
+
+        /// A copy both vaults started from, such as a backup. Values changed
+        /// on one side only are then taken without conflict
+        #[arg(long)]
+        base: Option<PathBuf>,
+
+        /// Show what would change without writing the vault
+        #[arg(long, short = 'n')]
+        dry_run: bool,
+    },
 
-        /// Generate a random password instead of prompting
+    /// List, restore or purge deleted secrets
+    ///
+    /// Deleted secrets stay in the trash for `trash.retention_days` (30 by
+    /// default) and are then purged for good.
+    #[command(subcommand)]
+    Trash(TrashCommand),
+
+    /// Pull the vault from its git remote, merge and push it back
+    ///
+    /// The vault's directory must be a git repository (see `valt sync
+    /// init`). When both sides changed the vault, the two versions are
+    /// decrypted and merged secret by secret, as `valt merge` does.
+    Sync {
+        #[command(subcommand)]
+        action: Option<SyncCommand>,
+    },
+
+    /// List automatic backups of the vault, or restore one
+    #[command(subcommand)]
+    Backup(BackupCommand),
+
+    /// Read or change settings in the config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommand {
+    /// Show every backup with its date and number of entries
+    List,
+
+    /// Replace the vault with a backup; the current vault is backed up first
+    Restore {
+        /// Backup id, as shown by `valt backup list`
+        id: String,
+
+        /// Skip the confirmation prompt
         #[arg(long, short)]
-        generate: bool,
+        yes: bool,
     },
+}
 
-    /// Delete the best-matching secret
-    Rm {
+#[derive(Subcommand)]
+pub enum TrashCommand {
+    /// Show deleted secrets, most recent first
+    List,
+
+    /// Bring the best-matching deleted secret back
+    Restore {
         /// Name to search for (fuzzy)
         name: String,
+    },
 
+    /// Purge every secret in the trash for good
+    Empty {
         /// Skip the confirmation prompt
         #[arg(long, short)]
         yes: bool,
     },
 }
 
-pub fn run_command(
-    command: Command,
-    vault_path: &PathBuf,
-) -> Result<(), Box<dyn std::error::Error>> {
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// Make the vault's directory a git repository and commit the vault
+    Init {
+        /// URL of the remote to sync with, added under `sync.remote`
+        #[arg(long)]
+        remote: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the value of a key (e.g. generator.length)
+    Get { key: String },
+
+    /// Set a key; an empty value unsets optional keys and profiles
+    Set { key: String, value: String },
+
+    /// Print every key with its current value
+    List,
+}
+
+#[derive(Args)]
+pub struct AddArgs {
+    /// Secret name
+    name: String,
+
+    /// Type of entry; the fields of the type not given with --field are
+    /// prompted for
+    #[arg(long = "type", value_enum, default_value_t = EntryType::Login)]
+    kind: EntryType,
+
+    /// With --type ssh-key, read the private key from this file, and the
+    /// public key from PATH.pub if it exists
+    #[arg(long, value_name = "PATH")]
+    key_file: Option<PathBuf>,
+
+    /// Username / login
+    #[arg(long, short)]
+    username: Option<String>,
+
+    /// URL associated with this secret
+    #[arg(long)]
+    url: Option<String>,
+
+    /// Comma-separated tags (e.g. "work,ssh")
+    #[arg(long, short)]
+    tags: Option<String>,
+
+    /// Group path (e.g. "clients/acme/prod")
+    #[arg(long, short = 'G')]
+    group: Option<String>,
+
+    /// Generate a random password instead of prompting
+    #[arg(long, short)]
+    generate: bool,
+
+    /// With --generate, make a diceware passphrase of N words instead
+    #[arg(long, value_name = "N", requires = "generate")]
+    words: Option<usize>,
+
+    /// TOTP/HOTP seed: raw base32 or an `otpauth://` URI
+    #[arg(long)]
+    otp: Option<String>,
+
+    /// Custom field as `label=value`, repeatable. Prefix the label with a
+    /// kind to change how it is shown: `hidden:PIN=1234`, `date:Expires=2027-01-31`
+    /// (kinds: text, hidden, url, email, date). Fields of the entry type
+    /// are set the same way: `--type card -f Number="4111 1111 1111 1111"`.
+    #[arg(long = "field", short = 'f', value_name = "[KIND:]LABEL=VALUE")]
+    fields: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EntryType {
+    Login,
+    Card,
+    Identity,
+    SshKey,
+    /// A wireless network: SSID, security type and password
+    Wifi,
+    /// Free-form text, read from stdin
+    Note,
+}
+
+impl From<EntryType> for EntryKind {
+    fn from(kind: EntryType) -> Self {
+        match kind {
+            EntryType::Login => EntryKind::Login,
+            EntryType::Card => EntryKind::Card,
+            EntryType::Identity => EntryKind::Identity,
+            EntryType::SshKey => EntryKind::SshKey,
+            EntryType::Wifi => EntryKind::Wifi,
+            EntryType::Note => EntryKind::Note,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct KdfArgs {
+    /// Target unlock latency, in milliseconds
+    #[arg(long, default_value_t = 1000, value_name = "MS")]
+    target_ms: u64,
+
+    /// Never suggest more memory than this, in MiB
+    #[arg(long, default_value_t = 1024, value_name = "MIB")]
+    max_memory: u32,
+
+    /// Memory cost in MiB (overrides the suggestion)
+    #[arg(long, short, value_name = "MIB")]
+    memory: Option<u32>,
+
+    /// Number of passes (overrides the suggestion)
+    #[arg(long, short = 't', value_name = "N")]
+    iterations: Option<u32>,
+
+    /// Degree of parallelism
+    #[arg(long, short, default_value_t = 1, value_name = "N")]
+    parallelism: u32,
+
+    /// Re-encrypt the vault with the resulting parameters
+    #[arg(long)]
+    apply: bool,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Export file to read (the store directory for `--format pass`)
+    path: PathBuf,
+
+    /// Format of the export file
+    #[arg(long, short = 'F', value_enum)]
+    format: ImportSource,
+
+    /// Show what would be imported without writing the vault
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+
+    /// Also import entries that duplicate an existing secret
+    #[arg(long)]
+    allow_duplicates: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportSource {
+    BitwardenJson,
+    KeepassXml,
+    #[value(name = "1password-csv")]
+    OnePasswordCsv,
+    ChromeCsv,
+    FirefoxCsv,
+    GenericCsv,
+    /// A password-store directory, decrypted with the local `gpg`
+    Pass,
+}
+
+impl From<ImportSource> for ImportFormat {
+    fn from(source: ImportSource) -> Self {
+        match source {
+            ImportSource::BitwardenJson => ImportFormat::BitwardenJson,
+            ImportSource::KeepassXml => ImportFormat::KeepassXml,
+            ImportSource::OnePasswordCsv => ImportFormat::OnePasswordCsv,
+            ImportSource::ChromeCsv => ImportFormat::ChromeCsv,
+            ImportSource::FirefoxCsv => ImportFormat::FirefoxCsv,
+            ImportSource::GenericCsv => ImportFormat::GenericCsv,
+            ImportSource::Pass => ImportFormat::PassStore,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// File to write
+    path: PathBuf,
+
+    /// Output format
+    #[arg(long, short = 'F', value_enum)]
+    format: ExportFormat,
+
+    /// Only export secrets carrying this tag
+    #[arg(long, short)]
+    tag: Option<String>,
+
+    /// Only export secrets matching this fuzzy query
+    #[arg(long, short)]
+    query: Option<String>,
+
+    /// Skip the confirmation prompt
+    #[arg(long, short)]
+    yes: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Csv,
+    Encrypted,
+}
+
+/// What every command needs to know besides its own arguments.
+pub struct Context<'a> {
+    pub vault_path: &'a Path,
+    pub config: &'a Config,
+}
+
+pub fn run_command(command: Command, ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
     match command {
-        Command::List { query } => cmd_list(vault_path, query.as_deref()),
-        Command::Get { name } => cmd_get(vault_path, &name),
-        Command::Add {
+        Command::List { query, long, group } => {
+            cmd_list(ctx, query.as_deref(), long, group.as_deref())
+        }
+        Command::Get { name } => cmd_get(ctx, &name),
+        Command::Add(args) => cmd_add(ctx, args),
+        Command::Otp { name } => cmd_otp(ctx, &name),
+        Command::History {
             name,
-            username,
-            url,
-            tags,
-            generate: gen,
-        } => cmd_add(
-            vault_path,
-            &name,
-            username.as_deref(),
-            url.as_deref(),
-            tags.as_deref(),
-            gen,
-        ),
-        Command::Rm { name, yes } => cmd_rm(vault_path, &name, yes),
+            show,
+            restore,
+        } => cmd_history(ctx, &name, show, restore),
+        Command::Passwd { wipe_backups } => cmd_passwd(ctx, wipe_backups),
+        Command::Kdf(args) => cmd_kdf(ctx, args),
+        Command::Import(args) => cmd_import(ctx, args),
+        Command::Export(args) => cmd_export(ctx, args),
+        Command::Audit { max_age, json } => cmd_audit(ctx, max_age, json),
+        Command::Breach { hibp_file } => cmd_breach(ctx, &hibp_file),
+        Command::Mv { name, group } => cmd_mv(ctx, &name, &group),
+        Command::Rm { name, yes } => cmd_rm(ctx, &name, yes),
+        Command::Trash(TrashCommand::List) => cmd_trash_list(ctx),
+        Command::Trash(TrashCommand::Restore { name }) => cmd_trash_restore(ctx, &name),
+        Command::Trash(TrashCommand::Empty { yes }) => cmd_trash_empty(ctx, yes),
+        Command::Merge {
+            other,
+            base,
+            dry_run,
+        } => cmd_merge(ctx, &other, base.as_deref(), dry_run),
+        Command::Sync { action: None } => cmd_sync(ctx),
+        Command::Sync {
+            action: Some(SyncCommand::Init { remote }),
+        } => cmd_sync_init(ctx, remote.as_deref()),
+        Command::Backup(BackupCommand::List) => cmd_backup_list(ctx),
+        Command::Backup(BackupCommand::Restore { id, yes }) => cmd_backup_restore(ctx, &id, yes),
+        // Dispatched by `main` before a vault is selected.
+        Command::Config(cmd) => cmd_config(cmd),
     }
 }
 
-fn prompt_vault_password() -> Result<String, Box<dyn std::error::Error>> {
-    Ok(rpassword::prompt_password("Vault password: ")?)
+fn prompt_vault_password() -> Result<SecretString, Box<dyn std::error::Error>> {
+    prompt_secret("Vault password: ")
+}
+
+/// Read a line with echo, into a buffer wiped on drop.
+fn prompt_line(prompt: &str) -> Result<SecretString, Box<dyn std::error::Error>> {
+    eprint!("{prompt}");
+    let mut line = SecretString::default();
+    io::stdin().lock().read_line(&mut line)?;
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(line)
+}
+
+/// Read a password without echo, into a buffer wiped on drop.
+fn prompt_secret(prompt: &str) -> Result<SecretString, Box<dyn std::error::Error>> {
+    Ok(SecretString::new(rpassword::prompt_password(prompt)?))
 }
 
 /// Open an existing vault — fails with a helpful message if the file is absent.
-fn open_vault(vault_path: &PathBuf) -> Result<VaultManager, Box<dyn std::error::Error>> {
+fn open_vault(ctx: &Context) -> Result<VaultManager, Box<dyn std::error::Error>> {
+    ensure_vault_exists(ctx.vault_path)?;
+    let password = prompt_vault_password()?;
+    unlock_vault(ctx, &password)
+}
+
+fn ensure_vault_exists(vault_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     if !vault_path.exists() {
         return Err("Vault not found. Add your first secret with `valt add` \
              or launch `valt` to open the TUI."
             .into());
     }
-    let password = prompt_vault_password()?;
-    let vf = VaultFile::open(vault_path, &password);
-    VaultManager::open(vf)
-        .map(|m| m.with_backup_path(vault_path.clone()))
-        .map_err(|_| "Wrong password or corrupted vault.".into())
+    Ok(())
+}
+
+fn unlock_vault(ctx: &Context, password: &str) -> Result<VaultManager, Box<dyn std::error::Error>> {
+    let vf = open_vault_file(ctx.vault_path, password);
+    VaultManager::open_at(vf, ctx.vault_path.to_path_buf())
+        .map(|m| {
+            m.with_backups(ctx.config.backup.on_save())
+                .with_git_commits(ctx.config.sync.auto_commit)
+                .with_trash_retention(ctx.config.trash.retention())
+        })
+        .map_err(|e| match e {
+            CoreError::Vault(_) => "Wrong password or corrupted vault.".into(),
+            e => e.into(),
+        })
 }
 
 /// Open existing vault or create a new one (used by `add`).
-fn open_or_create_vault(vault_path: &PathBuf) -> Result<VaultManager, Box<dyn std::error::Error>> {
+fn open_or_create_vault(ctx: &Context) -> Result<VaultManager, Box<dyn std::error::Error>> {
     let password = prompt_vault_password()?;
-    let vf = VaultFile::open(vault_path, &password);
-    VaultManager::open_or_create(vf)
-        .map(|m| m.with_backup_path(vault_path.clone()))
+    let vf = open_vault_file(ctx.vault_path, &password);
+    VaultManager::open_or_create_at(vf, ctx.vault_path.to_path_buf())
+        .map(|m| {
+            m.with_backups(ctx.config.backup.on_save())
+                .with_git_commits(ctx.config.sync.auto_commit)
+                .with_trash_retention(ctx.config.trash.retention())
+        })
         .map_err(|e| format!("Failed to open vault: {e}").into())
 }
 
-fn cmd_list(vault_path: &PathBuf, query: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-    let vault = open_vault(vault_path)?;
-    let results = vault.search(query.unwrap_or(""));
+fn cmd_list(
+    ctx: &Context,
+    query: Option<&str>,
+    long: bool,
+    group: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let vault = open_vault(ctx)?;
+    let mut results = vault.search(query.unwrap_or(""));
+    if let Some(filter) = group {
+        results.retain(|s| group::contains(filter, s.group.as_deref()));
+    }
 
     if results.is_empty() {
         eprintln!("No secrets found.");
@@ -130,15 +586,33 @@ fn cmd_list(vault_path: &PathBuf, query: Option<&str>) -> Result<(), Box<dyn std
 
     for s in &results {
         let username = s.username.as_deref().unwrap_or("");
-        let url = s.url.as_deref().unwrap_or("");
-        println!("  {:<30}  {:<24}  {}", s.name, username, url);
+        // Entries other than logins show their type where they have no URL.
+        let url = match (&s.url, s.kind) {
+            (Some(url), _) => url.clone(),
+            (None, EntryKind::Login) => String::new(),
+            (None, kind) => format!("({})", kind.title()),
+        };
+        if long {
+            let strength = if !s.password.is_empty() {
+                let strength = strength::estimate(&s.password);
+                format!("{}/4 {:<11}", strength.score, strength.label())
+            } else {
+                String::new()
+            };
+            println!(
+                "  {:<30}  {:<24}  {:<15}  {}",
+                s.name, username, strength, url
+            );
+        } else {
+            println!("  {:<30}  {:<24}  {}", s.name, username, url);
+        }
     }
 
     Ok(())
 }
 
-fn cmd_get(vault_path: &PathBuf, name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let vault = open_vault(vault_path)?;
+fn cmd_get(ctx: &Context, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let vault = open_vault(ctx)?;
     let results = vault.search(name);
 
     match results.first() {
@@ -150,48 +624,132 @@ fn cmd_get(vault_path: &PathBuf, name: &str) -> Result<(), Box<dyn std::error::E
             } else {
                 eprintln!("Matched: {} ({})", s.name, username);
             }
-            println!("{}", s.password);
+            let value = s.main_value().ok_or_else(|| {
+                format!(
+                    "'{}' has no {}.",
+                    s.name,
+                    s.kind.main_field().to_lowercase()
+                )
+            })?;
+            // A key file already ends with its newline.
+            println!("{}", value.strip_suffix('\n').unwrap_or(value));
             Ok(())
         }
         None => Err(format!("No secret matching '{name}'.").into()),
     }
 }
 
-fn cmd_add(
-    vault_path: &PathBuf,
-    name: &str,
-    username: Option<&str>,
-    url: Option<&str>,
-    tags: Option<&str>,
-    gen: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut vault = open_or_create_vault(vault_path)?;
+fn cmd_add(ctx: &Context, args: AddArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let AddArgs {
+        name,
+        kind,
+        key_file,
+        username,
+        url,
+        tags,
+        group: group_path,
+        generate: gen,
+        words,
+        otp,
+        fields,
+    } = args;
+
+    let kind = EntryKind::from(kind);
+
+    // Validate the seed and fields before asking for any password.
+    if kind.password_label().is_none() && gen {
+        return Err(format!("{} entries have no password to generate.", kind.title()).into());
+    }
+    if !kind.has_login() && (username.is_some() || url.is_some()) {
+        return Err(format!("{} entries have no username or URL.", kind.title()).into());
+    }
+    if kind != EntryKind::Login && otp.is_some() {
+        return Err("Only logins have an OTP seed.".into());
+    }
+    if key_file.is_some() && kind != EntryKind::SshKey {
+        return Err("--key-file only applies to --type ssh-key.".into());
+    }
+    let otp = otp.as_deref().map(Otp::parse).transpose()?;
+    let mut fields = fields
+        .iter()
+        .map(|f| parse_field(f).map(|f| as_detail(kind, f)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(path) = &key_file {
+        fields.extend(read_key_pair(path)?);
+    }
+    // Multi-line fields are never prompted for.
+    for spec in kind.schema().iter().filter(|s| s.multiline && s.required) {
+        if !fields.iter().any(|f| f.label == spec.label) {
+            return Err(format!("{} is required: pass it with --key-file.", spec.label).into());
+        }
+    }
+
+    let mut vault = open_or_create_vault(ctx)?;
 
     // Warn if a secret with the same name already exists.
-    if vault.search(name).iter().any(|s| s.name == name) {
+    if vault.search(&name).iter().any(|s| s.name == name) {
         eprintln!("Warning: a secret named '{name}' already exists.");
     }
 
-    let password = if gen {
-        let pwd = generate(&GeneratorConfig::default())?;
-        eprintln!("Generated: {pwd}");
+    // Ask for the fields of the type left out of the arguments. Blank
+    // answers leave optional fields unset.
+    for spec in kind.schema() {
+        if spec.multiline || fields.iter().any(|f| f.label == spec.label) {
+            continue;
+        }
+        let prompt = format!("{}: ", spec.label);
+        let value = match spec.kind {
+            FieldKind::Hidden => prompt_secret(&prompt)?,
+            _ => prompt_line(&prompt)?,
+        };
+        if !value.trim().is_empty() {
+            fields.push(CustomField::new(spec.label, value.trim(), spec.kind));
+        }
+    }
+    if let Some(msg) = kind.check(&fields) {
+        return Err(format!("{msg}.").into());
+    }
+    let notes = if kind == EntryKind::Note {
+        eprintln!("Type the note, then Ctrl+D on an empty line:");
+        let mut text = SecretString::default();
+        io::Read::read_to_string(&mut io::stdin(), &mut text)?;
+        if text.trim().is_empty() {
+            return Err("The note is empty.".into());
+        }
+        Some(text)
+    } else {
+        None
+    };
+
+    let password = if kind.password_label().is_none() {
+        SecretString::default()
+    } else if gen {
+        let mut config = ctx.config.generator.to_generator_config();
+        if let Some(words) = words {
+            config.mode = GeneratorMode::Passphrase;
+            config.passphrase.words = words;
+        }
+        let pwd = SecretString::new(generate(&config)?);
+        eprintln!("Generated: {}", pwd.as_str());
         pwd
     } else {
-        let p1 = rpassword::prompt_password("Password: ")?;
-        let p2 = rpassword::prompt_password("Confirm:  ")?;
-        if p1 != p2 {
-            return Err("Passwords do not match.".into());
+        let label = kind.password_label().unwrap_or("Password");
+        let p1 = prompt_secret(&format!("{label}: "))?;
+        // A key or an open network may have none: only ask to confirm one.
+        if !(matches!(kind, EntryKind::SshKey | EntryKind::Wifi) && p1.is_empty()) {
+            let p2 = prompt_secret(&format!("{:<width$}", "Confirm: ", width = label.len() + 2))?;
+            if p1 != p2 {
+                return Err(format!("{label}s do not match.").into());
+            }
         }
         p1
     };
 
-    let mut secret = Secret::new(name, &password);
-    if let Some(u) = username {
-        secret.username = Some(u.to_string());
-    }
-    if let Some(u) = url {
-        secret.url = Some(u.to_string());
-    }
+    let mut secret = Secret::new(&name, password.as_str());
+    secret.kind = kind;
+    secret.notes = notes;
+    secret.username = username;
+    secret.url = url;
     if let Some(t) = tags {
         secret.tags = t
             .split(',')
@@ -199,35 +757,777 @@ fn cmd_add(
             .filter(|s| !s.is_empty())
             .collect();
     }
+    secret.group = group_path.as_deref().and_then(group::normalize);
+    secret.fields = fields;
+    secret.otp = otp;
 
     vault.add(secret)?;
     eprintln!("Secret '{name}' saved.");
     Ok(())
 }
 
-fn cmd_rm(vault_path: &PathBuf, name: &str, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let mut vault = open_vault(vault_path)?;
+/// `field` under the label and kind of the field of `kind` it names, if it
+/// names one: `-f number=…` sets the hidden "Number" of a card.
+fn as_detail(kind: EntryKind, field: CustomField) -> CustomField {
+    match kind
+        .schema()
+        .iter()
+        .find(|spec| spec.label.eq_ignore_ascii_case(&field.label))
+    {
+        Some(spec) => CustomField {
+            label: spec.label.to_string(),
+            kind: spec.kind,
+            ..field
+        },
+        None => field,
+    }
+}
+
+/// The private key stored at `path` and, when `path.pub` exists next to it,
+/// the public key.
+fn read_key_pair(path: &Path) -> Result<Vec<CustomField>, Box<dyn std::error::Error>> {
+    let read =
+        |path: &Path| std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()));
+    let mut fields = vec![CustomField::new(
+        "Private key",
+        read(path)?,
+        FieldKind::Hidden,
+    )];
+    let mut public = path.as_os_str().to_owned();
+    public.push(".pub");
+    let public = PathBuf::from(public);
+    if public.exists() {
+        fields.push(CustomField::new(
+            "Public key",
+            read(&public)?.trim(),
+            FieldKind::Text,
+        ));
+    }
+    Ok(fields)
+}
+
+/// Parse a `--field` argument: `label=value` or `kind:label=value`.
+fn parse_field(arg: &str) -> Result<CustomField, String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid field '{arg}': expected label=value."))?;
+
+    let (kind, label) = match key.split_once(':') {
+        Some((kind, label)) => match kind.parse::<FieldKind>() {
+            Ok(kind) => (kind, label),
+            Err(_) => (FieldKind::Text, key),
+        },
+        None => (FieldKind::Text, key),
+    };
+
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(format!("Invalid field '{arg}': label is empty."));
+    }
+    if let Some(msg) = kind.check(value) {
+        return Err(format!("Invalid field '{label}': {msg}."));
+    }
+    Ok(CustomField::new(label, value, kind))
+}
+
+fn cmd_otp(ctx: &Context, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
     let results = vault.search(name);
 
     let secret = results
         .first()
         .ok_or_else(|| format!("No secret matching '{name}'."))?;
+    let otp = secret
+        .otp
+        .clone()
+        .ok_or_else(|| format!("'{}' has no OTP seed.", secret.name))?;
+    let id = secret.id;
+    let secret_name = secret.name.clone();
+
+    let now = unix_now();
+    let code = otp.generate(now)?;
+
+    match otp.seconds_remaining(now)? {
+        Some(secs) => eprintln!("Matched: {secret_name} (valid for {secs}s)"),
+        None => {
+            // HOTP: each code may be used once, so persist the next counter.
+            vault.advance_otp(id)?;
+            eprintln!("Matched: {secret_name} (counter advanced)");
+        }
+    }
+    println!("{code}");
+    Ok(())
+}
+
+fn cmd_history(
+    ctx: &Context,
+    name: &str,
+    show: bool,
+    restore: Option<usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
+    let results = vault.search(name);
 
+    let secret = results
+        .first()
+        .ok_or_else(|| format!("No secret matching '{name}'."))?;
     let id = secret.id;
     let secret_name = secret.name.clone();
+    let len = secret.history.len();
 
-    if !yes {
-        eprint!("Delete '{secret_name}'? [y/N] ");
-        let stdin = io::stdin();
-        let mut line = String::new();
-        stdin.lock().read_line(&mut line)?;
-        if line.trim().to_lowercase() != "y" {
+    if let Some(n) = restore {
+        if n == 0 || n > len {
+            return Err(format!("'{secret_name}' has no history entry #{n}.").into());
+        }
+        vault.restore_password(id, n - 1)?;
+        eprintln!("Restored password #{n} of '{secret_name}'.");
+        return Ok(());
+    }
+
+    if len == 0 {
+        eprintln!("No previous passwords for '{secret_name}'.");
+        return Ok(());
+    }
+
+    eprintln!("Previous passwords of '{secret_name}' (most recent first):");
+    for (i, entry) in secret.history.iter().enumerate() {
+        let password = if show {
+            entry.password.to_string()
+        } else {
+            "•".repeat(entry.password.chars().count().min(20))
+        };
+        println!(
+            "  {:>2}  {}  {}",
+            i + 1,
+            entry.retired_at.format("%Y-%m-%d %H:%M"),
+            password
+        );
+    }
+    Ok(())
+}
+
+fn cmd_passwd(ctx: &Context, wipe_backups: bool) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_vault_exists(ctx.vault_path)?;
+    let current = prompt_secret("Current password: ")?;
+    let mut vault = unlock_vault(ctx, &current)?;
+
+    let p1 = prompt_secret("New password:     ")?;
+    if p1.is_empty() {
+        return Err("The new password must not be empty.".into());
+    }
+    let p2 = prompt_secret("Confirm:          ")?;
+    if p1 != p2 {
+        return Err("Passwords do not match.".into());
+    }
+
+    vault.rekey(&p1)?;
+    eprintln!("Master password changed.");
+    if wipe_backups {
+        vault.clear_backups()?;
+        eprintln!("Backups made under the old password deleted.");
+    }
+    Ok(())
+}
+
+fn cmd_kdf(ctx: &Context, args: KdfArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if ctx.vault_path.exists() {
+        let current = KdfParams::read(ctx.vault_path)?;
+        eprintln!(
+            "Current:   {}  (unlock ≈ {} ms)",
+            current,
+            current.measure()?.as_millis()
+        );
+    }
+
+    let mut chosen = match (args.memory, args.iterations) {
+        (Some(m), Some(t)) => KdfParams {
+            m_cost: m.saturating_mul(1024),
+            t_cost: t,
+            p_cost: args.parallelism,
+        },
+        _ => {
+            eprintln!("Benchmarking…");
+            let rate = kdf::benchmark_rate()?;
+            kdf::suggest(
+                rate,
+                Duration::from_millis(args.target_ms),
+                args.max_memory.saturating_mul(1024),
+                args.parallelism,
+            )
+        }
+    };
+    if let Some(m) = args.memory {
+        chosen.m_cost = m.saturating_mul(1024);
+    }
+    if let Some(t) = args.iterations {
+        chosen.t_cost = t;
+    }
+    chosen.validate()?;
+
+    eprintln!(
+        "Suggested: {}  (unlock ≈ {} ms)",
+        chosen,
+        chosen.measure()?.as_millis()
+    );
+    if chosen.m_cost < MIN_RECOMMENDED_MEMORY_KIB {
+        eprintln!(
+            "Warning: less than {} MiB of memory is weak against GPU attacks.",
+            MIN_RECOMMENDED_MEMORY_KIB / 1024
+        );
+    }
+
+    if !args.apply {
+        eprintln!("Run again with --apply to re-encrypt the vault with these parameters.");
+        return Ok(());
+    }
+
+    ensure_vault_exists(ctx.vault_path)?;
+    let password = prompt_vault_password()?;
+    let mut vault = unlock_vault(ctx, &password)?;
+    vault.set_kdf_params(&password, chosen)?;
+    eprintln!("Vault re-encrypted with the new parameters.");
+    Ok(())
+}
+
+fn cmd_import(ctx: &Context, args: ImportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    // Parse the export before asking for any password.
+    let imported = ImportFormat::from(args.format)
+        .importer()
+        .import(&args.path)?;
+
+    // A dry run against a vault that does not exist yet needs no password.
+    let mut vault = if args.dry_run && !ctx.vault_path.exists() {
+        None
+    } else {
+        Some(open_or_create_vault(ctx)?)
+    };
+    let existing = vault
+        .as_ref()
+        .map(|v| v.list().to_vec())
+        .unwrap_or_default();
+    let plan = import::plan(&existing, imported.secrets);
+
+    for s in &plan.fresh {
+        let mut line = format!("+ {}", s.name);
+        if let Some(user) = s.username.as_deref() {
+            line.push_str(&format!(" ({user})"));
+        }
+        if let Some(group) = s.group.as_deref() {
+            line.push_str(&format!(" in {group}"));
+        }
+        if !s.tags.is_empty() {
+            line.push_str(&format!(" [{}]", s.tags.join(", ")));
+        }
+        println!("{line}");
+    }
+    for (s, id) in &plan.duplicates {
+        let matched = existing
+            .iter()
+            .chain(plan.fresh.iter())
+            .find(|e| e.id == *id)
+            .map(|e| e.name.as_str())
+            .unwrap_or("?");
+        println!("= {} (matches existing '{matched}')", s.name);
+    }
+    for skipped in &imported.skipped {
+        println!("! {}: {}", skipped.source, skipped.reason);
+    }
+
+    let fresh = plan.fresh.len();
+    let duplicates = plan.duplicates.len();
+    eprintln!(
+        "{fresh} new, {duplicates} duplicate{}{}, {} skipped.",
+        if duplicates == 1 { "" } else { "s" },
+        if args.allow_duplicates && duplicates > 0 {
+            " (imported anyway)"
+        } else {
+            ""
+        },
+        imported.skipped.len()
+    );
+
+    let mut to_add = plan.fresh;
+    if args.allow_duplicates {
+        to_add.extend(plan.duplicates.into_iter().map(|(s, _)| s));
+    }
+
+    match vault.as_mut() {
+        Some(vault) if !args.dry_run => {
+            let count = to_add.len();
+            vault.add_all(to_add)?;
+            eprintln!(
+                "Imported {count} secret{}.",
+                if count == 1 { "" } else { "s" }
+            );
+        }
+        _ => eprintln!("Dry run: nothing was written."),
+    }
+    Ok(())
+}
+
+fn cmd_export(ctx: &Context, args: ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let vault = open_vault(ctx)?;
+    let secrets: Vec<Secret> = vault
+        .search(args.query.as_deref().unwrap_or(""))
+        .into_iter()
+        .filter(|s| match &args.tag {
+            Some(tag) => s.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            None => true,
+        })
+        .cloned()
+        .collect();
+
+    if secrets.is_empty() {
+        return Err("No secrets match the given filters.".into());
+    }
+    let count = secrets.len();
+    let plural = if count == 1 { "" } else { "s" };
+    let target = args.path.display();
+
+    if args.format == ExportFormat::Encrypted {
+        if args.path.exists() && !args.yes && !confirm(&format!("Overwrite {target}?"))? {
             eprintln!("Aborted.");
             return Ok(());
         }
+        let p1 = prompt_secret("Export passphrase: ")?;
+        if p1.is_empty() {
+            return Err("The export passphrase must not be empty.".into());
+        }
+        let p2 = prompt_secret("Confirm:           ")?;
+        if p1 != p2 {
+            return Err("Passphrases do not match.".into());
+        }
+        let params = vault.kdf_params().unwrap_or(KdfParams::DEFAULT);
+        export::write_encrypted(&args.path, &p1, params, &secrets)?;
+        eprintln!("Exported {count} secret{plural} to {target} (encrypted).");
+        return Ok(());
+    }
+
+    if args.format == ExportFormat::Csv && export::csv_loses_hidden_fields(&secrets) {
+        eprintln!("Warning: CSV cannot mark hidden fields; they will be written as plain text.");
+    }
+    if !args.yes {
+        let overwrite = if args.path.exists() {
+            " (overwriting it)"
+        } else {
+            ""
+        };
+        eprintln!("Anyone who can read the file will be able to read these passwords.");
+        if !confirm(&format!(
+            "Write {count} secret{plural} UNENCRYPTED to {target}{overwrite}?"
+        ))? {
+            eprintln!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let contents = match args.format {
+        ExportFormat::Csv => export::to_csv(&secrets)?,
+        _ => export::to_json(&secrets)?,
+    };
+    export::write_plaintext(&args.path, &contents)?;
+    eprintln!("Exported {count} secret{plural} to {target}.");
+    Ok(())
+}
+
+fn cmd_audit(ctx: &Context, max_age: i64, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let vault = open_vault(ctx)?;
+    let findings = vault.audit(max_age);
+    let name = |id| vault.get(id).map(|s| s.name.as_str()).unwrap_or("?");
+
+    if json {
+        let report: Vec<_> = findings
+            .iter()
+            .map(|f| {
+                serde_json::json!({
+                    "issue": f.issue,
+                    "detail": f.detail,
+                    "entries": f.ids.iter().map(|id| serde_json::json!({
+                        "id": id,
+                        "name": name(*id),
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let mut current: Option<Issue> = None;
+        for f in &findings {
+            if current != Some(f.issue) {
+                let count = findings.iter().filter(|g| g.issue == f.issue).count();
+                println!("{} ({count})", f.issue.title());
+                current = Some(f.issue);
+            }
+            let names: Vec<_> = f.ids.iter().map(|id| name(*id)).collect();
+            println!("  {:<40}  {}", names.join(", "), f.detail);
+        }
+    }
+
+    if findings.is_empty() {
+        eprintln!("No issues found in {} secrets.", vault.list().len());
+    } else {
+        eprintln!(
+            "{} issue{} found in {} secrets.",
+            findings.len(),
+            if findings.len() == 1 { "" } else { "s" },
+            vault.list().len()
+        );
+    }
+    Ok(())
+}
+
+fn cmd_breach(ctx: &Context, hibp_file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut pwned = PwnedFile::open(hibp_file)?;
+    let mut vault = open_vault(ctx)?;
+    let compromised = vault.check_breaches(&mut pwned)?;
+
+    for id in &compromised {
+        let Some(s) = vault.get(*id) else { continue };
+        let username = s.username.as_deref().unwrap_or("");
+        let count = s.breach_count.unwrap_or(0);
+        println!("! {:<30}  {:<24}  seen {count} times", s.name, username);
+    }
+
+    let total = vault.list().len();
+    if compromised.is_empty() {
+        eprintln!("None of {total} passwords appear in the breach list.");
+    } else {
+        eprintln!(
+            "{} of {total} passwords appear in the breach list. Change them.",
+            compromised.len()
+        );
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on stderr; anything but `y` means no.
+fn confirm(question: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    eprint!("{question} [y/N] ");
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().eq_ignore_ascii_case("y"))
+}
+
+fn cmd_mv(ctx: &Context, name: &str, group_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
+    let mut secret = vault
+        .search(name)
+        .first()
+        .map(|s| (*s).clone())
+        .ok_or_else(|| format!("No secret matching '{name}'."))?;
+
+    let id = secret.id;
+    secret.group = group::normalize(group_path);
+    let message = match &secret.group {
+        Some(group) => format!("Secret '{}' moved to {group}.", secret.name),
+        None => format!("Secret '{}' moved to the top level.", secret.name),
+    };
+    let based_on = secret.updated_at;
+    vault.update(id, secret, based_on)?;
+    eprintln!("{message}");
+    Ok(())
+}
+
+fn cmd_rm(ctx: &Context, name: &str, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
+    let results = vault.search(name);
+
+    let secret = results
+        .first()
+        .ok_or_else(|| format!("No secret matching '{name}'."))?;
+
+    let id = secret.id;
+    let secret_name = secret.name.clone();
+
+    if !yes && !confirm(&format!("Move '{secret_name}' to the trash?"))? {
+        eprintln!("Aborted.");
+        return Ok(());
     }
 
     vault.delete(id)?;
-    eprintln!("Secret '{secret_name}' deleted.");
+    eprintln!("Secret '{secret_name}' moved to the trash (see `valt trash`).");
+    Ok(())
+}
+
+fn cmd_trash_list(ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
+    let vault = open_vault(ctx)?;
+    let trash = vault.trash();
+    if trash.is_empty() {
+        eprintln!("The trash is empty.");
+        return Ok(());
+    }
+    for s in &trash {
+        let deleted = s
+            .deleted_at
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let purge = vault
+            .purge_due(s.id)
+            .map(|d| format!("  purged after {}", d.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        let user = s
+            .username
+            .as_deref()
+            .map(|u| format!(" ({u})"))
+            .unwrap_or_default();
+        println!("{deleted}  {}{user}{purge}", s.name);
+    }
+    eprintln!("{} secret(s) in the trash.", trash.len());
+    Ok(())
+}
+
+fn cmd_trash_restore(ctx: &Context, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
+    let (id, secret_name) = vault
+        .search_trash(name)
+        .first()
+        .map(|s| (s.id, s.name.clone()))
+        .ok_or_else(|| format!("No secret matching '{name}' in the trash."))?;
+
+    vault.restore(id)?;
+    eprintln!("Secret '{secret_name}' restored.");
+    Ok(())
+}
+
+fn cmd_trash_empty(ctx: &Context, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
+    let count = vault.trash().len();
+    if count == 0 {
+        eprintln!("The trash is empty.");
+        return Ok(());
+    }
+    if !yes && !confirm(&format!("Purge {count} secret(s) for good?"))? {
+        eprintln!("Aborted.");
+        return Ok(());
+    }
+
+    let purged = vault.empty_trash()?;
+    eprintln!("{purged} secret(s) purged.");
     Ok(())
 }
+
+fn cmd_merge(
+    ctx: &Context,
+    other: &Path,
+    base: Option<&Path>,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_vault_exists(ctx.vault_path)?;
+    for path in std::iter::once(other).chain(base) {
+        if !path.exists() {
+            return Err(format!("No vault at {}.", path.display()).into());
+        }
+    }
+    let password = prompt_vault_password()?;
+    let mut vault = unlock_vault(ctx, &password)?;
+
+    let theirs = open_copy(other, &password)?;
+    let base = base.map(|base| open_copy(base, &password)).transpose()?;
+
+    let report = if dry_run {
+        vault.preview_merge(&theirs, base.as_ref())
+    } else {
+        vault.merge(&theirs, base.as_ref())?
+    };
+
+    print_merge_report(&report);
+    if report.is_empty() {
+        eprintln!(
+            "Nothing to merge: the vault already has every change from {}.",
+            other.display()
+        );
+        return Ok(());
+    }
+    if dry_run {
+        eprintln!("Dry run: the vault was not changed.");
+    }
+    Ok(())
+}
+
+/// Open another copy of the vault. It usually shares the master password;
+/// ask for its own only if not.
+fn open_copy(path: &Path, password: &str) -> Result<VaultManager, Box<dyn std::error::Error>> {
+    match VaultManager::open(open_vault_file(path, password)) {
+        Ok(copy) => Ok(copy),
+        Err(_) => {
+            let password = prompt_secret(&format!("Password for {}: ", path.display()))?;
+            Ok(VaultManager::open(open_vault_file(path, &password))
+                .map_err(|_| "Wrong password or corrupted vault.")?)
+        }
+    }
+}
+
+/// One line per secret `merge` touched, then the totals.
+fn print_merge_report(report: &MergeReport) {
+    for name in &report.added {
+        println!("+ {name}");
+    }
+    for name in &report.updated {
+        println!("~ {name}");
+    }
+    for name in &report.deleted {
+        println!("- {name}");
+    }
+    for conflict in &report.conflicts {
+        println!("! {}: {}", conflict.name, conflict.kind.describe());
+    }
+    if !report.is_empty() {
+        let conflicts = report.conflicts.len();
+        eprintln!(
+            "{} added, {} updated, {} deleted, {conflicts} conflict{}.",
+            report.added.len(),
+            report.updated.len(),
+            report.deleted.len(),
+            if conflicts == 1 { "" } else { "s" },
+        );
+    }
+}
+
+fn cmd_sync(ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
+    let mut vault = open_vault(ctx)?;
+    let remote = &ctx.config.sync.remote;
+    match vault.sync(remote)? {
+        Pulled::Nothing => eprintln!("Nothing new on {remote}."),
+        Pulled::Changes => eprintln!("Pulled changes from {remote}."),
+        Pulled::Merged(report) => {
+            print_merge_report(&report);
+            eprintln!("Merged changes made on both sides.");
+        }
+    }
+    eprintln!("Vault pushed to {remote}.");
+    Ok(())
+}
+
+fn cmd_sync_init(ctx: &Context, remote: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_vault_exists(ctx.vault_path)?;
+    let _lock = VaultLock::acquire(ctx.vault_path)?;
+    let repo = Repo::init(ctx.vault_path)?;
+    if let Some(url) = remote {
+        repo.add_remote(&ctx.config.sync.remote, url)?;
+    }
+    eprintln!(
+        "Vault versioned with git in {}. Run `valt sync` to push it.",
+        ctx.vault_path.parent().unwrap_or(Path::new(".")).display()
+    );
+    Ok(())
+}
+
+fn cmd_backup_list(ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
+    let generations = backup::list(ctx.vault_path)?;
+    if generations.is_empty() {
+        eprintln!("No backups yet. One is taken before every change to the vault.");
+        return Ok(());
+    }
+
+    // Each backup is decrypted to count its entries. Backups made before a
+    // master password change cannot be opened with the current one.
+    let password = prompt_vault_password()?;
+    for generation in &generations {
+        let contents = match VaultManager::open(open_vault_file(&generation.path, &password)) {
+            Ok(vault) => match vault.list().len() {
+                1 => "1 entry".to_string(),
+                n => format!("{n} entries"),
+            },
+            Err(_) => "cannot unlock with this password".to_string(),
+        };
+        println!(
+            "{}  {}  {}",
+            generation.id,
+            generation.created_at.format("%Y-%m-%d %H:%M"),
+            contents
+        );
+    }
+    eprintln!(
+        "{} backup(s) in {}",
+        generations.len(),
+        backup::dir(ctx.vault_path).display()
+    );
+    Ok(())
+}
+
+fn cmd_backup_restore(
+    ctx: &Context,
+    id: &str,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let generation = backup::list(ctx.vault_path)?
+        .into_iter()
+        .find(|g| g.id == id)
+        .ok_or_else(|| format!("No backup '{id}'. See `valt backup list`."))?;
+
+    let prompt = format!(
+        "Replace the vault with the backup from {}?",
+        generation.created_at.format("%Y-%m-%d %H:%M")
+    );
+    if !yes && !confirm(&prompt)? {
+        eprintln!("Aborted.");
+        return Ok(());
+    }
+
+    let replaced = backup::restore(ctx.vault_path, id, &ctx.config.backup.retention())?;
+    eprintln!("Backup {id} restored.");
+    if let Some(replaced) = replaced {
+        eprintln!("The previous vault was saved as backup {}.", replaced.id);
+    }
+    Ok(())
+}
+
+/// Works on the config file alone, so it runs before any vault is selected.
+pub fn cmd_config(command: ConfigCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Config::path();
+    let mut config = Config::load_from(&path)?;
+
+    match command {
+        ConfigCommand::Get { key } => match config.get(&key)? {
+            Some(value) => println!("{value}"),
+            None => eprintln!("'{key}' is not set."),
+        },
+        ConfigCommand::Set { key, value } => {
+            config.set(&key, &value)?;
+            config.save_to(&path)?;
+            eprintln!("Saved to {}.", path.display());
+        }
+        ConfigCommand::List => {
+            for (key, value) in config.list() {
+                match value {
+                    Some(value) => println!("{key} = {value}"),
+                    None => println!("{key} (unset)"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_field() {
+        let field = parse_field("Recovery code=1234-5678").unwrap();
+        assert_eq!(field.label, "Recovery code");
+        assert_eq!(field.value.as_str(), "1234-5678");
+        assert_eq!(field.kind, FieldKind::Text);
+
+        let field = parse_field("hidden:PIN=0000").unwrap();
+        assert_eq!(
+            (field.label.as_str(), field.kind),
+            ("PIN", FieldKind::Hidden)
+        );
+        // Only the first '=' separates the value, which may contain more.
+        let field = parse_field("url:Portal=https://example.com/?a=b").unwrap();
+        assert_eq!(field.value.as_str(), "https://example.com/?a=b");
+        // An unknown prefix is part of the label.
+        let field = parse_field("Q: pet's name=Rex").unwrap();
+        assert_eq!(
+            (field.label.as_str(), field.kind),
+            ("Q: pet's name", FieldKind::Text)
+        );
+
+        assert!(parse_field("no value").is_err());
+        assert!(parse_field("text: =x").is_err());
+        assert!(parse_field("email:Contact=nobody").is_err());
+        assert!(parse_field("date:Expires=31/12/2026").is_err());
+    }
+}
@@ -0,0 +1,758 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::core::{
+    backup::Retention, error::CoreError, generate, manager::DEFAULT_TRASH_RETENTION_DAYS,
+    GeneratorConfig, GeneratorMode, PassphraseConfig,
+};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Cannot read {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+
+    #[error("Cannot write {0}: {1}")]
+    Write(PathBuf, std::io::Error),
+
+    #[error("Invalid config file {0}: {1}")]
+    Parse(PathBuf, String),
+
+    #[error("Unknown config key '{0}' (see `valt config list`)")]
+    UnknownKey(String),
+
+    #[error("Invalid value for '{key}': {message}")]
+    Invalid { key: String, message: String },
+
+    #[error("Unknown profile '{0}' (known profiles: {1})")]
+    UnknownProfile(String, String),
+}
+
+/// User configuration, read from `$XDG_CONFIG_HOME/valt/config.toml`.
+/// Every key is optional; a missing file is the default configuration.
+///
+/// ```toml
+/// vault = "~/vaults/main.svlt"
+/// default_profile = "personal"
+/// clipboard_timeout = 30
+///
+/// [backup]
+/// keep = 20
+///
+/// [trash]
+/// retention_days = 90
+///
+/// [sync]
+/// remote = "origin"
+///
+/// [generator]
+/// length = 24
+/// symbols = false
+///
+/// [profiles]
+/// personal = "~/.local/share/valt/vault.svlt"
+/// team = "/mnt/shared/team.svlt"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Vault opened when nothing more specific is given. Defaults to
+    /// `vault.svlt` in `data_dir()`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault: Option<PathBuf>,
+    /// Profile opened when neither `--vault`, `--profile` nor `VALT_VAULT`
+    /// is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    /// Seconds before a copied secret is wiped from the clipboard; 0 never wipes.
+    pub clipboard_timeout: u64,
+    pub backup: BackupSettings,
+    pub trash: TrashSettings,
+    pub sync: SyncSettings,
+    /// Defaults for `valt add -g` and the TUI generator popup.
+    pub generator: GeneratorSettings,
+    /// Named vault files, selected with `--profile <name>`.
+    pub profiles: BTreeMap<String, PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            vault: None,
+            default_profile: None,
+            clipboard_timeout: 30,
+            backup: BackupSettings::default(),
+            trash: TrashSettings::default(),
+            sync: SyncSettings::default(),
+            generator: GeneratorSettings::default(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+/// Snapshots of the vault taken before every save; see `core::backup`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// Most recent snapshots kept, whatever their age.
+    pub keep: usize,
+    /// Days, then ISO weeks, for which the newest snapshot is also kept.
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        let d = Retention::default();
+        Self {
+            enabled: true,
+            keep: d.keep,
+            daily: d.daily,
+            weekly: d.weekly,
+        }
+    }
+}
+
+impl BackupSettings {
+    pub fn retention(&self) -> Retention {
+        Retention {
+            keep: self.keep,
+            daily: self.daily,
+            weekly: self.weekly,
+        }
+    }
+
+    /// What `VaultManager::with_backups` expects: `None` when disabled.
+    pub fn on_save(&self) -> Option<Retention> {
+        self.enabled.then(|| self.retention())
+    }
+}
+
+/// Deleted secrets are kept in the trash this long before being purged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrashSettings {
+    /// 0 keeps them until the trash is emptied by hand.
+    pub retention_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_TRASH_RETENTION_DAYS as u32,
+        }
+    }
+}
+
+impl TrashSettings {
+    /// What `VaultManager::with_trash_retention` expects.
+    pub fn retention(&self) -> Option<TimeDelta> {
+        (self.retention_days > 0).then(|| TimeDelta::days(self.retention_days.into()))
+    }
+}
+
+/// Git versioning of the vault directory; see `core::sync`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncSettings {
+    /// Commit the vault after every save when its directory is a git
+    /// repository.
+    pub auto_commit: bool,
+    /// Remote that `valt sync` pulls from and pushes to.
+    pub remote: String,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            auto_commit: true,
+            remote: "origin".to_string(),
+        }
+    }
+}
+
+/// The subset of `GeneratorConfig` worth setting once and for all.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneratorSettings {
+    pub mode: GeneratorMode,
+    pub length: usize,
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub min_uppercase: usize,
+    pub min_lowercase: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    pub exclude_ambiguous: bool,
+    pub no_repeat: bool,
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub digit: bool,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        let d = GeneratorConfig::default();
+        Self {
+            mode: d.mode,
+            length: d.length,
+            uppercase: d.uppercase,
+            lowercase: d.lowercase,
+            digits: d.digits,
+            symbols: d.symbols,
+            min_uppercase: d.min_uppercase,
+            min_lowercase: d.min_lowercase,
+            min_digits: d.min_digits,
+            min_symbols: d.min_symbols,
+            exclude_ambiguous: d.exclude_ambiguous,
+            no_repeat: d.no_repeat,
+            words: d.passphrase.words,
+            separator: d.passphrase.separator,
+            capitalize: d.passphrase.capitalize,
+            digit: d.passphrase.digit,
+        }
+    }
+}
+
+impl GeneratorSettings {
+    pub fn to_generator_config(&self) -> GeneratorConfig {
+        GeneratorConfig {
+            mode: self.mode,
+            length: self.length,
+            uppercase: self.uppercase,
+            lowercase: self.lowercase,
+            digits: self.digits,
+            symbols: self.symbols,
+            min_uppercase: self.min_uppercase,
+            min_lowercase: self.min_lowercase,
+            min_digits: self.min_digits,
+            min_symbols: self.min_symbols,
+            exclude_ambiguous: self.exclude_ambiguous,
+            extra_chars: String::new(),
+            excluded_chars: String::new(),
+            no_repeat: self.no_repeat,
+            passphrase: PassphraseConfig {
+                words: self.words,
+                separator: self.separator.clone(),
+                capitalize: self.capitalize,
+                digit: self.digit,
+            },
+        }
+    }
+}
+
+/// How `Config::set` parses a value given on the command line.
+#[derive(Clone, Copy)]
+enum Kind {
+    Bool,
+    Integer,
+    Text,
+    /// Text that may be unset by giving an empty value.
+    Optional,
+}
+
+/// Every settable key, in the order `valt config list` prints them.
+/// `profiles.<name>` keys come on top of these.
+const KEYS: &[(&str, Kind)] = &[
+    ("vault", Kind::Optional),
+    ("default_profile", Kind::Optional),
+    ("clipboard_timeout", Kind::Integer),
+    ("backup.enabled", Kind::Bool),
+    ("backup.keep", Kind::Integer),
+    ("backup.daily", Kind::Integer),
+    ("backup.weekly", Kind::Integer),
+    ("trash.retention_days", Kind::Integer),
+    ("sync.auto_commit", Kind::Bool),
+    ("sync.remote", Kind::Text),
+    ("generator.mode", Kind::Text),
+    ("generator.length", Kind::Integer),
+    ("generator.uppercase", Kind::Bool),
+    ("generator.lowercase", Kind::Bool),
+    ("generator.digits", Kind::Bool),
+    ("generator.symbols", Kind::Bool),
+    ("generator.min_uppercase", Kind::Integer),
+    ("generator.min_lowercase", Kind::Integer),
+    ("generator.min_digits", Kind::Integer),
+    ("generator.min_symbols", Kind::Integer),
+    ("generator.exclude_ambiguous", Kind::Bool),
+    ("generator.no_repeat", Kind::Bool),
+    ("generator.words", Kind::Integer),
+    ("generator.separator", Kind::Text),
+    ("generator.capitalize", Kind::Bool),
+    ("generator.digit", Kind::Bool),
+];
+
+fn key_kind(key: &str) -> Option<Kind> {
+    KEYS.iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, kind)| *kind)
+        .or_else(|| {
+            key.strip_prefix("profiles.")
+                .filter(|name| !name.is_empty())
+                .map(|_| Kind::Optional)
+        })
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/valt/config.toml`, or `~/.config/valt/config.toml`.
+    pub fn path() -> PathBuf {
+        xdg_dir("XDG_CONFIG_HOME", ".config").join("config.toml")
+    }
+
+    /// `$XDG_DATA_HOME/valt`, or `~/.local/share/valt`.
+    pub fn data_dir() -> PathBuf {
+        xdg_dir("XDG_DATA_HOME", ".local/share")
+    }
+
+    /// Load and validate the config file; a missing file is the default config.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(&Self::path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ConfigError::Read(path.to_path_buf(), e)),
+        };
+        let config: Self = toml::from_str(&text)
+            .map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Write the config to `path`, creating its directory. Comments in an
+    /// existing file are not preserved.
+    ///
+    /// The config is written next to `path` and renamed over it, so that an
+    /// interrupted save leaves the previous file intact.
+    pub fn save_to(&self, path: &Path) -> Result<(), ConfigError> {
+        let write_err = |e| ConfigError::Write(path.to_path_buf(), e);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(write_err)?;
+        }
+        let text = toml::to_string_pretty(self).expect("config serializes to TOML");
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, text)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&tmp);
+                write_err(e)
+            })
+    }
+
+    /// Check what the types alone cannot express.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, message: String| ConfigError::Invalid {
+            key: key.to_string(),
+            message,
+        };
+        if let Some(name) = &self.default_profile {
+            if !self.profiles.contains_key(name) {
+                return Err(invalid(
+                    "default_profile",
+                    format!("no profile named '{name}'"),
+                ));
+            }
+        }
+        if self.backup.keep == 0 {
+            return Err(invalid(
+                "backup.keep",
+                "must be at least 1; set backup.enabled = false to take no backups".to_string(),
+            ));
+        }
+        if self.sync.remote.trim().is_empty() {
+            return Err(invalid("sync.remote", "must name a git remote".to_string()));
+        }
+        // Both modes are checked: the TUI popup can switch to the other one.
+        let mut generator = self.generator.to_generator_config();
+        for mode in [GeneratorMode::Characters, GeneratorMode::Passphrase] {
+            generator.mode = mode;
+            if let Err(e) = generate(&generator) {
+                let key = match e {
+                    CoreError::InvalidLength => "generator.length",
+                    CoreError::InvalidWordCount => "generator.words",
+                    _ => "generator",
+                };
+                return Err(invalid(key, e.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of `key` as text, or `None` if it is unset.
+    pub fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        key_kind(key).ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+        let table = self.to_table();
+        let value = match key.split_once('.') {
+            Some((section, leaf)) => table
+                .get(section)
+                .and_then(toml::Value::as_table)
+                .and_then(|t| t.get(leaf)),
+            None => table.get(key),
+        };
+        Ok(value.map(|v| match v {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }))
+    }
+
+    /// Set `key` from its textual `value`. An empty value unsets optional
+    /// keys and removes `profiles.<name>`. The result is validated as a whole,
+    /// and `self` is left untouched on error.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let kind = key_kind(key).ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+        let invalid = |message: String| ConfigError::Invalid {
+            key: key.to_string(),
+            message,
+        };
+        let value = match kind {
+            Kind::Bool => {
+                Some(toml::Value::Boolean(value.parse().map_err(|_| {
+                    invalid(format!("expected true or false, got '{value}'"))
+                })?))
+            }
+            Kind::Integer => {
+                Some(toml::Value::Integer(value.parse::<u32>().map_err(|_| {
+                    invalid(format!("expected a non-negative number, got '{value}'"))
+                })? as i64))
+            }
+            Kind::Text => Some(toml::Value::String(value.to_string())),
+            Kind::Optional => (!value.is_empty()).then(|| toml::Value::String(value.to_string())),
+        };
+
+        let mut table = self.to_table();
+        let (target, leaf) = match key.split_once('.') {
+            Some((section, leaf)) => {
+                let section = table
+                    .entry(section)
+                    .or_insert_with(|| toml::Table::new().into());
+                (
+                    section.as_table_mut().expect("config sections are tables"),
+                    leaf,
+                )
+            }
+            None => (&mut table, key),
+        };
+        match value {
+            Some(value) => target.insert(leaf.to_string(), value),
+            None => target.remove(leaf),
+        };
+
+        let config: Config = table
+            .try_into()
+            .map_err(|e: toml::de::Error| invalid(e.message().to_string()))?;
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    /// Every known key with its value (`None` when unset), profiles last.
+    pub fn list(&self) -> Vec<(String, Option<String>)> {
+        let profiles = self.profiles.keys().map(|name| format!("profiles.{name}"));
+        KEYS.iter()
+            .map(|(key, _)| key.to_string())
+            .chain(profiles)
+            .map(|key| {
+                let value = self.get(&key).unwrap_or(None);
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// Vault of `vault`, or `vault.svlt` in `data_dir()`.
+    ///
+    /// Earlier versions ignored `XDG_DATA_HOME` and always kept the
+    /// vault in `~/.local/share/valt`: if only that one exists, it is used
+    /// rather than starting an empty vault.
+    pub fn default_vault(&self) -> PathBuf {
+        match &self.vault {
+            Some(path) => expand_tilde(path),
+            None => existing_or(
+                Self::data_dir().join("vault.svlt"),
+                home().join(".local/share/valt/vault.svlt"),
+            ),
+        }
+    }
+
+    pub fn clipboard_timeout(&self) -> Option<Duration> {
+        (self.clipboard_timeout > 0).then(|| Duration::from_secs(self.clipboard_timeout))
+    }
+
+    /// Vault file of profile `name`, with a leading `~` expanded.
+    pub fn profile(&self, name: &str) -> Result<PathBuf, ConfigError> {
+        self.profiles
+            .get(name)
+            .map(|p| expand_tilde(p))
+            .ok_or_else(|| {
+                let known: Vec<_> = self.profiles.keys().map(String::as_str).collect();
+                let known = if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                };
+                ConfigError::UnknownProfile(name.to_string(), known)
+            })
+    }
+
+    /// Every vault the user told us about, as `(label, path)`: the default
+    /// location first, then the profiles in name order.
+    pub fn known_vaults(&self, default: PathBuf) -> Vec<(String, PathBuf)> {
+        let mut vaults = vec![("default".to_string(), default)];
+        for (name, path) in &self.profiles {
+            let path = expand_tilde(path);
+            if !vaults.iter().any(|(_, p)| *p == path) {
+                vaults.push((name.clone(), path));
+            }
+        }
+        vaults
+    }
+
+    fn to_table(&self) -> toml::Table {
+        toml::Table::try_from(self).expect("config serializes to TOML")
+    }
+}
+
+/// Which vault to open, from the most to the least specific source:
+/// `--vault`, `--profile`, `VALT_VAULT`, `default_profile`, then `default`.
+pub fn resolve_vault(
+    config: &Config,
+    vault: Option<PathBuf>,
+    profile: Option<&str>,
+    env: Option<PathBuf>,
+    default: PathBuf,
+) -> Result<PathBuf, ConfigError> {
+    if let Some(path) = vault {
+        return Ok(path);
+    }
+    if let Some(name) = profile {
+        return config.profile(name);
+    }
+    if let Some(path) = env {
+        return Ok(path);
+    }
+    match &config.default_profile {
+        Some(name) => config.profile(name),
+        None => Ok(default),
+    }
+}
+
+fn home() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+}
+
+/// `$<var>/valt`, or `~/<fallback>/valt` when the variable is unset or
+/// empty, as the XDG base directory spec asks.
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home().join(fallback))
+        .join("valt")
+}
+
+/// `path`, unless only `legacy` exists.
+fn existing_or(path: PathBuf, legacy: PathBuf) -> PathBuf {
+    if !path.exists() && legacy.exists() {
+        legacy
+    } else {
+        path
+    }
+}
+
+fn expand_tilde(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample() -> Config {
+        toml::from_str(
+            r#"
+            [profiles]
+            team = "/mnt/shared/team.svlt"
+            personal = "~/vaults/me.svlt"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn invalid_key(err: ConfigError) -> String {
+        match err {
+            ConfigError::Invalid { key, .. } => key,
+            other => panic!("expected Invalid, got {other}"),
+        }
+    }
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let dir = tempdir().unwrap();
+        let config = Config::load_from(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.clipboard_timeout(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_invalid_file_names_key() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        std::fs::write(&path, "[generator]\nlenght = 12\n").unwrap();
+        let err = Config::load_from(&path).unwrap_err().to_string();
+        assert!(
+            err.contains("config.toml") && err.contains("lenght"),
+            "{err}"
+        );
+
+        std::fs::write(&path, "[generator]\nlength = 0\n").unwrap();
+        let err = Config::load_from(&path).unwrap_err();
+        assert_eq!(invalid_key(err), "generator.length");
+    }
+
+    #[test]
+    fn test_set_get_and_save() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("valt").join("config.toml");
+        let mut config = Config::default();
+
+        config.set("generator.length", "32").unwrap();
+        config.set("generator.mode", "passphrase").unwrap();
+        config.set("backup.enabled", "false").unwrap();
+        config.set("profiles.team", "/mnt/team.svlt").unwrap();
+        config.set("default_profile", "team").unwrap();
+        assert_eq!(
+            config.get("generator.length").unwrap().as_deref(),
+            Some("32")
+        );
+        assert_eq!(config.get("vault").unwrap(), None);
+
+        config.save_to(&path).unwrap();
+        assert!(!path.with_extension("toml.tmp").exists());
+        let reloaded = Config::load_from(&path).unwrap();
+        assert_eq!(reloaded, config);
+        assert_eq!(reloaded.generator.mode, GeneratorMode::Passphrase);
+        assert!(!reloaded.backup.enabled);
+
+        // Empty values unset optional keys.
+        config.set("default_profile", "").unwrap();
+        config.set("profiles.team", "").unwrap();
+        assert!(config.profiles.is_empty());
+        assert!(config
+            .list()
+            .iter()
+            .all(|(k, _)| !k.starts_with("profiles.")));
+    }
+
+    #[test]
+    fn test_class_minimums_are_set_separately() {
+        let mut config = Config::default();
+        config.set("generator.min_digits", "3").unwrap();
+        config.set("generator.min_symbols", "0").unwrap();
+        let generator = config.generator.to_generator_config();
+        assert_eq!(
+            (
+                generator.min_uppercase,
+                generator.min_lowercase,
+                generator.min_digits,
+                generator.min_symbols
+            ),
+            (1, 1, 3, 0)
+        );
+
+        let err = config.set("generator.min_uppercase", "30").unwrap_err();
+        assert_eq!(invalid_key(err), "generator");
+        assert_eq!(config.generator.min_uppercase, 1);
+    }
+
+    #[test]
+    fn test_set_rejects_bad_values() {
+        let mut config = Config::default();
+        let before = config.clone();
+
+        assert!(matches!(
+            config.set("generator.lenght", "3"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        for (key, value) in [
+            ("generator.length", "abc"),
+            ("backup.enabled", "yes"),
+            ("generator.mode", "emoji"),
+            ("generator.words", "0"),
+            ("backup.keep", "0"),
+            ("sync.remote", ""),
+            ("default_profile", "nope"),
+        ] {
+            let err = config.set(key, value).unwrap_err();
+            assert_eq!(invalid_key(err), key, "{key} = {value}");
+        }
+        assert_eq!(config, before);
+    }
+
+    #[test]
+    fn test_resolution_order() {
+        let config = sample();
+        let default = PathBuf::from("/default.svlt");
+        let flag = PathBuf::from("/flag.svlt");
+        let env = PathBuf::from("/env.svlt");
+
+        let resolve =
+            |vault, profile, env| resolve_vault(&config, vault, profile, env, default.clone());
+        assert_eq!(
+            resolve(Some(flag.clone()), Some("team"), Some(env.clone())).unwrap(),
+            flag
+        );
+        assert_eq!(
+            resolve(None, Some("team"), Some(env.clone())).unwrap(),
+            PathBuf::from("/mnt/shared/team.svlt")
+        );
+        assert_eq!(resolve(None, None, Some(env.clone())).unwrap(), env);
+        assert_eq!(resolve(None, None, None).unwrap(), default);
+        assert!(resolve(None, Some("nope"), None)
+            .unwrap_err()
+            .to_string()
+            .contains("personal, team"));
+    }
+
+    #[test]
+    fn test_tilde_and_known_vaults() {
+        let config = sample();
+        let personal = config.profile("personal").unwrap();
+        assert!(!personal.starts_with("~"));
+        assert!(personal.ends_with("vaults/me.svlt"));
+
+        let known = config.known_vaults(PathBuf::from("/default.svlt"));
+        let names: Vec<_> = known.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["default", "personal", "team"]);
+    }
+
+    #[test]
+    fn test_legacy_vault_location() {
+        let dir = tempdir().unwrap();
+        let xdg = dir.path().join("xdg/valt/vault.svlt");
+        let legacy = dir.path().join("home/.local/share/valt/vault.svlt");
+        assert_eq!(existing_or(xdg.clone(), legacy.clone()), xdg);
+
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(&legacy, "old").unwrap();
+        assert_eq!(existing_or(xdg.clone(), legacy.clone()), legacy);
+
+        // Once a vault exists at the new location, it wins.
+        std::fs::create_dir_all(xdg.parent().unwrap()).unwrap();
+        std::fs::write(&xdg, "new").unwrap();
+        assert_eq!(existing_or(xdg.clone(), legacy), xdg);
+    }
+}
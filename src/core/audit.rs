@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{entry::EntryKind, secret::Secret, strength};
+
+/// Default age, in days, after which a password is reported as old.
+pub const DEFAULT_MAX_AGE_DAYS: i64 = 365;
+
+/// Passwords scoring below this (see `strength::estimate`) are reported weak.
+const WEAK_BELOW_SCORE: u8 = 2;
+
+/// What is wrong with one or more entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Issue {
+    /// Several entries share the same password.
+    Reused,
+    Weak,
+    /// Not changed for longer than the configured age.
+    Old,
+    /// Several entries have the same name.
+    DuplicateName,
+    MissingUsername,
+    MissingUrl,
+}
+
+impl Issue {
+    pub fn title(self) -> &'static str {
+        match self {
+            Issue::Reused => "Reused password",
+            Issue::Weak => "Weak password",
+            Issue::Old => "Old password",
+            Issue::DuplicateName => "Duplicate name",
+            Issue::MissingUsername => "Missing username",
+            Issue::MissingUrl => "Missing URL",
+        }
+    }
+}
+
+/// One problem found by `audit`. Grouped issues (`Reused`, `DuplicateName`)
+/// list every entry of the group; the others list a single entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub issue: Issue,
+    pub ids: Vec<Uuid>,
+    /// Human-readable specifics, e.g. `very weak · 18 bits`.
+    pub detail: String,
+}
+
+/// Audit `secrets` as of `now`, most serious issues first. Entries without a
+/// password (cards, notes…) are not checked for reuse, strength or age, and
+/// only logins must have a username and URL.
+pub fn audit(secrets: &[Secret], max_age_days: i64, now: DateTime<Utc>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for group in groups(secrets, |s| {
+        (!s.password.is_empty()).then(|| s.password.to_string())
+    }) {
+        findings.push(Finding {
+            issue: Issue::Reused,
+            detail: format!("shared by {} entries", group.len()),
+            ids: group,
+        });
+    }
+
+    for s in secrets.iter().filter(|s| !s.password.is_empty()) {
+        let strength = strength::estimate(&s.password);
+        if strength.score < WEAK_BELOW_SCORE {
+            findings.push(Finding {
+                issue: Issue::Weak,
+                ids: vec![s.id],
+                detail: format!("{} · {:.0} bits", strength.label(), strength.bits),
+            });
+        }
+    }
+
+    for s in secrets.iter().filter(|s| !s.password.is_empty()) {
+        let days = (now - s.updated_at).num_days();
+        if days > max_age_days {
+            findings.push(Finding {
+                issue: Issue::Old,
+                ids: vec![s.id],
+                detail: format!("last changed {days} days ago"),
+            });
+        }
+    }
+
+    for group in groups(secrets, |s| Some(s.name.trim().to_lowercase())) {
+        findings.push(Finding {
+            issue: Issue::DuplicateName,
+            detail: format!("{} entries with this name", group.len()),
+            ids: group,
+        });
+    }
+
+    let blank = |v: &Option<String>| v.as_deref().is_none_or(|v| v.trim().is_empty());
+    let logins: Vec<_> = secrets
+        .iter()
+        .filter(|s| s.kind == EntryKind::Login)
+        .collect();
+    for s in &logins {
+        if blank(&s.username) {
+            findings.push(Finding {
+                issue: Issue::MissingUsername,
+                ids: vec![s.id],
+                detail: String::new(),
+            });
+        }
+    }
+    for s in &logins {
+        if blank(&s.url) {
+            findings.push(Finding {
+                issue: Issue::MissingUrl,
+                ids: vec![s.id],
+                detail: String::new(),
+            });
+        }
+    }
+
+    findings
+}
+
+/// Groups of two or more entries sharing the same `key`, in vault order.
+fn groups(secrets: &[Secret], key: impl Fn(&Secret) -> Option<String>) -> Vec<Vec<Uuid>> {
+    let mut order: Vec<String> = Vec::new();
+    let mut by_key: HashMap<String, Vec<Uuid>> = HashMap::new();
+    for s in secrets {
+        if let Some(k) = key(s) {
+            let ids = by_key.entry(k.clone()).or_default();
+            if ids.is_empty() {
+                order.push(k);
+            }
+            ids.push(s.id);
+        }
+    }
+    order
+        .into_iter()
+        .filter_map(|k| by_key.remove(&k))
+        .filter(|ids| ids.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn login(name: &str, password: &str) -> Secret {
+        let mut s = Secret::new(name, password);
+        s.username = Some("jb".to_string());
+        s.url = Some(format!("https://{}.example", name.to_lowercase()));
+        s
+    }
+
+    fn of(findings: &[Finding], issue: Issue) -> Vec<&Finding> {
+        findings.iter().filter(|f| f.issue == issue).collect()
+    }
+
+    #[test]
+    fn test_clean_vault() {
+        let secrets = vec![
+            login("GitHub", "k#9Lw!q2Vz$8mR@4xT&e"),
+            login("GitLab", "Zp3!vR8#qW2@mK7$xN4&"),
+        ];
+        assert!(audit(&secrets, 365, Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_reused_grouped() {
+        let shared = "k#9Lw!q2Vz$8mR@4xT&e";
+        let secrets = vec![
+            login("A", shared),
+            login("B", "Zp3!vR8#qW2@mK7$xN4&"),
+            login("C", shared),
+            login("D", shared),
+        ];
+        let findings = audit(&secrets, 365, Utc::now());
+        let reused = of(&findings, Issue::Reused);
+        assert_eq!(reused.len(), 1);
+        assert_eq!(reused[0].ids, [secrets[0].id, secrets[2].id, secrets[3].id]);
+    }
+
+    #[test]
+    fn test_weak_old_and_missing() {
+        let mut old = login("Old", "k#9Lw!q2Vz$8mR@4xT&e");
+        old.updated_at = Utc::now() - Duration::days(400);
+        let mut bare = Secret::new("Bare", "Zp3!vR8#qW2@mK7$xN4&");
+        bare.url = Some("  ".to_string());
+        let mut card = Secret::new("Visa", "");
+        card.kind = EntryKind::Card;
+        card.updated_at = old.updated_at;
+        let secrets = vec![login("Weak", "password1"), old, bare, card];
+
+        let findings = audit(&secrets, 365, Utc::now());
+        assert_eq!(of(&findings, Issue::Weak)[0].ids, [secrets[0].id]);
+        assert_eq!(of(&findings, Issue::Old)[0].ids, [secrets[1].id]);
+        assert_eq!(
+            of(&findings, Issue::MissingUsername)[0].ids,
+            [secrets[2].id]
+        );
+        assert_eq!(of(&findings, Issue::MissingUrl)[0].ids, [secrets[2].id]);
+        // The card has no password, username or URL to speak of.
+        assert!(findings.iter().all(|f| !f.ids.contains(&secrets[3].id)));
+
+        // A longer allowed age silences the old entry.
+        assert!(of(&audit(&secrets, 500, Utc::now()), Issue::Old).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_names_case_insensitive() {
+        let secrets = vec![
+            login("GitHub", "k#9Lw!q2Vz$8mR@4xT&e"),
+            login("github ", "Zp3!vR8#qW2@mK7$xN4&"),
+        ];
+        let findings = audit(&secrets, 365, Utc::now());
+        assert_eq!(of(&findings, Issue::DuplicateName)[0].ids.len(), 2);
+    }
+
+    #[test]
+    fn test_serializes_snake_case() {
+        let secrets = vec![login("Weak", "123456")];
+        let json = serde_json::to_string(&audit(&secrets, 365, Utc::now())).unwrap();
+        assert!(json.contains("\"issue\":\"weak\""));
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeDelta, Utc};
+
+use super::error::CoreError;
+use super::lock::VaultLock;
+use super::manager::{suffixed_path, sync_parent_dir};
+
+/// Generation ids are their creation time, which also orders them.
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+const EXTENSION: &str = "svlt";
+
+/// How many generations `prune` keeps. A generation is kept if any rule
+/// selects it, so the default covers the last 10 saves, one per day over
+/// the last week and one per week over the last month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// The most recent generations, whatever their age.
+    pub keep: usize,
+    /// The newest generation of each of the last `daily` days that have one.
+    pub daily: usize,
+    /// The newest generation of each of the last `weekly` ISO weeks that have one.
+    pub weekly: usize,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            keep: 10,
+            daily: 7,
+            weekly: 4,
+        }
+    }
+}
+
+impl Retention {
+    /// Which of `times` (newest first) to keep. The newest is always kept.
+    fn kept(&self, times: &[DateTime<Utc>]) -> Vec<bool> {
+        let mut kept: Vec<bool> = (0..times.len()).map(|i| i < self.keep.max(1)).collect();
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+        for (i, t) in times.iter().enumerate() {
+            if days.len() < self.daily && days.insert(t.date_naive()) {
+                kept[i] = true;
+            }
+            let week = t.iso_week();
+            if weeks.len() < self.weekly && weeks.insert((week.year(), week.week())) {
+                kept[i] = true;
+            }
+        }
+        kept
+    }
+}
+
+/// One timestamped copy of the vault file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generation {
+    /// What `restore` takes, e.g. `20261017-143015-123`.
+    pub id: String,
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Generation {
+    fn at(dir: &Path, created_at: DateTime<Utc>) -> Self {
+        let id = created_at.format(ID_FORMAT).to_string();
+        Self {
+            path: dir.join(format!("{id}.{EXTENSION}")),
+            id,
+            created_at,
+        }
+    }
+}
+
+/// Where the generations of `vault_path` live: `<vault>.backups/`.
+pub fn dir(vault_path: &Path) -> PathBuf {
+    suffixed_path(vault_path, "backups")
+}
+
+/// Every generation of `vault_path`, newest first.
+pub fn list(vault_path: &Path) -> Result<Vec<Generation>, CoreError> {
+    let dir = dir(vault_path);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CoreError::Backup(e)),
+    };
+
+    let mut generations = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(CoreError::Backup)?.path();
+        if path.extension().is_none_or(|ext| ext != EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if let Ok(t) = NaiveDateTime::parse_from_str(id, ID_FORMAT) {
+            generations.push(Generation {
+                id: id.to_string(),
+                created_at: t.and_utc(),
+                path,
+            });
+        }
+    }
+    generations.sort_by_key(|g| std::cmp::Reverse(g.created_at));
+    Ok(generations)
+}
+
+/// Copy the vault file into a new generation, then `prune`. Does nothing if
+/// the vault file does not exist yet.
+pub fn snapshot(vault_path: &Path, retention: &Retention) -> Result<Option<Generation>, CoreError> {
+    if !vault_path.exists() {
+        return Ok(None);
+    }
+    let dir = dir(vault_path);
+    std::fs::create_dir_all(&dir).map_err(CoreError::Backup)?;
+    adopt_legacy_backup(vault_path, &dir)?;
+
+    // Ids must keep increasing, even for several saves within a millisecond.
+    let mut generation = Generation::at(&dir, Utc::now());
+    if let Some(newest) = list(vault_path)?.first() {
+        let next = newest.created_at + TimeDelta::milliseconds(1);
+        if generation.created_at < next {
+            generation = Generation::at(&dir, next);
+        }
+    }
+    std::fs::copy(vault_path, &generation.path).map_err(CoreError::Backup)?;
+
+    prune(vault_path, retention)?;
+    Ok(Some(generation))
+}
+
+/// Delete the generations `retention` does not keep. Returns the deleted ones.
+pub fn prune(vault_path: &Path, retention: &Retention) -> Result<Vec<Generation>, CoreError> {
+    let generations = list(vault_path)?;
+    let times: Vec<_> = generations.iter().map(|g| g.created_at).collect();
+    let mut removed = Vec::new();
+    for (generation, kept) in generations.into_iter().zip(retention.kept(&times)) {
+        if !kept {
+            std::fs::remove_file(&generation.path).map_err(CoreError::Backup)?;
+            removed.push(generation);
+        }
+    }
+    Ok(removed)
+}
+
+/// Delete every generation, e.g. after the master password changed.
+pub fn clear(vault_path: &Path) -> Result<(), CoreError> {
+    for generation in list(vault_path)? {
+        std::fs::remove_file(&generation.path).map_err(CoreError::Backup)?;
+    }
+    remove_if_exists(&suffixed_path(vault_path, "bak"))
+}
+
+/// Put generation `id` back in place of the vault file.
+///
+/// The generation is first copied next to the vault and synced; the current
+/// vault is snapshotted (so the restore itself can be undone), and the copy
+/// is renamed over the vault file. An interruption leaves either the old or
+/// the restored vault in place. Holds the vault lock throughout. Returns the
+/// snapshot of the replaced vault.
+pub fn restore(
+    vault_path: &Path,
+    id: &str,
+    retention: &Retention,
+) -> Result<Option<Generation>, CoreError> {
+    let _lock = VaultLock::acquire(vault_path)?;
+    let generation = list(vault_path)?
+        .into_iter()
+        .find(|g| g.id == id)
+        .ok_or_else(|| CoreError::BackupNotFound(id.to_string()))?;
+
+    let staged = suffixed_path(vault_path, "restore");
+    std::fs::copy(&generation.path, &staged).map_err(CoreError::Backup)?;
+    std::fs::File::open(&staged)
+        .and_then(|f| f.sync_all())
+        .map_err(CoreError::Backup)?;
+
+    let replaced = match snapshot(vault_path, retention) {
+        Ok(replaced) => replaced,
+        Err(e) => {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&staged, vault_path).map_err(CoreError::Backup)?;
+    sync_parent_dir(vault_path);
+    Ok(replaced)
+}
+
+/// Older versions kept a single `<vault>.bak`: turn it into a generation
+/// dated by its modification time.
+fn adopt_legacy_backup(vault_path: &Path, dir: &Path) -> Result<(), CoreError> {
+    let bak = suffixed_path(vault_path, "bak");
+    let Ok(modified) = std::fs::metadata(&bak).and_then(|m| m.modified()) else {
+        return Ok(());
+    };
+    let generation = Generation::at(dir, modified.into());
+    std::fs::rename(&bak, &generation.path).map_err(CoreError::Backup)
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), CoreError> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(CoreError::Backup(e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_retention_rules() {
+        // Newest first. 2026-03-09, 16, 23 and 30 are Mondays, so the 29th
+        // (a Sunday) shares its week with the 23rd.
+        let times = [
+            at(30, 18),
+            at(30, 9),
+            at(29, 12),
+            at(23, 8),
+            at(16, 8),
+            at(9, 8),
+        ];
+
+        let only_last = Retention {
+            keep: 1,
+            daily: 0,
+            weekly: 0,
+        };
+        assert_eq!(
+            only_last.kept(&times),
+            [true, false, false, false, false, false]
+        );
+
+        let daily = Retention {
+            keep: 1,
+            daily: 2,
+            weekly: 0,
+        };
+        assert_eq!(daily.kept(&times), [true, false, true, false, false, false]);
+
+        let weekly = Retention {
+            keep: 1,
+            daily: 0,
+            weekly: 3,
+        };
+        assert_eq!(weekly.kept(&times), [true, false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_snapshot_list_and_prune() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path().join("vault.svlt");
+        assert_eq!(snapshot(&vault, &Retention::default()).unwrap(), None);
+
+        let retention = Retention {
+            keep: 2,
+            daily: 0,
+            weekly: 0,
+        };
+        for n in 0..4 {
+            std::fs::write(&vault, format!("v{n}")).unwrap();
+            snapshot(&vault, &retention).unwrap().unwrap();
+        }
+        let generations = list(&vault).unwrap();
+        assert_eq!(generations.len(), 2);
+        assert_eq!(std::fs::read_to_string(&generations[0].path).unwrap(), "v3");
+        assert_eq!(std::fs::read_to_string(&generations[1].path).unwrap(), "v2");
+        assert!(generations[0].created_at > generations[1].created_at);
+    }
+
+    #[test]
+    fn test_restore_swaps_and_keeps_current() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path().join("vault.svlt");
+        let retention = Retention::default();
+        std::fs::write(&vault, "good").unwrap();
+        let good = snapshot(&vault, &retention).unwrap().unwrap();
+        std::fs::write(&vault, "bad").unwrap();
+
+        let replaced = restore(&vault, &good.id, &retention).unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&vault).unwrap(), "good");
+        assert_eq!(std::fs::read_to_string(&replaced.path).unwrap(), "bad");
+        assert!(!suffixed_path(&vault, "restore").exists());
+
+        assert!(matches!(
+            restore(&vault, "19700101-000000-000", &retention),
+            Err(CoreError::BackupNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_legacy_bak_adopted_and_clear() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path().join("vault.svlt");
+        std::fs::write(&vault, "current").unwrap();
+        std::fs::write(suffixed_path(&vault, "bak"), "legacy").unwrap();
+
+        snapshot(&vault, &Retention::default()).unwrap();
+        let generations = list(&vault).unwrap();
+        assert_eq!(generations.len(), 2);
+        assert!(generations
+            .iter()
+            .any(|g| std::fs::read_to_string(&g.path).unwrap() == "legacy"));
+        assert!(!suffixed_path(&vault, "bak").exists());
+
+        clear(&vault).unwrap();
+        assert!(list(&vault).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use sha1::{Digest, Sha1};
+
+use super::error::CoreError;
+
+/// A local copy of the Have I Been Pwned "Pwned Passwords" SHA-1 list,
+/// ordered by hash: one `HASH:COUNT` line per password, uppercase hex.
+///
+/// The file is tens of gigabytes, so it is never loaded: every lookup is a
+/// binary search over byte offsets, reading a couple of lines per step.
+pub struct PwnedFile {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl PwnedFile {
+    pub fn open(path: &Path) -> Result<Self, CoreError> {
+        let file =
+            File::open(path).map_err(|e| CoreError::Breach(format!("{}: {e}", path.display())))?;
+        let len = file
+            .metadata()
+            .map_err(|e| CoreError::Breach(e.to_string()))?
+            .len();
+        Ok(Self {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// How many times `password` appears in the breach corpus, or `None` if
+    /// it does not.
+    pub fn lookup(&mut self, password: &str) -> Result<Option<u64>, CoreError> {
+        let digest = Sha1::digest(password.as_bytes());
+        let target = data_encoding::HEXUPPER.encode(&digest);
+        self.find(&target)
+    }
+
+    /// Binary search for `hash`. Invariant: a matching line, if any, starts
+    /// within `lo..hi`.
+    fn find(&mut self, hash: &str) -> Result<Option<u64>, CoreError> {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let Some((start, next, line)) = self.line_at(mid)? else {
+                hi = mid;
+                continue;
+            };
+            if start >= hi {
+                hi = mid;
+                continue;
+            }
+
+            let (line_hash, count) = line.split_once(':').unwrap_or((&line, ""));
+            match line_hash.to_ascii_uppercase().as_str().cmp(hash) {
+                Ordering::Equal => return Ok(Some(count.trim().parse().unwrap_or(1))),
+                Ordering::Less => lo = next,
+                // No line starts between `mid` and `start`.
+                Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(None)
+    }
+
+    /// The first line starting at or after `offset`, as `(start, end, text)`
+    /// where `end` is the offset of the following line.
+    fn line_at(&mut self, offset: u64) -> Result<Option<(u64, u64, String)>, CoreError> {
+        let err = |e: std::io::Error| CoreError::Breach(e.to_string());
+        let mut buf = Vec::new();
+
+        let start = if offset == 0 {
+            self.reader.seek(SeekFrom::Start(0)).map_err(err)?;
+            0
+        } else {
+            // Skip the rest of the line that `offset - 1` belongs to.
+            self.reader.seek(SeekFrom::Start(offset - 1)).map_err(err)?;
+            offset - 1 + self.reader.read_until(b'\n', &mut buf).map_err(err)? as u64
+        };
+
+        buf.clear();
+        let read = self.reader.read_until(b'\n', &mut buf).map_err(err)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&buf).trim().to_string();
+        Ok(Some((start, start + read as u64, line)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sha1_hex(password: &str) -> String {
+        data_encoding::HEXUPPER.encode(&Sha1::digest(password.as_bytes()))
+    }
+
+    /// A small, sorted dump in the official layout (CRLF line endings).
+    fn dump(passwords: &[(&str, u64)]) -> (tempfile::TempDir, std::path::PathBuf) {
+        let mut lines: Vec<String> = passwords
+            .iter()
+            .map(|(p, n)| format!("{}:{n}", sha1_hex(p)))
+            .collect();
+        lines.extend((0..200).map(|i| format!("{}:1", sha1_hex(&format!("filler{i}")))));
+        lines.sort();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pwned-passwords-sha1-ordered-by-hash.txt");
+        std::fs::write(&path, lines.join("\r\n") + "\r\n").unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_known_hash() {
+        assert_eq!(
+            sha1_hex("password"),
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let (_dir, path) = dump(&[("password", 9_545_824), ("hunter2", 17_043)]);
+        let mut pwned = PwnedFile::open(&path).unwrap();
+        assert_eq!(pwned.lookup("password").unwrap(), Some(9_545_824));
+        assert_eq!(pwned.lookup("hunter2").unwrap(), Some(17_043));
+        assert_eq!(pwned.lookup("k#9Lw!q2Vz$8mR@4xT&e").unwrap(), None);
+        for i in 0..200 {
+            assert_eq!(pwned.lookup(&format!("filler{i}")).unwrap(), Some(1));
+        }
+    }
+
+    #[test]
+    fn test_first_and_last_lines() {
+        let (_dir, path) = dump(&[]);
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        let mut pwned = PwnedFile::open(&path).unwrap();
+        for line in [lines[0], lines[lines.len() - 1]] {
+            let hash = line.split(':').next().unwrap();
+            assert_eq!(pwned.find(hash).unwrap(), Some(1));
+        }
+    }
+
+    #[test]
+    fn test_empty_and_missing_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("empty.txt");
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(PwnedFile::open(&path).unwrap().lookup("x").unwrap(), None);
+        assert!(PwnedFile::open(&dir.path().join("missing.txt")).is_err());
+    }
+}
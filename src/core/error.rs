@@ -15,6 +15,51 @@ pub enum CoreError {
     #[error("Password length must be at least 1")]
     InvalidLength,
 
+    #[error("A passphrase needs at least one word")]
+    InvalidWordCount,
+
+    #[error("Cannot generate a password: {0}")]
+    GeneratorPolicy(String),
+
+    #[error("No password history entry #{0}")]
+    HistoryIndex(usize),
+
+    #[error("Invalid OTP seed: {0}")]
+    InvalidOtp(String),
+
+    #[error("Key derivation: {0}")]
+    Kdf(String),
+
+    #[error("Re-key failed: {0}")]
+    Rekey(String),
+
     #[error("Backup failed: {0}")]
     Backup(std::io::Error),
+
+    #[error("No backup '{0}' (see `valt backup list`)")]
+    BackupNotFound(String),
+
+    #[error("Cannot lock the vault: {0}")]
+    Lock(std::io::Error),
+
+    #[error("The vault is busy: another process is writing it")]
+    Busy,
+
+    #[error("The vault was changed by another process and cannot be reloaded with this password; nothing was saved")]
+    Conflict,
+
+    #[error("'{0}' was changed by another process since it was opened; nothing was saved")]
+    Stale(String),
+
+    #[error("Sync failed: {0}")]
+    Sync(String),
+
+    #[error("Import failed: {0}")]
+    Import(String),
+
+    #[error("Export failed: {0}")]
+    Export(String),
+
+    #[error("Breach check failed: {0}")]
+    Breach(String),
 }
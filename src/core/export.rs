@@ -0,0 +1,244 @@
+use std::io::Write;
+use std::path::Path;
+
+use serdevault::VaultFile;
+use zeroize::Zeroize;
+
+use super::{
+    entry::EntryKind,
+    error::CoreError,
+    kdf::KdfParams,
+    manager::suffixed_path,
+    secret::{FieldKind, Secret, SecretString},
+    vault_data::VaultData,
+};
+
+/// Columns of the CSV export, in the layout Bitwarden's CSV importer expects.
+/// KeePassXC maps them in its import dialog, and `valt import -F generic-csv`
+/// reads them back.
+const CSV_HEADER: [&str; 11] = [
+    "folder",
+    "favorite",
+    "type",
+    "name",
+    "notes",
+    "fields",
+    "reprompt",
+    "login_uri",
+    "login_username",
+    "login_password",
+    "login_totp",
+];
+
+/// Serialize secrets as a pretty-printed JSON array of `Secret`.
+pub fn to_json(secrets: &[Secret]) -> Result<SecretString, CoreError> {
+    serde_json::to_string_pretty(secrets)
+        .map(SecretString::new)
+        .map_err(|e| CoreError::Export(e.to_string()))
+}
+
+/// Serialize secrets as CSV.
+///
+/// The group becomes the folder. Tags are lost, as are password history and
+/// custom field kinds. Custom fields are written one per line as
+/// `label: value`. Notes keep their type; other entry kinds are written as
+/// logins carrying their fields.
+pub fn to_csv(secrets: &[Secret]) -> Result<SecretString, CoreError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let err = |e: csv::Error| CoreError::Export(e.to_string());
+
+    writer.write_record(CSV_HEADER).map_err(err)?;
+    for s in secrets {
+        let mut fields = SecretString::default();
+        for f in &s.fields {
+            if !fields.is_empty() {
+                fields.push('\n');
+            }
+            fields.push_str(&f.label);
+            fields.push_str(": ");
+            fields.push_str(&f.value);
+        }
+        let totp = s.otp.as_ref().map(|o| o.to_uri()).unwrap_or_default();
+        writer
+            .write_record([
+                s.group.as_deref().unwrap_or(""),
+                "",
+                match s.kind {
+                    EntryKind::Note => "note",
+                    _ => "login",
+                },
+                &s.name,
+                s.notes.as_deref().map_or("", String::as_str),
+                &fields,
+                "",
+                s.url.as_deref().unwrap_or(""),
+                s.username.as_deref().unwrap_or(""),
+                &s.password,
+                &totp,
+            ])
+            .map_err(err)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| CoreError::Export(e.to_string()))?;
+    String::from_utf8(bytes)
+        .map(SecretString::new)
+        .map_err(|e| {
+            let message = e.to_string();
+            e.into_bytes().zeroize();
+            CoreError::Export(message)
+        })
+}
+
+/// Write secrets to a new vault file encrypted under `passphrase`.
+///
+/// The result is a regular valt vault: it can be opened with the passphrase
+/// like any other vault.
+pub fn write_encrypted(
+    path: &Path,
+    passphrase: &str,
+    params: KdfParams,
+    secrets: &[Secret],
+) -> Result<(), CoreError> {
+    let data = VaultData {
+        secrets: secrets.to_vec(),
+        ..Default::default()
+    };
+    params
+        .apply(VaultFile::open(path, passphrase))
+        .save(&data)?;
+    Ok(())
+}
+
+/// Write a plaintext export. On Unix the file is readable by its owner
+/// only, even if it existed before with looser permissions: the export is
+/// written to a new owner-only file next to `path` and renamed over it.
+pub fn write_plaintext(path: &Path, contents: &str) -> Result<(), CoreError> {
+    let staged = suffixed_path(path, "tmp");
+    // A leftover from an interrupted export may have any mode.
+    let _ = std::fs::remove_file(&staged);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(&staged)
+        .and_then(|mut f| {
+            f.write_all(contents.as_bytes())?;
+            f.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&staged, path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&staged);
+            CoreError::Export(format!("{}: {e}", path.display()))
+        })
+}
+
+/// Whether any secret has hidden custom fields, which CSV cannot mark as
+/// such: they come back as plain text fields on import.
+pub fn csv_loses_hidden_fields(secrets: &[Secret]) -> bool {
+    secrets
+        .iter()
+        .flat_map(|s| &s.fields)
+        .any(|f| f.kind == FieldKind::Hidden)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::import::{CsvFlavor, CsvImporter, Importer};
+    use crate::core::{CustomField, Otp, VaultManager};
+    use tempfile::tempdir;
+
+    fn sample() -> Vec<Secret> {
+        let mut s = Secret::new("GitHub", "s3cr3t");
+        s.username = Some("jb".to_string());
+        s.url = Some("https://github.com".to_string());
+        s.notes = Some("line one\nline two".to_string().into());
+        s.tags = vec!["dev".to_string()];
+        s.group = Some("Work/Infra".to_string());
+        s.fields = vec![CustomField::new("Recovery", "abcd", FieldKind::Hidden)];
+        s.otp = Some(Otp::parse("JBSWY3DPEHPK3PXP").unwrap());
+        vec![s, Secret::new("Wi-Fi", "door")]
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let secrets = sample();
+        let json = to_json(&secrets).unwrap();
+        let back: Vec<Secret> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.len(), 2);
+        assert_eq!(back[0].id, secrets[0].id);
+        assert_eq!(back[0].fields, secrets[0].fields);
+    }
+
+    #[test]
+    fn test_csv_reads_back() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        std::fs::write(&path, to_csv(&sample()).unwrap()).unwrap();
+
+        let imported = CsvImporter(CsvFlavor::Generic).import(&path).unwrap();
+        assert!(imported.skipped.is_empty());
+        let gh = &imported.secrets[0];
+        assert_eq!(gh.name, "GitHub");
+        assert_eq!(gh.username.as_deref(), Some("jb"));
+        assert_eq!(gh.password.as_str(), "s3cr3t");
+        assert_eq!(
+            gh.notes.as_deref().map(String::as_str),
+            Some("line one\nline two")
+        );
+        assert_eq!(gh.group.as_deref(), Some("Work/Infra"));
+        assert!(gh.otp.is_some());
+        assert_eq!(gh.fields[0].label, "Recovery");
+        assert_eq!(gh.fields[0].value.as_str(), "abcd");
+    }
+
+    #[test]
+    fn test_encrypted_export_is_a_vault() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("export.svlt");
+        let params = KdfParams {
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        write_encrypted(&path, "other passphrase", params, &sample()).unwrap();
+
+        assert_eq!(KdfParams::read(&path).unwrap(), params);
+        assert!(VaultManager::open(VaultFile::open(&path, "wrong")).is_err());
+        let mgr = VaultManager::open(VaultFile::open(&path, "other passphrase")).unwrap();
+        assert_eq!(mgr.list().len(), 2);
+        assert!(mgr.list()[0].otp.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plaintext_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("export.json");
+        write_plaintext(&path, "[]").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+
+        // Overwriting a world-readable file tightens it too.
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        write_plaintext(&path, "[{}]").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[{}]");
+        assert!(!suffixed_path(&path, "tmp").exists());
+    }
+
+    #[test]
+    fn test_csv_loses_hidden_fields() {
+        assert!(csv_loses_hidden_fields(&sample()));
+        assert!(!csv_loses_hidden_fields(&sample()[1..]));
+    }
+}
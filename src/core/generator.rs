@@ -1,4 +1,5 @@
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 
 use super::error::CoreError;
 
@@ -6,60 +7,236 @@ const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
 const DIGITS: &str = "0123456789";
 const SYMBOLS: &str = "!@#$%^&*()-_=+[]{}|;:,.<>?";
+/// Characters easily mistaken for one another when read or typed.
+const AMBIGUOUS: &str = "0O1lI|";
+
+/// EFF large wordlist: 7776 words, one per line, each prefixed with the five
+/// dice rolls that select it. Every word adds log2(7776) ≈ 12.9 bits.
+const EFF_WORDLIST: &str = include_str!("wordlists/eff_large_wordlist.txt");
+
+/// What `generate` produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratorMode {
+    /// Random characters from the enabled classes.
+    #[default]
+    Characters,
+    /// Diceware words, easier to type by hand.
+    Passphrase,
+}
 
 /// Options for the password generator.
 #[derive(Debug, Clone)]
 pub struct GeneratorConfig {
+    pub mode: GeneratorMode,
     pub length: usize,
     pub uppercase: bool,
     pub lowercase: bool,
     pub digits: bool,
     pub symbols: bool,
+    /// Minimum number of characters from each class, when it is enabled.
+    pub min_uppercase: usize,
+    pub min_lowercase: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    /// Leave out look-alike characters (see `AMBIGUOUS`).
+    pub exclude_ambiguous: bool,
+    /// Extra characters added to the pool, with no minimum.
+    pub extra_chars: String,
+    /// Characters never used, whatever their class.
+    pub excluded_chars: String,
+    /// Use every character at most once.
+    pub no_repeat: bool,
+    /// Only used in `GeneratorMode::Passphrase`.
+    pub passphrase: PassphraseConfig,
 }
 
 impl Default for GeneratorConfig {
     fn default() -> Self {
         Self {
+            mode: GeneratorMode::Characters,
             length: 20,
             uppercase: true,
             lowercase: true,
             digits: true,
             symbols: true,
+            min_uppercase: 1,
+            min_lowercase: 1,
+            min_digits: 1,
+            min_symbols: 1,
+            exclude_ambiguous: false,
+            extra_chars: String::new(),
+            excluded_chars: String::new(),
+            no_repeat: false,
+            passphrase: PassphraseConfig::default(),
+        }
+    }
+}
+
+/// The words of the EFF list, without their dice rolls.
+pub(crate) fn eff_words() -> impl Iterator<Item = &'static str> {
+    EFF_WORDLIST
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+}
+
+/// Options for passphrase generation.
+#[derive(Debug, Clone)]
+pub struct PassphraseConfig {
+    pub words: usize,
+    pub separator: String,
+    /// Capitalise the first letter of every word.
+    pub capitalize: bool,
+    /// Append a random digit to one of the words.
+    pub digit: bool,
+}
+
+impl Default for PassphraseConfig {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            digit: false,
         }
     }
 }
 
 /// Generate a random password from the given configuration.
 pub fn generate(config: &GeneratorConfig) -> Result<String, CoreError> {
+    if config.mode == GeneratorMode::Passphrase {
+        return generate_passphrase(&config.passphrase);
+    }
     if config.length == 0 {
         return Err(CoreError::InvalidLength);
     }
 
-    let mut charset = String::new();
-    if config.uppercase {
-        charset.push_str(UPPERCASE);
+    let excluded = |c: &char| {
+        config.excluded_chars.contains(*c) || (config.exclude_ambiguous && AMBIGUOUS.contains(*c))
+    };
+    let classes = [
+        (
+            "uppercase",
+            config.uppercase,
+            UPPERCASE,
+            config.min_uppercase,
+        ),
+        (
+            "lowercase",
+            config.lowercase,
+            LOWERCASE,
+            config.min_lowercase,
+        ),
+        ("digits", config.digits, DIGITS, config.min_digits),
+        ("symbols", config.symbols, SYMBOLS, config.min_symbols),
+    ];
+
+    // Pool of each enabled class, with its minimum; extra characters form a
+    // last class with no minimum.
+    let mut pools: Vec<(Vec<char>, usize)> = Vec::new();
+    for (name, enabled, chars, min) in classes {
+        if !enabled {
+            continue;
+        }
+        let pool: Vec<char> = chars.chars().filter(|c| !excluded(c)).collect();
+        if pool.is_empty() && min > 0 {
+            return Err(CoreError::GeneratorPolicy(format!(
+                "every {name} character is excluded"
+            )));
+        }
+        pools.push((pool, min));
+    }
+    let mut extra: Vec<char> = Vec::new();
+    for c in config.extra_chars.chars() {
+        if !excluded(&c) && !extra.contains(&c) && !pools.iter().any(|(p, _)| p.contains(&c)) {
+            extra.push(c);
+        }
     }
-    if config.lowercase {
-        charset.push_str(LOWERCASE);
+    pools.push((extra, 0));
+
+    let available: usize = pools.iter().map(|(p, _)| p.len()).sum();
+    if available == 0 {
+        return Err(CoreError::EmptyCharset);
     }
-    if config.digits {
-        charset.push_str(DIGITS);
+    let required: usize = pools.iter().map(|(_, min)| min).sum();
+    if required > config.length {
+        return Err(CoreError::GeneratorPolicy(format!(
+            "the class minimums add up to {required}, more than the length {}",
+            config.length
+        )));
     }
-    if config.symbols {
-        charset.push_str(SYMBOLS);
+    if config.no_repeat && available < config.length {
+        return Err(CoreError::GeneratorPolicy(format!(
+            "only {available} distinct characters for a length of {}",
+            config.length
+        )));
+    }
+    if config.no_repeat {
+        if let Some((pool, min)) = pools.iter().find(|(p, min)| p.len() < *min) {
+            return Err(CoreError::GeneratorPolicy(format!(
+                "a minimum of {min} needs more than {} distinct characters",
+                pool.len()
+            )));
+        }
     }
 
-    if charset.is_empty() {
-        return Err(CoreError::EmptyCharset);
+    let mut rng = rand::thread_rng();
+    let mut pick = |pool: &mut Vec<char>| {
+        let i = rng.gen_range(0..pool.len());
+        if config.no_repeat {
+            pool.swap_remove(i)
+        } else {
+            pool[i]
+        }
+    };
+
+    // Satisfy each minimum first, then fill from the union of all classes.
+    let mut password: Vec<char> = Vec::with_capacity(config.length);
+    for (pool, min) in pools.iter_mut() {
+        for _ in 0..*min {
+            password.push(pick(pool));
+        }
+    }
+    let mut union: Vec<char> = pools.into_iter().flat_map(|(p, _)| p).collect();
+    while password.len() < config.length {
+        password.push(pick(&mut union));
     }
 
-    let chars: Vec<char> = charset.chars().collect();
+    // Minimums were placed first: shuffle so their positions are random too.
+    password.shuffle(&mut rand::thread_rng());
+    Ok(password.into_iter().collect())
+}
+
+fn generate_passphrase(config: &PassphraseConfig) -> Result<String, CoreError> {
+    if config.words == 0 {
+        return Err(CoreError::InvalidWordCount);
+    }
+
+    let wordlist: Vec<&str> = eff_words().collect();
     let mut rng = rand::thread_rng();
-    let password: String = (0..config.length)
-        .map(|_| chars[rng.gen_range(0..chars.len())])
+
+    let mut words: Vec<String> = (0..config.words)
+        .map(|_| {
+            let word = wordlist[rng.gen_range(0..wordlist.len())];
+            if config.capitalize {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            } else {
+                word.to_string()
+            }
+        })
         .collect();
 
-    Ok(password)
+    if config.digit {
+        let i = rng.gen_range(0..words.len());
+        let digit = DIGITS.as_bytes()[rng.gen_range(0..DIGITS.len())] as char;
+        words[i].push(digit);
+    }
+
+    Ok(words.join(&config.separator))
 }
 
 #[cfg(test)]
@@ -84,6 +261,7 @@ mod tests {
             lowercase: false,
             digits: true,
             symbols: false,
+            ..Default::default()
         };
         let pwd = generate(&cfg).unwrap();
         assert!(pwd.chars().all(|c| c.is_ascii_digit()));
@@ -97,6 +275,7 @@ mod tests {
             lowercase: false,
             digits: false,
             symbols: false,
+            ..Default::default()
         };
         let pwd = generate(&cfg).unwrap();
         assert!(pwd.chars().all(|c| c.is_ascii_uppercase()));
@@ -110,6 +289,7 @@ mod tests {
             lowercase: false,
             digits: false,
             symbols: false,
+            ..Default::default()
         };
         assert!(matches!(generate(&cfg), Err(CoreError::EmptyCharset)));
     }
@@ -131,4 +311,186 @@ mod tests {
         // Statistically guaranteed to differ with 20 chars from a 90-char charset
         assert_ne!(a, b);
     }
+
+    fn passphrase(config: PassphraseConfig) -> GeneratorConfig {
+        GeneratorConfig {
+            mode: GeneratorMode::Passphrase,
+            passphrase: config,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_wordlist_is_complete() {
+        let mut rolls = Vec::new();
+        let mut words = std::collections::HashSet::new();
+        for line in EFF_WORDLIST.lines() {
+            let (roll, word) = line.split_once('\t').unwrap();
+            assert!(roll.len() == 5 && roll.chars().all(|c| ('1'..='6').contains(&c)));
+            assert!(word.chars().all(|c| c.is_ascii_lowercase() || c == '-'));
+            rolls.push(roll);
+            words.insert(word);
+        }
+        assert_eq!(words.len(), 7776);
+        assert!(rolls.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_passphrase_word_count() {
+        let cfg = passphrase(PassphraseConfig {
+            words: 5,
+            separator: " ".to_string(),
+            ..Default::default()
+        });
+        let phrase = generate(&cfg).unwrap();
+        let words: Vec<_> = phrase.split(' ').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words
+            .iter()
+            .all(|w| EFF_WORDLIST.lines().any(|l| l.ends_with(&format!("\t{w}")))));
+    }
+
+    #[test]
+    fn test_passphrase_capitalize_and_digit() {
+        let cfg = passphrase(PassphraseConfig {
+            words: 4,
+            separator: ".".to_string(),
+            capitalize: true,
+            digit: true,
+        });
+        let phrase = generate(&cfg).unwrap();
+        assert!(phrase
+            .split('.')
+            .all(|w| w.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert_eq!(phrase.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+    }
+
+    #[test]
+    fn test_passphrase_zero_words_error() {
+        let cfg = passphrase(PassphraseConfig {
+            words: 0,
+            ..Default::default()
+        });
+        assert!(matches!(generate(&cfg), Err(CoreError::InvalidWordCount)));
+    }
+
+    fn count(pwd: &str, class: &str) -> usize {
+        pwd.chars().filter(|c| class.contains(*c)).count()
+    }
+
+    #[test]
+    fn test_class_minimums_guaranteed() {
+        let cfg = GeneratorConfig {
+            length: 8,
+            min_uppercase: 2,
+            min_lowercase: 1,
+            min_digits: 3,
+            min_symbols: 2,
+            ..Default::default()
+        };
+        // With 8 characters and minimums adding up to 8, every run must hit
+        // them exactly; a uniform pick would almost never do so.
+        for _ in 0..200 {
+            let pwd = generate(&cfg).unwrap();
+            assert_eq!(count(&pwd, UPPERCASE), 2);
+            assert_eq!(count(&pwd, LOWERCASE), 1);
+            assert_eq!(count(&pwd, DIGITS), 3);
+            assert_eq!(count(&pwd, SYMBOLS), 2);
+        }
+    }
+
+    #[test]
+    fn test_default_has_every_class() {
+        let cfg = GeneratorConfig {
+            length: 4,
+            ..Default::default()
+        };
+        for _ in 0..200 {
+            let pwd = generate(&cfg).unwrap();
+            for class in [UPPERCASE, LOWERCASE, DIGITS, SYMBOLS] {
+                assert!(count(&pwd, class) >= 1, "{pwd} misses a class");
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimums_exceed_length() {
+        let cfg = GeneratorConfig {
+            length: 3,
+            ..Default::default()
+        };
+        assert!(matches!(generate(&cfg), Err(CoreError::GeneratorPolicy(_))));
+    }
+
+    #[test]
+    fn test_exclude_ambiguous() {
+        let cfg = GeneratorConfig {
+            length: 500,
+            exclude_ambiguous: true,
+            ..Default::default()
+        };
+        let pwd = generate(&cfg).unwrap();
+        assert_eq!(count(&pwd, AMBIGUOUS), 0);
+    }
+
+    #[test]
+    fn test_custom_include_and_exclude() {
+        let cfg = GeneratorConfig {
+            length: 200,
+            symbols: false,
+            min_symbols: 0,
+            extra_chars: "€§".to_string(),
+            excluded_chars: "aeiouAEIOU".to_string(),
+            ..Default::default()
+        };
+        let pwd = generate(&cfg).unwrap();
+        assert_eq!(count(&pwd, "aeiouAEIOU"), 0);
+        assert_eq!(count(&pwd, SYMBOLS), 0);
+        assert!(pwd.contains('€') || pwd.contains('§'));
+
+        let only_extra = GeneratorConfig {
+            length: 50,
+            uppercase: false,
+            lowercase: false,
+            digits: false,
+            symbols: false,
+            extra_chars: "xyz".to_string(),
+            ..Default::default()
+        };
+        let pwd = generate(&only_extra).unwrap();
+        assert!(pwd.chars().all(|c| "xyz".contains(c)));
+    }
+
+    #[test]
+    fn test_class_fully_excluded() {
+        let cfg = GeneratorConfig {
+            excluded_chars: DIGITS.to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(generate(&cfg), Err(CoreError::GeneratorPolicy(_))));
+    }
+
+    #[test]
+    fn test_no_repeat() {
+        let cfg = GeneratorConfig {
+            length: 10,
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            no_repeat: true,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let pwd = generate(&cfg).unwrap();
+            let mut digits: Vec<char> = pwd.chars().collect();
+            digits.sort();
+            assert_eq!(digits.iter().collect::<String>(), DIGITS);
+        }
+
+        let too_long = GeneratorConfig { length: 11, ..cfg };
+        assert!(matches!(
+            generate(&too_long),
+            Err(CoreError::GeneratorPolicy(_))
+        ));
+    }
 }
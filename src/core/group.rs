@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use super::secret::Secret;
+
+/// Separates the levels of a group path, as in `clients/acme/prod`.
+pub const SEPARATOR: char = '/';
+
+/// `path` without blank levels or the whitespace around each level
+/// (" clients//acme/ " becomes "clients/acme"), or `None` if nothing is
+/// left: the secret then sits at the top level.
+pub fn normalize(path: &str) -> Option<String> {
+    let levels: Vec<_> = levels(path).collect();
+    (!levels.is_empty()).then(|| levels.join("/"))
+}
+
+/// Whether a secret in `group` shows up under `filter`: it is in that group
+/// or in one of its subgroups. Levels compare case-insensitively.
+pub fn contains(filter: &str, group: Option<&str>) -> bool {
+    let mut group = levels(group.unwrap_or(""));
+    levels(filter).all(|level| group.next().is_some_and(|g| g.eq_ignore_ascii_case(level)))
+}
+
+fn levels(path: &str) -> impl Iterator<Item = &str> {
+    path.split(SEPARATOR)
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+}
+
+/// A group in the tree built by `tree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// Full path, e.g. `clients/acme`.
+    pub path: String,
+    /// Last level of the path, e.g. `acme`.
+    pub name: String,
+    /// 0 for top-level groups.
+    pub depth: usize,
+    /// Secrets in the group and its subgroups.
+    pub count: usize,
+    pub has_children: bool,
+}
+
+/// Every group used by `secrets`, parents included, depth first and sorted
+/// by name at each level. Levels that differ only in case are one group, as
+/// for `contains`, shown as first spelled.
+pub fn tree(secrets: &[Secret]) -> Vec<Node> {
+    // Keyed by levels so that a group sorts right before its subgroups.
+    let mut groups: BTreeMap<Vec<String>, (Vec<&str>, usize)> = BTreeMap::new();
+    for secret in secrets {
+        let levels: Vec<_> = levels(secret.group.as_deref().unwrap_or("")).collect();
+        for depth in 1..=levels.len() {
+            let key = levels[..depth].iter().map(|l| l.to_lowercase()).collect();
+            groups.entry(key).or_insert((levels[..depth].to_vec(), 0)).1 += 1;
+        }
+    }
+
+    let keys: Vec<_> = groups.keys().collect();
+    keys.iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let (levels, count) = &groups[*key];
+            Node {
+                path: levels.join("/"),
+                name: levels[levels.len() - 1].to_string(),
+                depth: levels.len() - 1,
+                count: *count,
+                has_children: keys.get(i + 1).is_some_and(|next| next.starts_with(key)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_group(name: &str, group: &str) -> Secret {
+        let mut s = Secret::new(name, "p");
+        s.group = normalize(group);
+        s
+    }
+
+    #[test]
+    fn test_normalize_and_contains() {
+        assert_eq!(
+            normalize(" clients//acme/ ").as_deref(),
+            Some("clients/acme")
+        );
+        assert_eq!(normalize(" / "), None);
+
+        assert!(contains("clients/acme", Some("clients/acme/prod")));
+        assert!(contains("Clients", Some("clients/acme")));
+        assert!(contains("", None));
+        assert!(!contains("clients/acme", Some("clients")));
+        assert!(!contains("clients/ac", Some("clients/acme")));
+        assert!(!contains("clients", None));
+    }
+
+    #[test]
+    fn test_tree() {
+        let secrets = [
+            in_group("a", "clients/acme/prod"),
+            in_group("b", "clients/acme/staging"),
+            in_group("c", "clients-old"),
+            in_group("d", "clients"),
+            in_group("e", ""),
+        ];
+        let nodes = tree(&secrets);
+        let paths: Vec<_> = nodes.iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "clients",
+                "clients/acme",
+                "clients/acme/prod",
+                "clients/acme/staging",
+                "clients-old"
+            ]
+        );
+        assert_eq!(nodes[0].count, 3);
+        assert!(nodes[0].has_children);
+        assert_eq!((nodes[2].name.as_str(), nodes[2].depth), ("prod", 2));
+        assert!(!nodes[2].has_children);
+        assert!(!nodes[4].has_children);
+    }
+
+    #[test]
+    fn test_tree_ignores_case_like_contains() {
+        let secrets = [
+            in_group("a", "Work/acme"),
+            in_group("b", "work/ACME"),
+            in_group("c", "work"),
+            in_group("d", "personal"),
+        ];
+        let nodes = tree(&secrets);
+        let paths: Vec<_> = nodes.iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(paths, ["personal", "Work", "Work/acme"]);
+        assert_eq!(nodes[1].count, 3);
+        assert_eq!(nodes[2].count, 2);
+        for node in &nodes {
+            let shown = secrets
+                .iter()
+                .filter(|s| contains(&node.path, s.group.as_deref()))
+                .count();
+            assert_eq!(shown, node.count, "{}", node.path);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::{read_file, Draft, Imported, Importer, Skipped};
+use crate::core::{
+    entry::EntryKind,
+    error::CoreError,
+    secret::{CustomField, FieldKind},
+};
+
+/// Unencrypted Bitwarden JSON export (`bitwarden_export_*.json`).
+pub struct BitwardenJson;
+
+impl Importer for BitwardenJson {
+    fn import(&self, path: &Path) -> Result<Imported, CoreError> {
+        parse(&read_file(path)?)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    kind: u8,
+    name: Option<String>,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    login: Option<Login>,
+    card: Option<HashMap<String, Option<String>>>,
+    identity: Option<HashMap<String, Option<String>>>,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<Uri>,
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: Option<String>,
+    value: Option<String>,
+    /// 0 = text, 1 = hidden, 2 = boolean, 3 = linked
+    #[serde(rename = "type", default)]
+    kind: u8,
+}
+
+const LOGIN: u8 = 1;
+const SECURE_NOTE: u8 = 2;
+const CARD: u8 = 3;
+const IDENTITY: u8 = 4;
+
+/// Identity keys that must not be shown in clear.
+const IDENTITY_HIDDEN: [&str; 1] = ["ssn"];
+
+pub(super) fn parse(input: &str) -> Result<Imported, CoreError> {
+    let export: Export =
+        serde_json::from_str(input).map_err(|e| CoreError::Import(format!("invalid JSON: {e}")))?;
+    if export.encrypted {
+        return Err(CoreError::Import(
+            "encrypted Bitwarden exports are not supported; export as unencrypted JSON".to_string(),
+        ));
+    }
+
+    let folders: HashMap<String, String> =
+        export.folders.into_iter().map(|f| (f.id, f.name)).collect();
+
+    let mut imported = Imported::default();
+    for (i, item) in export.items.into_iter().enumerate() {
+        let name = item.name.clone().unwrap_or_default();
+        if name.trim().is_empty() {
+            imported.skipped.push(Skipped {
+                source: format!("item #{}", i + 1),
+                reason: "no name".to_string(),
+            });
+            continue;
+        }
+
+        let mut draft = Draft {
+            name,
+            notes: item.notes.unwrap_or_default(),
+            ..Default::default()
+        };
+        // Nested folders are named after their full path, e.g. "Work/Infra".
+        if let Some(folder) = item.folder_id.and_then(|id| folders.get(&id)) {
+            draft.tags.push(folder.clone());
+            draft.group = folder.clone();
+        }
+
+        match item.kind {
+            LOGIN => {
+                if let Some(login) = item.login {
+                    draft.username = login.username.unwrap_or_default();
+                    draft.password = login.password.unwrap_or_default();
+                    draft.otp = login.totp.unwrap_or_default();
+                    let mut uris = login.uris.into_iter().filter_map(|u| u.uri);
+                    draft.url = uris.next().unwrap_or_default();
+                    for uri in uris {
+                        draft
+                            .fields
+                            .push(CustomField::new("URL", uri, FieldKind::Url));
+                    }
+                }
+            }
+            SECURE_NOTE => draft.kind = EntryKind::Note,
+            CARD => {
+                draft.kind = EntryKind::Card;
+                let mut card = non_empty(item.card);
+                draft.set_detail("Cardholder", take(&mut card, "cardholderName"));
+                draft.set_detail("Number", take(&mut card, "number"));
+                let month = take(&mut card, "expMonth");
+                let year = take(&mut card, "expYear");
+                if !month.is_empty() && !year.is_empty() {
+                    draft.set_detail("Expiry", format!("{month:0>2}/{year}"));
+                }
+                draft.set_detail("CVV", take(&mut card, "code"));
+                push_rest(&mut draft, card, &[]);
+            }
+            IDENTITY => {
+                draft.kind = EntryKind::Identity;
+                let mut id = non_empty(item.identity);
+                let full_name = ["firstName", "middleName", "lastName"]
+                    .map(|k| take(&mut id, k))
+                    .join(" ");
+                draft.set_detail(
+                    "Full name",
+                    full_name.split_whitespace().collect::<Vec<_>>().join(" "),
+                );
+                draft.set_detail("Email", take(&mut id, "email"));
+                draft.set_detail("Phone", take(&mut id, "phone"));
+                let address: Vec<_> = [
+                    "address1",
+                    "address2",
+                    "address3",
+                    "city",
+                    "state",
+                    "postalCode",
+                    "country",
+                ]
+                .map(|k| take(&mut id, k))
+                .into_iter()
+                .filter(|v| !v.is_empty())
+                .collect();
+                draft.set_detail("Address", address.join(", "));
+                for (key, document) in [
+                    ("passportNumber", "Passport"),
+                    ("licenseNumber", "Driver's license"),
+                ] {
+                    let number = take(&mut id, key);
+                    if number.is_empty() {
+                        continue;
+                    }
+                    if draft.fields.iter().any(|f| f.label == "Document number") {
+                        draft
+                            .fields
+                            .push(CustomField::new(document, number, FieldKind::Hidden));
+                    } else {
+                        draft.set_detail("Document", document);
+                        draft.set_detail("Document number", number);
+                    }
+                }
+                push_rest(&mut draft, id, &IDENTITY_HIDDEN);
+            }
+            other => {
+                imported.skipped.push(Skipped {
+                    source: draft.name,
+                    reason: format!("unsupported item type {other}"),
+                });
+                continue;
+            }
+        }
+
+        for field in item.fields {
+            let label = field.name.unwrap_or_default();
+            let value = field.value.unwrap_or_default();
+            if label.is_empty() && value.is_empty() {
+                continue;
+            }
+            let kind = if field.kind == 1 {
+                FieldKind::Hidden
+            } else {
+                FieldKind::Text
+            };
+            draft.fields.push(CustomField::new(label, value, kind));
+        }
+
+        imported.secrets.push(draft.into_secret());
+    }
+    Ok(imported)
+}
+
+type Details = HashMap<String, String>;
+
+/// The non-blank values of a card or identity.
+fn non_empty(map: Option<HashMap<String, Option<String>>>) -> Details {
+    map.unwrap_or_default()
+        .into_iter()
+        .filter_map(|(k, v)| v.filter(|v| !v.trim().is_empty()).map(|v| (k, v)))
+        .collect()
+}
+
+fn take(details: &mut Details, key: &str) -> String {
+    details.remove(key).unwrap_or_default()
+}
+
+/// Keep values with no place in the entry's schema as custom fields.
+fn push_rest(draft: &mut Draft, details: Details, hidden: &[&str]) {
+    let mut rest: Vec<_> = details.into_iter().collect();
+    rest.sort();
+    for (key, value) in rest {
+        let kind = if hidden.contains(&key.as_str()) {
+            FieldKind::Hidden
+        } else {
+            FieldKind::Text
+        };
+        draft.fields.push(CustomField::new(key, value, kind));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "encrypted": false,
+        "folders": [{ "id": "f1", "name": "Work/Infra" }],
+        "items": [
+            {
+                "type": 1, "name": "GitHub", "notes": "2FA on", "folderId": "f1",
+                "login": {
+                    "username": "jb", "password": "s3cr3t", "totp": "JBSWY3DPEHPK3PXP",
+                    "uris": [{ "uri": "https://github.com" }, { "uri": "https://gist.github.com" }]
+                },
+                "fields": [{ "name": "Recovery", "value": "abcd-efgh", "type": 1 }]
+            },
+            { "type": 2, "name": "Wi-Fi", "notes": "door code 1234", "folderId": null },
+            { "type": 3, "name": "Visa", "card": { "number": "4111111111111111", "expMonth": "3", "expYear": "2027", "brand": "Visa", "code": null } },
+            { "type": 4, "name": "Me", "identity": { "firstName": "Jane", "lastName": "Doe", "city": "Lyon", "country": "FR", "passportNumber": "X123", "ssn": "1-23" } },
+            { "type": 1, "name": "", "login": {} }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_sample() {
+        let imported = parse(SAMPLE).unwrap();
+        assert_eq!(imported.secrets.len(), 4);
+        assert_eq!(imported.skipped.len(), 1);
+
+        let gh = &imported.secrets[0];
+        assert_eq!(gh.username.as_deref(), Some("jb"));
+        assert_eq!(gh.password.as_str(), "s3cr3t");
+        assert_eq!(gh.url.as_deref(), Some("https://github.com"));
+        assert_eq!(gh.tags, ["Work/Infra"]);
+        assert_eq!(gh.group.as_deref(), Some("Work/Infra"));
+        assert!(gh.otp.is_some());
+        assert_eq!(gh.fields.len(), 2);
+        assert_eq!(gh.fields[1].kind, FieldKind::Hidden);
+
+        let note = &imported.secrets[1];
+        assert_eq!(
+            note.notes.as_deref().map(String::as_str),
+            Some("door code 1234")
+        );
+        assert!(note.tags.is_empty());
+        assert!(note.group.is_none());
+
+        assert_eq!(note.kind, EntryKind::Note);
+
+        let card = &imported.secrets[2];
+        assert_eq!(card.kind, EntryKind::Card);
+        assert_eq!(card.main_value(), Some("4111111111111111"));
+        assert_eq!(card.fields[0].kind, FieldKind::Hidden);
+        assert_eq!(card.field("Expiry"), Some("03/2027"));
+        assert_eq!(card.field("brand"), Some("Visa"));
+        assert_eq!(card.fields.len(), 3);
+        assert_eq!(EntryKind::Card.check(&card.fields), None);
+
+        let me = &imported.secrets[3];
+        assert_eq!(me.kind, EntryKind::Identity);
+        assert_eq!(me.field("Full name"), Some("Jane Doe"));
+        assert_eq!(me.field("Address"), Some("Lyon, FR"));
+        assert_eq!(me.field("Document"), Some("Passport"));
+        assert_eq!(me.main_value(), Some("X123"));
+        assert_eq!(me.fields.last().unwrap().kind, FieldKind::Hidden);
+    }
+
+    #[test]
+    fn test_encrypted_rejected() {
+        assert!(parse(r#"{ "encrypted": true, "items": [] }"#).is_err());
+        assert!(parse("not json").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::Path;
+
+use super::{read_file, Draft, Imported, Importer, Skipped};
+use crate::core::{
+    error::CoreError,
+    secret::{CustomField, FieldKind},
+};
+
+/// The password manager or browser that produced a CSV export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvFlavor {
+    /// `Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes`
+    OnePassword,
+    /// `name,url,username,password,note`
+    Chrome,
+    /// `url,username,password,httpRealm,formActionOrigin,guid,time…`
+    Firefox,
+    /// Any CSV with a header row using the column names below.
+    Generic,
+}
+
+impl CsvFlavor {
+    /// Bookkeeping columns of the exporter that carry nothing worth keeping.
+    fn ignored(self) -> &'static [&'static str] {
+        match self {
+            CsvFlavor::OnePassword => &["favorite", "archived"],
+            CsvFlavor::Chrome => &[],
+            CsvFlavor::Firefox => &[
+                "httprealm",
+                "formactionorigin",
+                "guid",
+                "timecreated",
+                "timelastused",
+                "timepasswordchanged",
+            ],
+            // Bitwarden's CSV layout, also written by `valt export --format csv`.
+            CsvFlavor::Generic => &["favorite", "type", "reprompt"],
+        }
+    }
+}
+
+/// Importer for CSV exports with a header row.
+pub struct CsvImporter(pub CsvFlavor);
+
+impl Importer for CsvImporter {
+    fn import(&self, path: &Path) -> Result<Imported, CoreError> {
+        parse(&read_file(path)?, self.0)
+    }
+}
+
+/// Which `Draft` slot a column fills.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Name,
+    Username,
+    Password,
+    Url,
+    Notes,
+    Otp,
+    Tags,
+    Folder,
+    /// `label: value` lines, as in Bitwarden's `fields` column.
+    Fields,
+    Ignored,
+    /// Anything else is kept as a text custom field named after the header.
+    Extra,
+}
+
+/// Map a header to a column, case-insensitively.
+fn column(header: &str, flavor: CsvFlavor) -> Column {
+    let h = header.trim().to_ascii_lowercase();
+    match h.as_str() {
+        "name" | "title" => Column::Name,
+        "username" | "login" | "user" | "login_username" => Column::Username,
+        "password" | "login_password" => Column::Password,
+        "url" | "uri" | "website" | "login_uri" => Column::Url,
+        "notes" | "note" | "comments" => Column::Notes,
+        "otp" | "totp" | "otpauth" | "login_totp" => Column::Otp,
+        "tags" | "labels" => Column::Tags,
+        "group" | "folder" | "category" => Column::Folder,
+        "fields" => Column::Fields,
+        _ if flavor.ignored().contains(&h.as_str()) => Column::Ignored,
+        _ => Column::Extra,
+    }
+}
+
+pub(super) fn parse(input: &str, flavor: CsvFlavor) -> Result<Imported, CoreError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(input.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| CoreError::Import(format!("invalid CSV header: {e}")))?
+        .clone();
+    let columns: Vec<Column> = headers.iter().map(|h| column(h, flavor)).collect();
+    if !columns.contains(&Column::Password) {
+        return Err(CoreError::Import(
+            "CSV header has no password column".to_string(),
+        ));
+    }
+
+    let mut imported = Imported::default();
+    for (i, record) in reader.records().enumerate() {
+        let source = format!("row {}", i + 1);
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                imported.skipped.push(Skipped {
+                    source,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let mut draft = Draft::default();
+        for ((value, col), header) in record.iter().zip(&columns).zip(headers.iter()) {
+            let value = value.to_string();
+            match col {
+                Column::Name => draft.name = value,
+                Column::Username => draft.username = value,
+                Column::Password => draft.password = value,
+                Column::Url => draft.url = value,
+                Column::Notes => draft.notes = value,
+                Column::Otp => draft.otp = value,
+                Column::Tags => draft.tags.extend(split_tags(&value)),
+                Column::Folder => {
+                    if !value.trim().is_empty() {
+                        draft.tags.insert(0, value.trim().to_string());
+                    }
+                    draft.group = value;
+                }
+                Column::Fields => {
+                    for line in value.lines() {
+                        if let Some((label, v)) = line.split_once(':') {
+                            draft.fields.push(CustomField::new(
+                                label.trim(),
+                                v.trim(),
+                                FieldKind::Text,
+                            ));
+                        }
+                    }
+                }
+                Column::Ignored => {}
+                Column::Extra => {
+                    if !value.trim().is_empty() {
+                        draft
+                            .fields
+                            .push(CustomField::new(header.trim(), value, FieldKind::Text));
+                    }
+                }
+            }
+        }
+
+        // Browsers don't always name their entries: fall back to the host.
+        if draft.name.trim().is_empty() {
+            draft.name = host(&draft.url).unwrap_or_default();
+        }
+        if draft.name.trim().is_empty() {
+            imported.skipped.push(Skipped {
+                source,
+                reason: "no name or URL".to_string(),
+            });
+            continue;
+        }
+        if draft.password.is_empty() && draft.notes.trim().is_empty() {
+            imported.skipped.push(Skipped {
+                source: format!("{source} ({})", draft.name.trim()),
+                reason: "no password".to_string(),
+            });
+            continue;
+        }
+
+        imported.secrets.push(draft.into_secret());
+    }
+    Ok(imported)
+}
+
+fn split_tags(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split([';', ','])
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+fn host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    parsed
+        .host_str()
+        .map(|h| h.strip_prefix("www.").unwrap_or(h).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome() {
+        let input = "name,url,username,password,note\n\
+                     GitHub,https://github.com/login,jb,s3cr3t,\n\
+                     ,https://www.example.com/,me,pw,hello\n\
+                     ,,,orphan,\n";
+        let imported = parse(input, CsvFlavor::Chrome).unwrap();
+        let names: Vec<_> = imported.secrets.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["GitHub", "example.com"]);
+        assert_eq!(
+            imported.secrets[1].notes.as_deref().map(String::as_str),
+            Some("hello")
+        );
+        assert_eq!(imported.skipped.len(), 1);
+        assert_eq!(imported.skipped[0].source, "row 3");
+    }
+
+    #[test]
+    fn test_firefox_ignores_metadata() {
+        let input = "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\",\"timeCreated\",\"timeLastUsed\",\"timePasswordChanged\"\n\
+                     \"https://accounts.example.org\",\"alice\",\"hunter2\",,\"https://accounts.example.org\",\"{abc}\",\"1700000000000\",\"1700000000000\",\"1700000000000\"\n";
+        let imported = parse(input, CsvFlavor::Firefox).unwrap();
+        let s = &imported.secrets[0];
+        assert_eq!(s.name, "accounts.example.org");
+        assert_eq!(s.username.as_deref(), Some("alice"));
+        assert!(s.fields.is_empty());
+    }
+
+    #[test]
+    fn test_one_password() {
+        let input = "Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\n\
+                     AWS,https://aws.amazon.com,root,pw,otpauth://totp/AWS?secret=JBSWY3DPEHPK3PXP,false,false,work;cloud,\n";
+        let imported = parse(input, CsvFlavor::OnePassword).unwrap();
+        let s = &imported.secrets[0];
+        assert!(s.otp.is_some());
+        assert_eq!(s.tags, ["work", "cloud"]);
+        assert!(s.fields.is_empty());
+    }
+
+    #[test]
+    fn test_generic_folder_and_extra_columns() {
+        let input = "Title,Login,Password,Folder,Security question\n\
+                     Bank,jb,pw,Finance,Mother's maiden name\n";
+        let imported = parse(input, CsvFlavor::Generic).unwrap();
+        let s = &imported.secrets[0];
+        assert_eq!(s.tags, ["Finance"]);
+        assert_eq!(s.group.as_deref(), Some("Finance"));
+        assert_eq!(s.fields[0].label, "Security question");
+    }
+
+    #[test]
+    fn test_missing_password_column() {
+        assert!(parse("name,url\nx,y\n", CsvFlavor::Generic).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use roxmltree::{Document, Node};
+
+use super::{read_file, Draft, Imported, Importer, Skipped};
+use crate::core::{
+    error::CoreError,
+    manager::PASSWORD_HISTORY_LIMIT,
+    secret::{CustomField, FieldKind, PasswordEntry, Secret},
+};
+
+/// KeePass 2.x / KeePassXC unencrypted XML export.
+pub struct KeepassXml;
+
+impl Importer for KeepassXml {
+    fn import(&self, path: &Path) -> Result<Imported, CoreError> {
+        parse(&read_file(path)?)
+    }
+}
+
+pub(super) fn parse(input: &str) -> Result<Imported, CoreError> {
+    let doc = Document::parse(input).map_err(|e| CoreError::Import(format!("invalid XML: {e}")))?;
+
+    let file = doc.root_element();
+    if !file.has_tag_name("KeePassFile") {
+        return Err(CoreError::Import(
+            "not a KeePass XML export (missing <KeePassFile>)".to_string(),
+        ));
+    }
+
+    let recycle_bin = child(file, "Meta")
+        .filter(|meta| child_text(*meta, "RecycleBinEnabled") != Some("False"))
+        .and_then(|meta| child_text(meta, "RecycleBinUUID"))
+        .map(str::to_string);
+
+    let mut imported = Imported::default();
+    if let Some(root) = child(file, "Root").and_then(|r| child(r, "Group")) {
+        // The top-level group is the database itself; its name is not a folder.
+        walk_group(root, &[], recycle_bin.as_deref(), &mut imported);
+    }
+    Ok(imported)
+}
+
+fn walk_group(group: Node, path: &[String], recycle_bin: Option<&str>, out: &mut Imported) {
+    for node in group.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "Entry" => match entry_to_secret(node, path) {
+                Ok(secret) => out.secrets.push(secret),
+                Err(reason) => out.skipped.push(Skipped {
+                    source: format!("{}/(entry)", path.join("/")),
+                    reason,
+                }),
+            },
+            "Group" => {
+                if recycle_bin.is_some() && child_text(node, "UUID") == recycle_bin {
+                    continue;
+                }
+                let mut sub = path.to_vec();
+                sub.push(child_text(node, "Name").unwrap_or("").to_string());
+                walk_group(node, &sub, recycle_bin, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn entry_to_secret(entry: Node, path: &[String]) -> Result<Secret, String> {
+    let mut draft = Draft::default();
+
+    for string in entry.children().filter(|n| n.has_tag_name("String")) {
+        let key = child_text(string, "Key").unwrap_or("");
+        let value_node = child(string, "Value");
+        let value = value_node.and_then(|v| v.text()).unwrap_or("").to_string();
+        match key {
+            "Title" => draft.name = value,
+            "UserName" => draft.username = value,
+            "Password" => draft.password = value,
+            "URL" => draft.url = value,
+            "Notes" => draft.notes = value,
+            "otp" | "TOTP Seed" => draft.otp = value,
+            _ if value.is_empty() => {}
+            _ => {
+                let protected =
+                    value_node.and_then(|v| v.attribute("ProtectInMemory")) == Some("True");
+                let kind = if protected {
+                    FieldKind::Hidden
+                } else {
+                    FieldKind::Text
+                };
+                draft.fields.push(CustomField::new(key, value, kind));
+            }
+        }
+    }
+
+    if draft.name.trim().is_empty() {
+        return Err("entry has no title".to_string());
+    }
+
+    let folder = path.join("/");
+    if !folder.is_empty() {
+        draft.tags.push(folder.clone());
+    }
+    draft.group = folder;
+    if let Some(tags) = child_text(entry, "Tags") {
+        draft.tags.extend(
+            tags.split([';', ','])
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    let current_password = draft.password.clone();
+    let mut secret = draft.into_secret();
+
+    // Older versions of the entry become password history, newest first.
+    if let Some(history) = child(entry, "History") {
+        let mut previous: Vec<PasswordEntry> = history
+            .children()
+            .filter(|n| n.has_tag_name("Entry"))
+            .filter_map(|old| {
+                let password = old
+                    .children()
+                    .filter(|n| n.has_tag_name("String"))
+                    .find(|s| child_text(*s, "Key") == Some("Password"))
+                    .and_then(|s| child_text(s, "Value"))?
+                    .to_string();
+                let retired_at = child(old, "Times")
+                    .and_then(|t| child_text(t, "LastModificationTime"))
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_else(Utc::now);
+                Some(PasswordEntry {
+                    password: password.into(),
+                    retired_at,
+                })
+            })
+            .collect();
+        previous.sort_by_key(|e| std::cmp::Reverse(e.retired_at));
+        previous.dedup_by(|a, b| a.password == b.password);
+        previous.retain(|e| *e.password != current_password);
+        previous.truncate(PASSWORD_HISTORY_LIMIT);
+        secret.history = previous;
+    }
+
+    Ok(secret)
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<KeePassFile>
+  <Meta>
+    <RecycleBinEnabled>True</RecycleBinEnabled>
+    <RecycleBinUUID>BIN</RecycleBinUUID>
+  </Meta>
+  <Root>
+    <Group>
+      <UUID>ROOT</UUID>
+      <Name>Database</Name>
+      <Entry>
+        <Tags>infra;ssh</Tags>
+        <String><Key>Title</Key><Value>Router</Value></String>
+        <String><Key>UserName</Key><Value>admin</Value></String>
+        <String><Key>Password</Key><Value ProtectInMemory="True">n3w</Value></String>
+        <String><Key>PIN</Key><Value ProtectInMemory="True">1234</Value></String>
+        <String><Key>Serial</Key><Value>XY-99</Value></String>
+        <History>
+          <Entry>
+            <Times><LastModificationTime>2023-01-01T10:00:00Z</LastModificationTime></Times>
+            <String><Key>Password</Key><Value>0ld</Value></String>
+          </Entry>
+          <Entry>
+            <Times><LastModificationTime>2024-01-01T10:00:00Z</LastModificationTime></Times>
+            <String><Key>Password</Key><Value>n3w</Value></String>
+          </Entry>
+        </History>
+      </Entry>
+      <Group>
+        <UUID>G1</UUID>
+        <Name>Clients</Name>
+        <Group>
+          <UUID>G2</UUID>
+          <Name>Acme</Name>
+          <Entry>
+            <String><Key>Title</Key><Value>Acme VPN</Value></String>
+            <String><Key>Password</Key><Value>vpn</Value></String>
+            <String><Key>URL</Key><Value>https://vpn.acme.test</Value></String>
+          </Entry>
+          <Entry>
+            <String><Key>UserName</Key><Value>nobody</Value></String>
+          </Entry>
+        </Group>
+      </Group>
+      <Group>
+        <UUID>BIN</UUID>
+        <Name>Recycle Bin</Name>
+        <Entry><String><Key>Title</Key><Value>Deleted</Value></String></Entry>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>"#;
+
+    #[test]
+    fn test_parse_sample() {
+        let imported = parse(SAMPLE).unwrap();
+        let names: Vec<_> = imported.secrets.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Router", "Acme VPN"]);
+        assert_eq!(imported.skipped.len(), 1);
+
+        let router = &imported.secrets[0];
+        assert_eq!(router.password.as_str(), "n3w");
+        assert_eq!(router.tags, ["infra", "ssh"]);
+        assert!(router.group.is_none());
+        assert_eq!(router.fields.len(), 2);
+        assert_eq!(router.fields[0].kind, FieldKind::Hidden);
+        assert_eq!(router.fields[1].kind, FieldKind::Text);
+        assert_eq!(router.history.len(), 1);
+        assert_eq!(router.history[0].password.as_str(), "0ld");
+
+        let vpn = &imported.secrets[1];
+        assert_eq!(vpn.tags, ["Clients/Acme"]);
+        assert_eq!(vpn.group.as_deref(), Some("Clients/Acme"));
+        assert_eq!(vpn.url.as_deref(), Some("https://vpn.acme.test"));
+    }
+
+    #[test]
+    fn test_not_keepass() {
+        assert!(parse("<html/>").is_err());
+        assert!(parse("<<<").is_err());
+    }
+}
@@ -0,0 +1,219 @@
+mod bitwarden;
+mod csv_export;
+mod keepass;
+mod pass;
+
+use std::path::Path;
+
+use uuid::Uuid;
+
+use super::{
+    entry::EntryKind,
+    error::CoreError,
+    group,
+    otp::Otp,
+    secret::{CustomField, FieldKind, Secret, SecretString},
+};
+
+pub use bitwarden::BitwardenJson;
+pub use csv_export::{CsvFlavor, CsvImporter};
+pub use keepass::KeepassXml;
+pub use pass::PassStore;
+
+/// A source of secrets exported by another password manager.
+///
+/// Implementations only translate: they never touch the vault. Entries that
+/// cannot be translated are reported in `Imported::skipped` rather than
+/// failing the whole import.
+pub trait Importer {
+    /// Read and translate the export found at `path` (a file, or a directory
+    /// for `PassStore`).
+    fn import(&self, path: &Path) -> Result<Imported, CoreError>;
+}
+
+/// Supported export formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    BitwardenJson,
+    KeepassXml,
+    OnePasswordCsv,
+    ChromeCsv,
+    FirefoxCsv,
+    GenericCsv,
+    PassStore,
+}
+
+impl ImportFormat {
+    pub fn importer(self) -> Box<dyn Importer> {
+        match self {
+            ImportFormat::BitwardenJson => Box::new(BitwardenJson),
+            ImportFormat::KeepassXml => Box::new(KeepassXml),
+            ImportFormat::OnePasswordCsv => Box::new(CsvImporter(CsvFlavor::OnePassword)),
+            ImportFormat::ChromeCsv => Box::new(CsvImporter(CsvFlavor::Chrome)),
+            ImportFormat::FirefoxCsv => Box::new(CsvImporter(CsvFlavor::Firefox)),
+            ImportFormat::GenericCsv => Box::new(CsvImporter(CsvFlavor::Generic)),
+            ImportFormat::PassStore => Box::new(PassStore),
+        }
+    }
+}
+
+/// Result of translating an export.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub secrets: Vec<Secret>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// An entry of the export that could not be imported, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    /// Where the entry came from: a title, a row number, a file path…
+    pub source: String,
+    pub reason: String,
+}
+
+/// What an import would do to the vault.
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    /// Entries with no counterpart in the vault.
+    pub fresh: Vec<Secret>,
+    /// Entries that look like an existing one (or an earlier entry of the
+    /// same import), with the `id` of the entry they match.
+    pub duplicates: Vec<(Secret, Uuid)>,
+}
+
+/// Split `incoming` into fresh entries and duplicates of `existing`.
+///
+/// Two entries are duplicates when their names match case-insensitively and
+/// their usernames are equal. Duplicates inside `incoming` itself are
+/// detected too: only the first occurrence is considered fresh.
+pub fn plan(existing: &[Secret], incoming: Vec<Secret>) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+    for secret in incoming {
+        let matched = existing
+            .iter()
+            .chain(plan.fresh.iter())
+            .find(|s| is_duplicate(s, &secret))
+            .map(|s| s.id);
+        match matched {
+            Some(id) => plan.duplicates.push((secret, id)),
+            None => plan.fresh.push(secret),
+        }
+    }
+    plan
+}
+
+fn is_duplicate(a: &Secret, b: &Secret) -> bool {
+    a.name.trim().eq_ignore_ascii_case(b.name.trim())
+        && a.username.as_deref().unwrap_or("") == b.username.as_deref().unwrap_or("")
+}
+
+/// Fields shared by every format, collected before building a `Secret`.
+#[derive(Debug, Default)]
+pub(crate) struct Draft {
+    pub name: String,
+    pub kind: EntryKind,
+    pub username: String,
+    pub password: String,
+    pub url: String,
+    pub notes: String,
+    pub otp: String,
+    pub tags: Vec<String>,
+    /// Folder path, levels separated by `/`.
+    pub group: String,
+    pub fields: Vec<CustomField>,
+}
+
+impl Draft {
+    /// Build the secret. Empty strings become `None`; an OTP value that does
+    /// not parse is kept as a hidden custom field instead of being lost.
+    pub fn into_secret(self) -> Secret {
+        let mut secret = Secret::new(self.name.trim(), self.password);
+        secret.kind = self.kind;
+        secret.username = non_empty(self.username);
+        secret.url = non_empty(self.url);
+        secret.notes = non_empty(self.notes).map(SecretString::new);
+        secret.tags = self.tags;
+        secret.group = group::normalize(&self.group);
+        secret.fields = self.fields;
+
+        if !self.otp.trim().is_empty() {
+            match Otp::parse(&self.otp) {
+                Ok(otp) => secret.otp = Some(otp),
+                Err(_) => secret
+                    .fields
+                    .push(CustomField::new("OTP", self.otp, FieldKind::Hidden)),
+            }
+        }
+        secret
+    }
+
+    /// Set the field `label` of the draft's kind (see `EntryKind::schema`),
+    /// unless `value` is blank.
+    pub fn set_detail(&mut self, label: &str, value: impl Into<String>) {
+        let value = value.into();
+        let Some(spec) = self.kind.schema().iter().find(|s| s.label == label) else {
+            return;
+        };
+        if !value.trim().is_empty() {
+            self.fields
+                .push(CustomField::new(spec.label, value.trim(), spec.kind));
+        }
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    let trimmed = s.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// The whole export file, wiped from memory once parsed.
+pub(crate) fn read_file(path: &Path) -> Result<SecretString, CoreError> {
+    std::fs::read_to_string(path)
+        .map(SecretString::new)
+        .map_err(|e| CoreError::Import(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login(name: &str, user: &str) -> Secret {
+        let mut s = Secret::new(name, "pwd");
+        s.username = Some(user.to_string());
+        s
+    }
+
+    #[test]
+    fn test_plan_detects_duplicates() {
+        let existing = vec![login("GitHub", "jb")];
+        let incoming = vec![
+            login("github", "jb"),
+            login("GitHub", "other"),
+            login("AWS", "root"),
+            login("aws", "root"),
+        ];
+
+        let plan = plan(&existing, incoming);
+        let fresh: Vec<_> = plan.fresh.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(fresh, ["GitHub", "AWS"]);
+        assert_eq!(plan.duplicates.len(), 2);
+        assert_eq!(plan.duplicates[0].1, existing[0].id);
+        assert_eq!(plan.duplicates[1].1, plan.fresh[1].id);
+    }
+
+    #[test]
+    fn test_draft_bad_otp_becomes_field() {
+        let draft = Draft {
+            name: " Site ".to_string(),
+            password: "p".to_string(),
+            otp: "not a seed!".to_string(),
+            ..Default::default()
+        };
+        let s = draft.into_secret();
+        assert_eq!(s.name, "Site");
+        assert!(s.otp.is_none());
+        assert_eq!(s.fields[0].label, "OTP");
+        assert!(s.username.is_none());
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use zeroize::Zeroize;
+
+use super::{Draft, Imported, Importer, Skipped};
+use crate::core::{
+    error::CoreError,
+    secret::{CustomField, FieldKind, Secret, SecretString},
+};
+
+/// A `pass` (password-store) directory, decrypted through the local `gpg`.
+///
+/// Each `foo/bar/entry.gpg` becomes a secret named `entry` in group `foo/bar`,
+/// also tagged `foo/bar`. The first line of the file is the password;
+/// `key: value` lines fill the username, URL and custom fields; other lines
+/// go to the notes.
+pub struct PassStore;
+
+impl Importer for PassStore {
+    fn import(&self, path: &Path) -> Result<Imported, CoreError> {
+        import_with(path, gpg_decrypt)
+    }
+}
+
+/// Walk the store, decrypting every `.gpg` file with `decrypt`.
+pub(super) fn import_with(
+    root: &Path,
+    decrypt: impl Fn(&Path) -> Result<SecretString, String>,
+) -> Result<Imported, CoreError> {
+    if !root.is_dir() {
+        return Err(CoreError::Import(format!(
+            "{}: not a password-store directory",
+            root.display()
+        )));
+    }
+
+    let mut files = Vec::new();
+    collect(root, &mut files).map_err(|e| CoreError::Import(e.to_string()))?;
+
+    let mut imported = Imported::default();
+    for file in files {
+        let relative = file.strip_prefix(root).unwrap_or(&file);
+        let source = relative.display().to_string();
+        match decrypt(&file) {
+            Ok(plaintext) if plaintext.trim().is_empty() => imported.skipped.push(Skipped {
+                source,
+                reason: "empty file".to_string(),
+            }),
+            Ok(plaintext) => imported.secrets.push(parse_entry(relative, &plaintext)),
+            Err(reason) => imported.skipped.push(Skipped { source, reason }),
+        }
+    }
+    Ok(imported)
+}
+
+/// Collect `.gpg` files, sorted, skipping hidden entries such as `.git`.
+fn collect(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| {
+            !p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "gpg") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn gpg_decrypt(file: &Path) -> Result<SecretString, String> {
+    let output = Command::new("gpg")
+        .args(["--quiet", "--yes", "--decrypt"])
+        .arg(file)
+        .output()
+        .map_err(|e| format!("cannot run gpg: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().last().unwrap_or("gpg failed").trim();
+        return Err(reason.to_string());
+    }
+    String::from_utf8(output.stdout)
+        .map(SecretString::new)
+        .map_err(|e| {
+            e.into_bytes().zeroize();
+            "not valid UTF-8".to_string()
+        })
+}
+
+/// Translate one decrypted entry, `relative` being its path inside the store.
+fn parse_entry(relative: &Path, plaintext: &str) -> Secret {
+    let mut lines = plaintext.lines();
+    let mut draft = Draft {
+        name: relative
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        password: lines.next().unwrap_or("").to_string(),
+        ..Default::default()
+    };
+
+    if let Some(dir) = relative.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let levels: Vec<_> = dir.iter().map(|c| c.to_string_lossy()).collect();
+        draft.tags.push(levels.join("/"));
+        draft.group = levels.join("/");
+    }
+
+    let mut notes = Vec::new();
+    for line in lines {
+        if line.trim_start().starts_with("otpauth://") {
+            draft.otp = line.trim().to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':').filter(|(k, _)| is_key(k)) else {
+            notes.push(line);
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user" | "username" | "login" | "email" if draft.username.is_empty() => {
+                draft.username = value
+            }
+            "url" | "website" | "site" if draft.url.is_empty() => draft.url = value,
+            "otp" | "totp" if draft.otp.is_empty() => draft.otp = value,
+            _ => draft
+                .fields
+                .push(CustomField::new(key.trim(), value, FieldKind::Text)),
+        }
+    }
+    draft.notes = notes.join("\n");
+    draft.into_secret()
+}
+
+/// A `key: value` key is one or two plain words, so that prose with a colon
+/// in it stays in the notes.
+fn is_key(key: &str) -> bool {
+    let key = key.trim();
+    !key.is_empty()
+        && key.split_whitespace().count() <= 2
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Stand-in for gpg: the "encrypted" files hold plaintext.
+    fn fake_decrypt(file: &Path) -> Result<SecretString, String> {
+        let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+        match text.strip_prefix("FAIL ") {
+            Some(reason) => Err(reason.trim().to_string()),
+            None => Ok(SecretString::new(text)),
+        }
+    }
+
+    #[test]
+    fn test_walk_store() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("web/github.com")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".gpg-id"), "ABCDEF\n").unwrap();
+        std::fs::write(root.join(".git/HEAD.gpg"), "ignored").unwrap();
+        std::fs::write(
+            root.join("web/github.com/jb.gpg"),
+            "s3cr3t\nlogin: jb@example.com\nurl: https://github.com\n\
+             Recovery: abcd-efgh\nRemember: the 2FA device is in the drawer\n\
+             otpauth://totp/GitHub?secret=JBSWY3DPEHPK3PXP\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("wifi.gpg"), "door\n").unwrap();
+        std::fs::write(
+            root.join("broken.gpg"),
+            "FAIL decryption failed: No secret key",
+        )
+        .unwrap();
+        std::fs::write(root.join("empty.gpg"), "\n").unwrap();
+        std::fs::write(root.join("README.md"), "not a secret").unwrap();
+
+        let imported = import_with(root, fake_decrypt).unwrap();
+        let names: Vec<_> = imported.secrets.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["jb", "wifi"]);
+
+        let gh = &imported.secrets[0];
+        assert_eq!(gh.password.as_str(), "s3cr3t");
+        assert_eq!(gh.username.as_deref(), Some("jb@example.com"));
+        assert_eq!(gh.url.as_deref(), Some("https://github.com"));
+        assert_eq!(gh.tags, ["web/github.com"]);
+        assert_eq!(gh.group.as_deref(), Some("web/github.com"));
+        assert!(gh.otp.is_some());
+        assert_eq!(gh.fields.len(), 2);
+        assert!(gh.notes.is_none());
+
+        let wifi = &imported.secrets[1];
+        assert!(wifi.tags.is_empty());
+        assert!(wifi.group.is_none());
+
+        let skipped: Vec<_> = imported.skipped.iter().map(|s| s.source.as_str()).collect();
+        assert_eq!(skipped, ["broken.gpg", "empty.gpg"]);
+        assert_eq!(
+            imported.skipped[0].reason,
+            "decryption failed: No secret key"
+        );
+    }
+
+    #[test]
+    fn test_free_text_goes_to_notes() {
+        let s = parse_entry(
+            Path::new("bank.gpg"),
+            "pw\nCall them at 9:00 if locked out, ask for the fraud desk.\n",
+        );
+        assert!(s.fields.is_empty());
+        assert_eq!(
+            s.notes.as_deref().map(String::as_str),
+            Some("Call them at 9:00 if locked out, ask for the fraud desk.")
+        );
+    }
+
+    #[test]
+    fn test_not_a_directory() {
+        let dir = tempdir().unwrap();
+        assert!(import_with(&dir.path().join("missing"), fake_decrypt).is_err());
+    }
+}
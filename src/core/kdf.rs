@@ -0,0 +1,230 @@
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use serdevault::VaultFile;
+
+use super::error::CoreError;
+
+/// serdevault header layout: magic (4) + version (1) + salt (32), then the
+/// three Argon2id costs as little-endian u32.
+const MAGIC: &[u8; 4] = b"SVLT";
+const PARAMS_OFFSET: usize = 4 + 1 + 32;
+const PARAMS_END: usize = PARAMS_OFFSET + 12;
+
+/// Below this memory cost (19 MiB, the OWASP minimum) a suggestion is
+/// considered too weak to recommend.
+pub const MIN_RECOMMENDED_MEMORY_KIB: u32 = 19 * 1024;
+
+/// Memory cost used for the calibration run of `benchmark_rate`.
+const CALIBRATION_MEMORY_KIB: u32 = 32 * 1024;
+
+/// Argon2id cost parameters of a vault file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory cost in KiB.
+    pub m_cost: u32,
+    /// Number of passes.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// serdevault's defaults (64 MiB, 3 passes, 1 lane), used for new vaults.
+    pub const DEFAULT: KdfParams = KdfParams {
+        m_cost: 65536,
+        t_cost: 3,
+        p_cost: 1,
+    };
+
+    /// Check the parameters are accepted by Argon2id.
+    pub fn validate(&self) -> Result<(), CoreError> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+            .map(|_| ())
+            .map_err(|e| CoreError::Kdf(e.to_string()))
+    }
+
+    /// Apply these parameters to a `VaultFile`, so its next save uses them.
+    pub fn apply(&self, vault: VaultFile) -> VaultFile {
+        vault.with_params(self.m_cost, self.t_cost, self.p_cost)
+    }
+
+    /// Time one key derivation with these parameters.
+    pub fn measure(&self) -> Result<Duration, CoreError> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| CoreError::Kdf(e.to_string()))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let mut key = [0u8; 32];
+
+        let start = Instant::now();
+        argon2
+            .hash_password_into(b"valt-benchmark", &[0u8; 32], &mut key)
+            .map_err(|e| CoreError::Kdf(e.to_string()))?;
+        Ok(start.elapsed())
+    }
+
+    /// Read the parameters stored in the (unencrypted) header of a vault file.
+    /// No password is needed.
+    pub fn read(path: &Path) -> Result<Self, CoreError> {
+        let mut header = [0u8; PARAMS_END];
+        std::fs::File::open(path)
+            .and_then(|mut f| f.read_exact(&mut header))
+            .map_err(|e| CoreError::Kdf(format!("cannot read vault header: {e}")))?;
+
+        if &header[..4] != MAGIC {
+            return Err(CoreError::Kdf("not a valt vault file".to_string()));
+        }
+
+        let word = |i: usize| {
+            let o = PARAMS_OFFSET + 4 * i;
+            u32::from_le_bytes([header[o], header[o + 1], header[o + 2], header[o + 3]])
+        };
+        Ok(Self {
+            m_cost: word(0),
+            t_cost: word(1),
+            p_cost: word(2),
+        })
+    }
+}
+
+impl fmt::Display for KdfParams {
+    /// e.g. `64 MiB, 3 passes, 1 lane`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} MiB, {} pass{}, {} lane{}",
+            self.m_cost / 1024,
+            self.t_cost,
+            if self.t_cost == 1 { "" } else { "es" },
+            self.p_cost,
+            if self.p_cost == 1 { "" } else { "s" },
+        )
+    }
+}
+
+/// Open a `VaultFile` that keeps the Argon2 parameters the file was written
+/// with, instead of silently falling back to serdevault's defaults on the
+/// next save. Missing or unreadable files get the defaults.
+pub fn open_vault_file(path: &Path, password: &str) -> VaultFile {
+    let params = KdfParams::read(path).unwrap_or(KdfParams::DEFAULT);
+    params.apply(VaultFile::open(path, password))
+}
+
+/// Measure this machine's Argon2id throughput, in seconds per KiB-pass, on a
+/// single lane.
+pub fn benchmark_rate() -> Result<f64, CoreError> {
+    let calibration = KdfParams {
+        m_cost: CALIBRATION_MEMORY_KIB,
+        t_cost: 1,
+        p_cost: 1,
+    };
+    // Warm-up run so page faults of the first allocation don't skew the result.
+    calibration.measure()?;
+    let elapsed = calibration.measure()?;
+    Ok(elapsed.as_secs_f64() / CALIBRATION_MEMORY_KIB as f64)
+}
+
+/// Suggest parameters whose derivation takes about `target` on a machine
+/// with the given throughput (see `benchmark_rate`).
+///
+/// Memory is favoured over passes, as RFC 9106 recommends: the memory cost
+/// grows up to `max_memory_kib`, and only then are passes added. Memory is
+/// rounded down to whole MiB.
+pub fn suggest(rate: f64, target: Duration, max_memory_kib: u32, p_cost: u32) -> KdfParams {
+    let budget = (target.as_secs_f64() / rate).max(1.0);
+    let floor = 8 * p_cost.max(1);
+
+    let m_cost = (budget.min(max_memory_kib as f64) as u32 / 1024 * 1024).max(floor);
+    let t_cost = ((budget / m_cost as f64) as u32).max(1);
+
+    KdfParams {
+        m_cost,
+        t_cost,
+        p_cost: p_cost.max(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdevault::VaultFile;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_params() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        VaultFile::open(&path, "pwd")
+            .with_params(16, 2, 1)
+            .save(&"data")
+            .unwrap();
+
+        let params = KdfParams::read(&path).unwrap();
+        assert_eq!(
+            params,
+            KdfParams {
+                m_cost: 16,
+                t_cost: 2,
+                p_cost: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_not_a_vault() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("junk");
+        std::fs::write(&path, [0u8; 64]).unwrap();
+        assert!(KdfParams::read(&path).is_err());
+        assert!(KdfParams::read(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_suggest_prefers_memory() {
+        // 1 µs per KiB-pass, 1 s target → budget of 1,000,000 KiB-passes.
+        let p = suggest(1e-6, Duration::from_secs(1), 4 * 1024 * 1024, 1);
+        assert_eq!(p.t_cost, 1);
+        assert_eq!(p.m_cost, 976 * 1024);
+    }
+
+    #[test]
+    fn test_suggest_adds_passes_when_memory_capped() {
+        let p = suggest(1e-6, Duration::from_secs(1), 256 * 1024, 1);
+        assert_eq!(p.m_cost, 256 * 1024);
+        assert_eq!(p.t_cost, 3);
+    }
+
+    #[test]
+    fn test_suggest_is_valid_on_slow_machines() {
+        let p = suggest(1.0, Duration::from_millis(10), 1024 * 1024, 2);
+        assert!(p.validate().is_ok());
+        assert!(p.m_cost < MIN_RECOMMENDED_MEMORY_KIB);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(KdfParams::DEFAULT.validate().is_ok());
+        let bad = KdfParams {
+            m_cost: 1,
+            t_cost: 0,
+            p_cost: 1,
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_open_vault_file_keeps_params() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        VaultFile::open(&path, "pwd")
+            .with_params(16, 2, 1)
+            .save(&"data")
+            .unwrap();
+
+        open_vault_file(&path, "pwd").save(&"again").unwrap();
+        assert_eq!(KdfParams::read(&path).unwrap().m_cost, 16);
+    }
+}
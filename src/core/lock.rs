@@ -0,0 +1,63 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::error::CoreError;
+use super::manager::suffixed_path;
+
+/// How long to wait for another writer before giving up. A save costs one
+/// Argon2 derivation, so a second or two is the common case.
+const LOCK_WAIT: Duration = Duration::from_secs(10);
+
+/// An exclusive advisory lock held while the vault is read, modified and
+/// written back, so that two processes never interleave their writes.
+///
+/// The lock is taken on `<vault>.lock` rather than on the vault itself: every
+/// save renames a new file over the vault, which would leave a lock on the
+/// old one behind. Released when dropped.
+pub struct VaultLock {
+    _file: File,
+}
+
+impl VaultLock {
+    pub fn acquire(vault_path: &Path) -> Result<Self, CoreError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(suffixed_path(vault_path, "lock"))
+            .map_err(CoreError::Lock)?;
+
+        let deadline = Instant::now() + LOCK_WAIT;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(TryLockError::WouldBlock) => return Err(CoreError::Busy),
+                Err(TryLockError::Error(e)) => return Err(CoreError::Lock(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_exclusive_until_dropped() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path().join("vault.svlt");
+
+        let held = VaultLock::acquire(&vault).unwrap();
+        // A second handle on the lock file stands in for another process.
+        let other = File::open(suffixed_path(&vault, "lock")).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        drop(held);
+        assert!(other.try_lock().is_ok());
+    }
+}
@@ -1,17 +1,88 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
+use chrono::{DateTime, TimeDelta, Utc};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use serdevault::VaultFile;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use super::{error::CoreError, secret::Secret, vault_data::VaultData};
+use super::{
+    audit::{self, Finding},
+    backup::{self, Retention},
+    breach::PwnedFile,
+    error::CoreError,
+    kdf::KdfParams,
+    lock::VaultLock,
+    memory,
+    merge::{self, MergeReport},
+    otp::OtpKind,
+    secret::{FieldKind, PasswordEntry, Secret, SecretString},
+    sync::{MergeOutcome, Pulled, Repo},
+    undo::{Change, History},
+    vault_data::{Tombstone, VaultData},
+};
+
+/// Message of the commits made by `save` and `sync`.
+const COMMIT_MESSAGE: &str = "Update vault";
+
+/// Days a deleted secret stays in the trash unless configured otherwise.
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Maximum number of previous passwords kept per secret.
+pub const PASSWORD_HISTORY_LIMIT: usize = 10;
 
 /// High-level interface to the encrypted vault.
 pub struct VaultManager {
     vault: VaultFile,
     data: VaultData,
-    /// When set, `save()` copies the current file to `<path>.bak` before each write.
+    /// On-disk location of the vault. When set, `save()` snapshots the current
+    /// file into the backup directory before each write, and `rekey()`
+    /// becomes available.
     path: Option<PathBuf>,
+    /// How many snapshots `save()` keeps; `None` takes none.
+    backups: Option<Retention>,
+    /// The vault file as we last read or wrote it, to notice other writers.
+    fingerprint: Option<Fingerprint>,
+    /// Commit the vault after each save if its directory is a git repository.
+    git_commits: bool,
+    /// How long secrets stay in the trash; `None` keeps them until purged.
+    trash_retention: Option<TimeDelta>,
+    /// Changes made through this manager, for `undo` and `redo`.
+    history: History,
+}
+
+/// Identifies one version of the vault file. Size and mtime are enough to
+/// tell it has not changed; the hash settles it when they differ.
+#[derive(Debug, Clone, PartialEq)]
+struct Fingerprint {
+    len: u64,
+    modified: Option<SystemTime>,
+    sha256: [u8; 32],
+}
+
+impl Fingerprint {
+    fn read(path: &Path) -> std::io::Result<Self> {
+        let meta = std::fs::metadata(path)?;
+        Ok(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+            sha256: Sha256::digest(std::fs::read(path)?).into(),
+        })
+    }
+
+    /// Whether the file at `path` is still this version. A missing file is not.
+    fn matches(&self, path: &Path) -> bool {
+        let Ok(meta) = std::fs::metadata(path) else {
+            return false;
+        };
+        if meta.len() == self.len && meta.modified().ok() == self.modified {
+            return true;
+        }
+        std::fs::read(path)
+            .is_ok_and(|bytes| <[u8; 32]>::from(Sha256::digest(bytes)) == self.sha256)
+    }
 }
 
 impl VaultManager {
@@ -19,10 +90,16 @@ impl VaultManager {
     /// password is wrong or the file is corrupted.
     pub fn open(vault: VaultFile) -> Result<Self, CoreError> {
         let data = vault.load::<VaultData>()?;
+        lock_in_memory(&data);
         Ok(Self {
             vault,
             data,
             path: None,
+            backups: Some(Retention::default()),
+            fingerprint: None,
+            git_commits: false,
+            trash_retention: Some(TimeDelta::days(DEFAULT_TRASH_RETENTION_DAYS)),
+            history: History::default(),
         })
     }
 
@@ -35,6 +112,11 @@ impl VaultManager {
             vault,
             data,
             path: None,
+            backups: Some(Retention::default()),
+            fingerprint: None,
+            git_commits: false,
+            trash_retention: Some(TimeDelta::days(DEFAULT_TRASH_RETENTION_DAYS)),
+            history: History::default(),
         })
     }
 
@@ -48,14 +130,60 @@ impl VaultManager {
         }
     }
 
-    /// Enable automatic backup: before every `save()`, the current vault file
-    /// is copied to `<path>.bak`. Call this right after construction.
-    pub fn with_backup_path(mut self, path: PathBuf) -> Self {
-        self.path = Some(path);
+    /// `open` the vault file at `path`, keeping the path: before every
+    /// `save()`, the current file is snapshotted into `<path>.backups/` (see
+    /// `backup`), and changes made by other processes are noticed (see
+    /// `begin`). Also required by `rekey()` and `verify_password()`.
+    ///
+    /// The file is fingerprinted under the vault lock before it is
+    /// decrypted, so that a write by another process in between is noticed
+    /// by the next change instead of being taken for the version loaded.
+    pub fn open_at(vault: VaultFile, path: PathBuf) -> Result<Self, CoreError> {
+        Self::at(vault, path, Self::open)
+    }
+
+    /// `open_or_create` the vault file at `path`, keeping the path as
+    /// `open_at` does.
+    pub fn open_or_create_at(vault: VaultFile, path: PathBuf) -> Result<Self, CoreError> {
+        Self::at(vault, path, Self::open_or_create)
+    }
+
+    fn at(
+        vault: VaultFile,
+        path: PathBuf,
+        open: fn(VaultFile) -> Result<Self, CoreError>,
+    ) -> Result<Self, CoreError> {
+        let _lock = VaultLock::acquire(&path)?;
+        let fingerprint = Fingerprint::read(&path).ok();
+        let mut manager = open(vault)?;
+        // A vault that did not exist was just written by `create`.
+        manager.fingerprint = fingerprint.or_else(|| Fingerprint::read(&path).ok());
+        manager.path = Some(path);
+        Ok(manager)
+    }
+
+    /// Set how many snapshots `save()` keeps, or turn them off with `None`.
+    /// The path set by `open_at` is still used by `rekey()` and friends.
+    pub fn with_backups(mut self, retention: Option<Retention>) -> Self {
+        self.backups = retention;
+        self
+    }
+
+    /// Commit the vault file after every save when its directory is a git
+    /// repository (see `sync`). Requires `open_at`.
+    pub fn with_git_commits(mut self, enabled: bool) -> Self {
+        self.git_commits = enabled;
+        self
+    }
+
+    /// Set how long deleted secrets stay in the trash before `save()` purges
+    /// them, or keep them until purged by hand with `None`.
+    pub fn with_trash_retention(mut self, retention: Option<TimeDelta>) -> Self {
+        self.trash_retention = retention;
         self
     }
 
-    /// All secrets, in insertion order.
+    /// All secrets, in insertion order, except those in the trash.
     pub fn list(&self) -> &[Secret] {
         &self.data.secrets
     }
@@ -65,16 +193,40 @@ impl VaultManager {
         self.data.secrets.iter().find(|s| s.id == id)
     }
 
-    /// Fuzzy search over `name`, `username`, `url`, and `tags`.
+    /// Fuzzy search over `name`, `username`, `url`, `tags`, and custom fields
+    /// (labels always, values unless the field is hidden).
     pub fn search(&self, query: &str) -> Vec<&Secret> {
+        Self::rank(&self.data.secrets, query)
+    }
+
+    /// Secrets in the trash, most recently deleted first.
+    pub fn trash(&self) -> Vec<&Secret> {
+        let mut trash: Vec<_> = self.data.trash.iter().collect();
+        trash.sort_by_key(|s| std::cmp::Reverse(s.deleted_at));
+        trash
+    }
+
+    /// `search` over the trash.
+    pub fn search_trash(&self, query: &str) -> Vec<&Secret> {
         if query.is_empty() {
-            return self.data.secrets.iter().collect();
+            return self.trash();
+        }
+        Self::rank(&self.data.trash, query)
+    }
+
+    /// When the secret `id`, in the trash, will be purged automatically.
+    pub fn purge_due(&self, id: Uuid) -> Option<chrono::DateTime<Utc>> {
+        let secret = self.data.trash.iter().find(|s| s.id == id)?;
+        Some(secret.deleted_at? + self.trash_retention?)
+    }
+
+    fn rank<'a>(secrets: &'a [Secret], query: &str) -> Vec<&'a Secret> {
+        if query.is_empty() {
+            return secrets.iter().collect();
         }
 
         let matcher = SkimMatcherV2::default();
-        let mut scored: Vec<(i64, &Secret)> = self
-            .data
-            .secrets
+        let mut scored: Vec<(i64, &Secret)> = secrets
             .iter()
             .filter_map(|s| {
                 let score = Self::match_score(&matcher, s, query);
@@ -82,62 +234,673 @@ impl VaultManager {
             })
             .collect();
 
-        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
         scored.into_iter().map(|(_, s)| s).collect()
     }
 
+    /// Health check of every secret: reused, weak and old passwords (not
+    /// changed in more than `max_age_days`), duplicate names and missing
+    /// usernames or URLs. See `audit::audit`.
+    pub fn audit(&self, max_age_days: i64) -> Vec<Finding> {
+        audit::audit(&self.data.secrets, max_age_days, Utc::now())
+    }
+
     /// Add a new secret and persist the vault.
     pub fn add(&mut self, secret: Secret) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        let change = Change {
+            label: format!("add of '{}'", secret.name),
+            id: secret.id,
+            before: None,
+            after: Some(secret.clone()),
+        };
         self.data.secrets.push(secret);
-        self.save()
+        self.save()?;
+        self.history.record(change);
+        Ok(())
+    }
+
+    /// Add several secrets and persist the vault once. Each addition is
+    /// undone on its own.
+    pub fn add_all(&mut self, secrets: Vec<Secret>) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        let changes: Vec<_> = secrets
+            .iter()
+            .map(|secret| Change {
+                label: format!("add of '{}'", secret.name),
+                id: secret.id,
+                before: None,
+                after: Some(secret.clone()),
+            })
+            .collect();
+        self.data.secrets.extend(secrets);
+        self.save()?;
+        for change in changes {
+            self.history.record(change);
+        }
+        Ok(())
     }
 
     /// Replace the secret with the given `id` and persist the vault.
     /// The `updated.id` field is ignored — the original `id` is preserved.
-    pub fn update(&mut self, id: Uuid, mut updated: Secret) -> Result<(), CoreError> {
+    ///
+    /// `based_on` is the `updated_at` of the version `updated` was edited
+    /// from. If another process changed the secret since, the edit is refused
+    /// with `Stale` rather than overwriting theirs.
+    ///
+    /// Password history is owned by the vault: `updated.history` is ignored
+    /// and, if the password changed, the old one is pushed onto the existing
+    /// history.
+    pub fn update(
+        &mut self,
+        id: Uuid,
+        mut updated: Secret,
+        based_on: DateTime<Utc>,
+    ) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
         let entry = self
             .data
             .secrets
             .iter_mut()
             .find(|s| s.id == id)
             .ok_or(CoreError::NotFound(id))?;
+        if entry.updated_at > based_on {
+            return Err(CoreError::Stale(entry.name.clone()));
+        }
 
+        let before = entry.clone();
         updated.id = id;
         updated.created_at = entry.created_at;
+        updated.history = std::mem::take(&mut entry.history);
+        if updated.password != entry.password {
+            retire_password(&mut updated.history, std::mem::take(&mut entry.password));
+            updated.breach_count = None;
+        } else {
+            updated.breach_count = entry.breach_count;
+        }
         updated.touch();
+        let change = Change {
+            label: format!("edit of '{}'", updated.name),
+            id,
+            before: Some(before),
+            after: Some(updated.clone()),
+        };
         *entry = updated;
 
-        self.save()
+        self.save()?;
+        self.history.record(change);
+        Ok(())
+    }
+
+    /// Swap the current password of secret `id` with entry `index` of its
+    /// history (0 = most recently retired). The current password is retired
+    /// in turn, so a restore can itself be undone the same way.
+    pub fn restore_password(&mut self, id: Uuid, index: usize) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        let entry = self
+            .data
+            .secrets
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or(CoreError::NotFound(id))?;
+
+        if index >= entry.history.len() {
+            return Err(CoreError::HistoryIndex(index));
+        }
+
+        let before = entry.clone();
+        let restored = entry.history.remove(index);
+        let current = std::mem::replace(&mut entry.password, restored.password);
+        retire_password(&mut entry.history, current);
+        entry.breach_count = None;
+        entry.touch();
+        let change = Change {
+            label: format!("password restore of '{}'", entry.name),
+            id,
+            before: Some(before),
+            after: Some(entry.clone()),
+        };
+
+        self.save()?;
+        self.history.record(change);
+        Ok(())
     }
 
-    /// Remove the secret with the given `id` and persist the vault.
+    /// Move the secret with the given `id` to the trash and persist the
+    /// vault. It stays there until `restore`d, `purge`d, or expired (see
+    /// `with_trash_retention`).
     pub fn delete(&mut self, id: Uuid) -> Result<(), CoreError> {
-        let before = self.data.secrets.len();
-        self.data.secrets.retain(|s| s.id != id);
+        let _lock = self.begin()?;
+        let idx = self
+            .data
+            .secrets
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or(CoreError::NotFound(id))?;
+
+        let mut secret = self.data.secrets.remove(idx);
+        let before = secret.clone();
+        secret.deleted_at = Some(Utc::now());
+        let change = Change {
+            label: format!("deletion of '{}'", secret.name),
+            id,
+            before: Some(before),
+            after: Some(secret.clone()),
+        };
+        self.data.trash.push(secret);
+        self.save()?;
+        self.history.record(change);
+        Ok(())
+    }
 
-        if self.data.secrets.len() == before {
+    /// What `undo` would revert, e.g. "edit of 'GitHub'".
+    pub fn undo_label(&self) -> Option<&str> {
+        self.history.next_undo().map(|c| c.label.as_str())
+    }
+
+    /// What `redo` would make again.
+    pub fn redo_label(&self) -> Option<&str> {
+        self.history.next_redo().map(|c| c.label.as_str())
+    }
+
+    /// Revert the last change made through this manager (an add, edit,
+    /// password restore, deletion or restore from the trash) and persist the
+    /// vault. Returns what was undone, or `None` if there is
+    /// nothing left to undo.
+    pub fn undo(&mut self) -> Result<Option<String>, CoreError> {
+        let Some(mut change) = self.history.pop_undo() else {
+            return Ok(None);
+        };
+        match self.apply(change.id, change.after.as_ref(), change.before.as_ref()) {
+            Ok(applied) => {
+                let label = change.label.clone();
+                self.history.undone_to(change.id, applied.clone());
+                change.before = applied;
+                self.history.push_undone(change);
+                Ok(Some(label))
+            }
+            Err(e) => {
+                self.history.push_done(change);
+                Err(e)
+            }
+        }
+    }
+
+    /// Make the last undone change again and persist the vault.
+    pub fn redo(&mut self) -> Result<Option<String>, CoreError> {
+        let Some(mut change) = self.history.pop_redo() else {
+            return Ok(None);
+        };
+        match self.apply(change.id, change.before.as_ref(), change.after.as_ref()) {
+            Ok(applied) => {
+                let label = change.label.clone();
+                self.history.redone_to(change.id, applied.clone());
+                change.after = applied;
+                self.history.push_done(change);
+                Ok(Some(label))
+            }
+            Err(e) => {
+                self.history.push_undone(change);
+                Err(e)
+            }
+        }
+    }
+
+    /// Put secret `id`, expected to be as `current` left it, in `state`:
+    /// live, in the trash, or gone (leaving a tombstone). Fails with `Stale`
+    /// if another process changed it since. The secret is stamped as changed
+    /// now, so that the undo or redo wins over older copies when merged;
+    /// returns it as stamped.
+    fn apply(
+        &mut self,
+        id: Uuid,
+        current: Option<&Secret>,
+        state: Option<&Secret>,
+    ) -> Result<Option<Secret>, CoreError> {
+        let _lock = self.begin()?;
+        let found = self
+            .data
+            .secrets
+            .iter()
+            .chain(&self.data.trash)
+            .find(|s| s.id == id);
+        if found.map(Secret::changed_at) != current.map(Secret::changed_at) {
+            let name = found.or(current).map_or(String::new(), |s| s.name.clone());
+            return Err(CoreError::Stale(name));
+        }
+        let applied = state.map(|secret| {
+            let mut secret = secret.clone();
+            if let Some(found) = found {
+                keep_usage_state(&mut secret, found);
+            }
+            secret.touch();
+            secret
+        });
+        let live = self.data.secrets.iter().position(|s| s.id == id);
+        self.data.trash.retain(|s| s.id != id);
+
+        match applied.clone() {
+            Some(secret) => {
+                self.data.tombstones.retain(|t| t.id != id);
+                match (secret.deleted_at.is_none(), live) {
+                    (true, Some(idx)) => self.data.secrets[idx] = secret,
+                    (true, None) => self.data.secrets.push(secret),
+                    (false, _) => {
+                        self.data.secrets.retain(|s| s.id != id);
+                        self.data.trash.push(secret);
+                    }
+                }
+            }
+            None => {
+                self.data.secrets.retain(|s| s.id != id);
+                self.data.tombstones.push(Tombstone {
+                    id,
+                    deleted_at: Utc::now(),
+                });
+            }
+        }
+        self.save()?;
+        Ok(applied)
+    }
+
+    /// Bring the secret `id` back from the trash and persist the vault.
+    pub fn restore(&mut self, id: Uuid) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        let idx = self
+            .data
+            .trash
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or(CoreError::NotFound(id))?;
+
+        let mut secret = self.data.trash.remove(idx);
+        let before = secret.clone();
+        secret.deleted_at = None;
+        // Restoring is a change in its own right, newer than the deletion.
+        secret.touch();
+        let change = Change {
+            label: format!("restore of '{}'", secret.name),
+            id,
+            before: Some(before),
+            after: Some(secret.clone()),
+        };
+        self.data.secrets.push(secret);
+        self.save()?;
+        self.history.record(change);
+        Ok(())
+    }
+
+    /// Delete the secret `id` from the trash for good and persist the vault.
+    /// A tombstone is left behind so that `merge` deletes it from other
+    /// copies too.
+    pub fn purge(&mut self, id: Uuid) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        if !self.data.trash.iter().any(|s| s.id == id) {
             return Err(CoreError::NotFound(id));
         }
+        purge_where(&mut self.data, |s| s.id == id);
+        self.save()
+    }
+
+    /// Purge everything in the trash. Returns how many secrets were purged.
+    pub fn empty_trash(&mut self) -> Result<usize, CoreError> {
+        let _lock = self.begin()?;
+        let purged = purge_where(&mut self.data, |_| true);
+        if purged > 0 {
+            self.save()?;
+        }
+        Ok(purged)
+    }
+
+    /// Advance the HOTP counter of the secret with the given `id` and persist
+    /// the vault. This is bookkeeping rather than an edit, so `updated_at` is
+    /// left untouched. No-op for TOTP seeds.
+    pub fn advance_otp(&mut self, id: Uuid) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        let entry = self
+            .data
+            .secrets
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or(CoreError::NotFound(id))?;
+
+        if let Some(otp) = &mut entry.otp {
+            otp.advance()?;
+        }
 
         self.save()
     }
 
-    /// Persist the current in-memory state to disk.
+    /// Fold another copy of the vault into this one and persist the result.
+    /// `base` is the copy both started from, if known. See `merge::merge`
+    /// for how differences are resolved.
+    pub fn merge(
+        &mut self,
+        other: &VaultManager,
+        base: Option<&VaultManager>,
+    ) -> Result<MergeReport, CoreError> {
+        let _lock = self.begin()?;
+        let tombstones = self.data.tombstones.clone();
+        let report = merge::merge(&mut self.data, &other.data, base.map(|b| &b.data));
+        if !report.is_empty() || self.data.tombstones != tombstones {
+            lock_in_memory(&self.data);
+            self.save()?;
+        }
+        Ok(report)
+    }
+
+    /// What `merge` would do, without changing anything.
+    pub fn preview_merge(&self, other: &VaultManager, base: Option<&VaultManager>) -> MergeReport {
+        merge::merge(&mut self.data.clone(), &other.data, base.map(|b| &b.data))
+    }
+
+    /// Bring the vault in line with `remote`: commit pending changes, pull,
+    /// and push the result. Requires `open_at` and a git repository
+    /// in the vault's directory.
     ///
-    /// If `with_backup_path` was called, copies the existing vault file to
-    /// `<path>.bak` before writing the new version. The backup reflects the
-    /// state just before the current save, so a single rollback is always
-    /// possible. Fails hard if the backup copy itself fails — a copy error
-    /// usually indicates a filesystem problem that would compromise the write
-    /// too.
-    pub fn save(&self) -> Result<(), CoreError> {
-        if let Some(ref path) = self.path {
-            if path.exists() {
-                let bak = bak_path(path);
-                std::fs::copy(path, &bak).map_err(CoreError::Backup)?;
+    /// When both sides changed the vault, git cannot merge the encrypted
+    /// file; the other side's version is decrypted with the current master
+    /// password instead, merged secret by secret against the version both
+    /// started from (see `merge::merge`), and the result is committed as the
+    /// merge. If it does not decrypt, the merge is aborted and nothing
+    /// changes.
+    pub fn sync(&mut self, remote: &str) -> Result<Pulled, CoreError> {
+        let _lock = self.begin()?;
+        let path = self.require_path()?.to_path_buf();
+        let repo = Repo::open(&path).ok_or_else(|| {
+            CoreError::Sync(format!(
+                "{} is not in a git repository (see `valt sync init`)",
+                path.display()
+            ))
+        })?;
+        repo.commit_vault(&path, COMMIT_MESSAGE)?;
+        repo.fetch(remote)?;
+
+        let pulled = match repo.upstream(remote)? {
+            None => Pulled::Nothing,
+            Some(upstream) => match repo.merge(&path, &upstream)? {
+                MergeOutcome::UpToDate => Pulled::Nothing,
+                MergeOutcome::Merged => {
+                    self.data = self.reload(&path).map_err(|_| {
+                        CoreError::Sync(
+                            "the pulled vault does not open with this master password; \
+                             unlock it again with the one set on the other machine"
+                                .to_string(),
+                        )
+                    })?;
+                    lock_in_memory(&self.data);
+                    self.fingerprint = Fingerprint::read(&path).ok();
+                    Pulled::Changes
+                }
+                MergeOutcome::Conflict => {
+                    let report = self.merge_theirs(&repo, &path)?;
+                    repo.commit_vault(&path, &format!("Merge vault from {upstream}"))?;
+                    Pulled::Merged(report)
+                }
+            },
+        };
+        repo.push(remote)?;
+        Ok(pulled)
+    }
+
+    /// Resolve a git conflict on the vault file: decrypt the other side and
+    /// the merge base, and fold the other side into ours. The merge is
+    /// aborted if the other side does not decrypt; a base that does not (it
+    /// may predate a password change) is left out.
+    fn merge_theirs(&mut self, repo: &Repo, path: &Path) -> Result<MergeReport, CoreError> {
+        let base = match repo.base(path) {
+            Ok(Some(bytes)) => self.load_version(path, bytes).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                repo.abort_merge();
+                return Err(e);
+            }
+        };
+        let theirs = repo
+            .theirs(path)
+            .and_then(|bytes| self.load_version(path, bytes))
+            .map_err(|e| match e {
+                CoreError::Vault(_) => CoreError::Sync(
+                    "the remote vault does not open with this master password".to_string(),
+                ),
+                e => e,
+            });
+        let theirs = match theirs {
+            Ok(theirs) => theirs,
+            Err(e) => {
+                repo.abort_merge();
+                return Err(e);
+            }
+        };
+        let report = merge::merge(&mut self.data, &theirs, base.as_ref());
+        lock_in_memory(&self.data);
+        self.write()?;
+        Ok(report)
+    }
+
+    /// Decrypt `bytes`, another version of the vault file, by writing them
+    /// over `path`. Only for use during a merge, which rewrites the file.
+    fn load_version(&mut self, path: &Path, bytes: Vec<u8>) -> Result<VaultData, CoreError> {
+        std::fs::write(path, bytes)
+            .map_err(|e| CoreError::Sync(format!("{}: {e}", path.display())))?;
+        Ok(self.vault.load::<VaultData>()?)
+    }
+
+    /// Look every password up in a Pwned Passwords dump, record the result
+    /// on each secret and persist the vault. Like `advance_otp`, this leaves
+    /// `updated_at` untouched. Returns the ids of compromised secrets.
+    ///
+    /// The lookups can take a while on a large dump, so they run before the
+    /// vault lock is taken. Secrets deleted or given a new password meanwhile
+    /// are left as they are.
+    pub fn check_breaches(&mut self, pwned: &mut PwnedFile) -> Result<Vec<Uuid>, CoreError> {
+        let mut counts = HashMap::new();
+        for secret in &self.data.secrets {
+            let count = if secret.password.is_empty() {
+                None
+            } else {
+                pwned.lookup(&secret.password)?
+            };
+            counts.insert(secret.id, (secret.password.clone(), count));
+        }
+
+        let _lock = self.begin()?;
+        let mut compromised = Vec::new();
+        for secret in &mut self.data.secrets {
+            let Some((password, count)) = counts.remove(&secret.id) else {
+                continue;
+            };
+            if password != secret.password {
+                continue;
+            }
+            secret.breach_count = count;
+            if count.is_some() {
+                compromised.push(secret.id);
             }
         }
-        self.vault.save(&self.data).map_err(CoreError::Vault)
+        self.save()?;
+        Ok(compromised)
+    }
+
+    /// Whether `password` decrypts the vault file on disk. Always `false`
+    /// without `open_at`.
+    pub fn verify_password(&self, password: &str) -> bool {
+        match &self.path {
+            Some(path) => VaultFile::open(path, password).load::<VaultData>().is_ok(),
+            None => false,
+        }
+    }
+
+    /// Argon2 parameters of the vault file on disk, if its path is known.
+    pub fn kdf_params(&self) -> Option<KdfParams> {
+        self.path.as_deref().and_then(|p| KdfParams::read(p).ok())
+    }
+
+    /// Re-encrypt the whole vault under `new_password` with a fresh salt,
+    /// keeping the Argon2 parameters of the current file. See `reencrypt`.
+    pub fn rekey(&mut self, new_password: &str) -> Result<(), CoreError> {
+        let params = KdfParams::read(self.require_path()?)?;
+        self.reencrypt(new_password, params)
+    }
+
+    /// Re-encrypt the whole vault with different Argon2 parameters. `password`
+    /// must be the current master password. See `reencrypt`.
+    pub fn set_kdf_params(&mut self, password: &str, params: KdfParams) -> Result<(), CoreError> {
+        params.validate()?;
+        if !self.verify_password(password) {
+            return Err(CoreError::Rekey("wrong master password".to_string()));
+        }
+        self.reencrypt(password, params)
+    }
+
+    /// Write the vault under `password` and `params`. Requires `open_at`.
+    ///
+    /// Crash-safe: the re-encrypted vault is written to `<path>.rekey`,
+    /// decrypted back and compared, and only then renamed over the vault
+    /// file. An interruption at any point leaves either the old or the new
+    /// vault fully readable. The file being replaced is snapshotted like
+    /// before any save; older backups are kept, see `clear_backups`.
+    fn reencrypt(&mut self, password: &str, params: KdfParams) -> Result<(), CoreError> {
+        let _lock = self.begin()?;
+        let path = self.require_path()?.to_path_buf();
+        let staged_path = suffixed_path(&path, "rekey");
+
+        let staged = params.apply(VaultFile::open(&staged_path, password));
+        staged.save(&self.data)?;
+
+        let verified = staged
+            .load::<VaultData>()
+            .is_ok_and(|d| d.secrets == self.data.secrets && d.trash == self.data.trash);
+        if !verified {
+            let _ = std::fs::remove_file(&staged_path);
+            return Err(CoreError::Rekey(
+                "re-encrypted vault did not verify".to_string(),
+            ));
+        }
+
+        if let Some(retention) = &self.backups {
+            backup::snapshot(&path, retention)?;
+        }
+        std::fs::rename(&staged_path, &path).map_err(|e| CoreError::Rekey(e.to_string()))?;
+        sync_parent_dir(&path);
+        self.fingerprint = Fingerprint::read(&path).ok();
+
+        self.vault = params.apply(VaultFile::open(&path, password));
+
+        if let Some(repo) = Repo::open(&path).filter(|_| self.git_commits) {
+            repo.commit_vault(&path, "Re-encrypt vault")?;
+        }
+        Ok(())
+    }
+
+    /// Delete every backup generation, then snapshot the vault file so that
+    /// one remains. After `rekey`, this leaves no backup readable with the
+    /// old password (git history, if any, still has it).
+    pub fn clear_backups(&self) -> Result<(), CoreError> {
+        let path = self.require_path()?;
+        let _lock = VaultLock::acquire(path)?;
+        backup::clear(path)?;
+        backup::snapshot(path, &self.backups.unwrap_or_default())?;
+        Ok(())
+    }
+
+    fn require_path(&self) -> Result<&Path, CoreError> {
+        self.path
+            .as_deref()
+            .ok_or_else(|| CoreError::Rekey("vault path is unknown".to_string()))
+    }
+
+    /// Reload the vault if another process wrote it since we last read or
+    /// wrote it. Returns whether it did.
+    pub fn refresh(&mut self) -> Result<bool, CoreError> {
+        let Some(path) = self.path.clone() else {
+            return Ok(false);
+        };
+        if self.fingerprint.as_ref().is_none_or(|f| f.matches(&path)) {
+            return Ok(false);
+        }
+        let _lock = VaultLock::acquire(&path)?;
+        self.reload_if_changed()
+    }
+
+    /// Start a change: take the vault lock, and reload the vault if another
+    /// process wrote it in the meantime, so that the change is applied on top
+    /// of theirs rather than overwriting it. Hold the guard until `save()`
+    /// returns. Without a path there is nothing to lock or compare against.
+    fn begin(&mut self) -> Result<Option<VaultLock>, CoreError> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        let lock = VaultLock::acquire(path)?;
+        self.reload_if_changed()?;
+        Ok(Some(lock))
+    }
+
+    /// Must be called with the vault lock held.
+    fn reload_if_changed(&mut self) -> Result<bool, CoreError> {
+        let (Some(path), Some(known)) = (self.path.clone(), &self.fingerprint) else {
+            return Ok(false);
+        };
+        if known.matches(&path) {
+            return Ok(false);
+        }
+        // Typically another process changed the master password.
+        let data = self.reload(&path).map_err(|_| CoreError::Conflict)?;
+        lock_in_memory(&data);
+        self.data = data;
+        self.fingerprint = Fingerprint::read(&path).ok();
+        Ok(true)
+    }
+
+    /// Decrypt the vault file again after something else replaced it. The
+    /// Argon2 parameters of its header are adopted too: our next save would
+    /// otherwise revert a `valt kdf --apply` made elsewhere.
+    fn reload(&mut self, path: &Path) -> Result<VaultData, serdevault::SerdeVaultError> {
+        if let Ok(params) = KdfParams::read(path) {
+            // `apply` takes the handle by value; the placeholder is never used.
+            let vault = std::mem::replace(&mut self.vault, VaultFile::open(path, ""));
+            self.vault = params.apply(vault);
+        }
+        self.vault.load::<VaultData>()
+    }
+
+    /// Persist the current in-memory state to disk, and commit it if
+    /// `with_git_commits` asked for it. Secrets that have been in the trash
+    /// longer than the retention period are purged first. Callers hold the
+    /// guard returned by `begin`.
+    ///
+    /// The file is replaced atomically (written to a temporary file, synced
+    /// and renamed over the vault, then the directory is synced), so a crash
+    /// leaves either the old or the new version. If `open_at` was
+    /// called, the existing vault file is snapshotted first and old snapshots
+    /// are pruned, so recent states can always be rolled back to. Fails hard
+    /// if the snapshot itself fails — a copy error usually indicates a
+    /// filesystem problem that would compromise the write too.
+    fn save(&mut self) -> Result<(), CoreError> {
+        if let Some(retention) = self.trash_retention {
+            let cutoff = Utc::now() - retention;
+            purge_where(&mut self.data, |s| s.deleted_at.is_some_and(|d| d < cutoff));
+        }
+        self.write()?;
+        if let Some(path) = self.path.as_deref().filter(|_| self.git_commits) {
+            if let Some(repo) = Repo::open(path) {
+                repo.commit_vault(path, COMMIT_MESSAGE)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The part of `save` that writes the file.
+    fn write(&mut self) -> Result<(), CoreError> {
+        if let (Some(path), Some(retention)) = (&self.path, &self.backups) {
+            backup::snapshot(path, retention)?;
+        }
+        self.vault.save(&self.data).map_err(CoreError::Vault)?;
+        if let Some(path) = &self.path {
+            sync_parent_dir(path);
+            self.fingerprint = Fingerprint::read(path).ok();
+        }
+        Ok(())
     }
 
     /// Compute the best fuzzy match score for a secret against a query string.
@@ -161,28 +924,121 @@ impl VaultManager {
             .filter_map(|tag| matcher.fuzzy_match(tag, query))
             .max();
 
-        match (best_field, best_tag) {
-            (None, None) => None,
-            (a, b) => Some(a.unwrap_or(0).max(b.unwrap_or(0))),
+        let best_custom = secret
+            .fields
+            .iter()
+            .flat_map(|f| {
+                let value = (f.kind != FieldKind::Hidden).then_some(f.value.as_str());
+                std::iter::once(f.label.as_str()).chain(value)
+            })
+            .filter_map(|text| matcher.fuzzy_match(text, query))
+            .max();
+
+        [best_field, best_tag, best_custom]
+            .into_iter()
+            .flatten()
+            .max()
+    }
+}
+
+/// Best effort: keep decrypted values out of swap. See `memory::lock`.
+fn lock_in_memory(data: &VaultData) {
+    for s in data.secrets.iter().chain(&data.trash) {
+        memory::lock(s.password.as_bytes());
+        if let Some(notes) = &s.notes {
+            memory::lock(notes.as_bytes());
+        }
+        if let Some(otp) = &s.otp {
+            memory::lock(otp.secret.as_bytes());
+        }
+        for f in &s.fields {
+            memory::lock(f.value.as_bytes());
+        }
+        for e in &s.history {
+            memory::lock(e.password.as_bytes());
+        }
+    }
+}
+
+/// Remove the trashed secrets matching `pred` for good, leaving tombstones.
+/// Returns how many were removed.
+fn purge_where(data: &mut VaultData, pred: impl Fn(&Secret) -> bool) -> usize {
+    let now = Utc::now();
+    let before = data.trash.len();
+    let tombstones = &mut data.tombstones;
+    data.trash.retain(|s| {
+        if !pred(s) {
+            return true;
+        }
+        tombstones.push(Tombstone {
+            id: s.id,
+            deleted_at: now,
+        });
+        false
+    });
+    before - data.trash.len()
+}
+
+/// Push `password` onto the front of `history`, dropping the oldest entries
+/// beyond `PASSWORD_HISTORY_LIMIT`.
+pub(super) fn retire_password(history: &mut Vec<PasswordEntry>, password: SecretString) {
+    history.insert(
+        0,
+        PasswordEntry {
+            password,
+            retired_at: Utc::now(),
+        },
+    );
+    history.truncate(PASSWORD_HISTORY_LIMIT);
+}
+
+/// Carry over to `secret` what `other`, another version of it, recorded as
+/// the secret was used rather than edited: a HOTP counter never goes back,
+/// since its codes may have been used, and a breach check still holds for
+/// the same password.
+pub(super) fn keep_usage_state(secret: &mut Secret, other: &Secret) {
+    if let (Some(otp), Some(theirs)) = (&mut secret.otp, &other.otp) {
+        if let (OtpKind::Hotp { counter }, OtpKind::Hotp { counter: used }) =
+            (&mut otp.kind, theirs.kind)
+        {
+            if otp.secret == theirs.secret {
+                *counter = (*counter).max(used);
+            }
         }
     }
+    if secret.password == other.password {
+        secret.breach_count = other.breach_count.or(secret.breach_count);
+    }
 }
 
-/// Returns the backup path for a vault file: `<path>.bak`
-/// e.g. `/home/user/.local/share/valt/vault.svlt` → `vault.svlt.bak`
-fn bak_path(path: &Path) -> PathBuf {
-    let mut bak = path.to_path_buf();
+/// `<path>.<suffix>`, in the same directory as `path`.
+pub(super) fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut out = path.to_path_buf();
     let name = path
         .file_name()
-        .map(|n| format!("{}.bak", n.to_string_lossy()))
-        .unwrap_or_else(|| "vault.svlt.bak".to_string());
-    bak.set_file_name(name);
-    bak
+        .map(|n| format!("{}.{suffix}", n.to_string_lossy()))
+        .unwrap_or_else(|| format!("vault.svlt.{suffix}"));
+    out.set_file_name(name);
+    out
+}
+
+/// Flush a rename to disk by syncing the containing directory. Best effort;
+/// directories cannot be opened this way on Windows.
+pub(super) fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = std::fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::secret::CustomField;
     use serdevault::VaultFile;
     use tempfile::tempdir;
 
@@ -264,12 +1120,13 @@ mod tests {
         mgr.add(original).unwrap();
 
         let updated = Secret::new("GitHub perso", "new_password");
-        mgr.update(id, updated).unwrap();
+        mgr.update(id, updated, mgr.get(id).unwrap().updated_at)
+            .unwrap();
 
         let found = mgr.get(id).unwrap();
         assert_eq!(found.id, id);
         assert_eq!(found.name, "GitHub perso");
-        assert_eq!(found.password, "new_password");
+        assert_eq!(found.password.as_str(), "new_password");
         assert_eq!(found.created_at, created); // created_at unchanged
         assert!(found.updated_at > created); // updated_at bumped
     }
@@ -280,7 +1137,7 @@ mod tests {
         let dir = tempdir().unwrap();
         let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
         let err = mgr
-            .update(Uuid::new_v4(), make_secret("X", "y"))
+            .update(Uuid::new_v4(), make_secret("X", "y"), Utc::now())
             .unwrap_err();
         assert!(matches!(err, CoreError::NotFound(_)));
     }
@@ -381,4 +1238,670 @@ mod tests {
         let result = VaultManager::open(wrong);
         assert!(matches!(result, Err(CoreError::Vault(_))));
     }
+
+    // 16. advance_otp bumps the HOTP counter, persists, and leaves updated_at alone
+    #[test]
+    fn test_advance_otp() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        let mut s = make_secret("Bank", "a");
+        s.otp = Some(
+            crate::core::Otp::parse("otpauth://hotp/Bank?secret=JBSWY3DPEHPK3PXP&counter=4")
+                .unwrap(),
+        );
+        let id = s.id;
+        let updated = s.updated_at;
+        mgr.add(s).unwrap();
+        mgr.advance_otp(id).unwrap();
+
+        let mgr2 = VaultManager::open(test_vault(&dir)).unwrap();
+        let found = mgr2.get(id).unwrap();
+        assert_eq!(
+            found.otp.as_ref().unwrap().kind,
+            crate::core::otp::OtpKind::Hotp { counter: 5 }
+        );
+        assert_eq!(found.updated_at, updated);
+    }
+
+    // 17. search matches custom field labels and visible values, never hidden values
+    #[test]
+    fn test_search_custom_fields() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        let mut s = make_secret("Bank", "a");
+        s.fields = vec![
+            CustomField::new("Account number", "FR7630006", FieldKind::Text),
+            CustomField::new("PIN", "zebra", FieldKind::Hidden),
+        ];
+        mgr.add(s).unwrap();
+        mgr.add(make_secret("GitHub", "b")).unwrap();
+
+        assert_eq!(mgr.search("FR7630").len(), 1);
+        assert_eq!(mgr.search("pin").len(), 1);
+        assert!(mgr.search("zebra").is_empty());
+    }
+
+    // 18. update retires the old password; unchanged password adds nothing
+    #[test]
+    fn test_update_records_history() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        let s = make_secret("GitHub", "first");
+        let id = s.id;
+        mgr.add(s).unwrap();
+
+        mgr.update(
+            id,
+            make_secret("GitHub", "second"),
+            mgr.get(id).unwrap().updated_at,
+        )
+        .unwrap();
+        mgr.update(
+            id,
+            make_secret("GitHub renamed", "second"),
+            mgr.get(id).unwrap().updated_at,
+        )
+        .unwrap();
+
+        let found = mgr.get(id).unwrap();
+        assert_eq!(found.history.len(), 1);
+        assert_eq!(found.history[0].password.as_str(), "first");
+    }
+
+    // 19. history is bounded, newest first
+    #[test]
+    fn test_history_bounded() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        let s = make_secret("GitHub", "p0");
+        let id = s.id;
+        mgr.add(s).unwrap();
+
+        for i in 1..=PASSWORD_HISTORY_LIMIT + 3 {
+            mgr.update(
+                id,
+                make_secret("GitHub", &format!("p{i}")),
+                mgr.get(id).unwrap().updated_at,
+            )
+            .unwrap();
+        }
+
+        let history = &mgr.get(id).unwrap().history;
+        assert_eq!(history.len(), PASSWORD_HISTORY_LIMIT);
+        assert_eq!(
+            *history[0].password,
+            format!("p{}", PASSWORD_HISTORY_LIMIT + 2)
+        );
+    }
+
+    // 20. restore_password swaps current and historical password, persisted
+    #[test]
+    fn test_restore_password() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        let s = make_secret("GitHub", "old");
+        let id = s.id;
+        mgr.add(s).unwrap();
+        mgr.update(
+            id,
+            make_secret("GitHub", "new"),
+            mgr.get(id).unwrap().updated_at,
+        )
+        .unwrap();
+
+        mgr.restore_password(id, 0).unwrap();
+
+        let mgr2 = VaultManager::open(test_vault(&dir)).unwrap();
+        let found = mgr2.get(id).unwrap();
+        assert_eq!(found.password.as_str(), "old");
+        assert_eq!(found.history.len(), 1);
+        assert_eq!(found.history[0].password.as_str(), "new");
+
+        let err = mgr.restore_password(id, 5).unwrap_err();
+        assert!(matches!(err, CoreError::HistoryIndex(5)));
+    }
+
+    // 21. rekey: new password opens, old one fails, data and KDF params preserved
+    #[test]
+    fn test_rekey() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        let mut mgr = VaultManager::open_or_create_at(test_vault(&dir), path.clone()).unwrap();
+        mgr.add(make_secret("GitHub", "s3cr3t")).unwrap();
+
+        assert!(mgr.verify_password("test_password"));
+        mgr.rekey("n3w-master").unwrap();
+        assert!(mgr.verify_password("n3w-master"));
+        assert!(!mgr.verify_password("test_password"));
+
+        // Backups made under the old password are kept, the vault as it
+        // was before the change included.
+        let generations = backup::list(&path).unwrap();
+        assert_eq!(generations.len(), 2);
+        let before = VaultFile::open(&generations[0].path, "test_password").with_params(M, T, P);
+        assert_eq!(VaultManager::open(before).unwrap().list().len(), 1);
+
+        // Unless they are cleared: one backup remains, under the new password.
+        mgr.clear_backups().unwrap();
+        let generations = backup::list(&path).unwrap();
+        assert_eq!(generations.len(), 1);
+        let old = VaultFile::open(&generations[0].path, "test_password");
+        assert!(VaultManager::open(old).is_err());
+        let new = VaultFile::open(&generations[0].path, "n3w-master").with_params(M, T, P);
+        assert_eq!(VaultManager::open(new).unwrap().list().len(), 1);
+
+        // Later saves keep using the new password.
+        mgr.add(make_secret("GitLab", "x")).unwrap();
+
+        let reopened = VaultManager::open(VaultFile::open(&path, "n3w-master")).unwrap();
+        assert_eq!(reopened.list().len(), 2);
+        assert!(VaultManager::open(test_vault(&dir)).is_err());
+        assert_eq!(KdfParams::read(&path).unwrap().m_cost, M);
+        assert!(!suffixed_path(&path, "rekey").exists());
+    }
+
+    // 22. rekey without a known path fails cleanly
+    #[test]
+    fn test_rekey_without_path() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        assert!(matches!(mgr.rekey("x"), Err(CoreError::Rekey(_))));
+    }
+
+    // 23. set_kdf_params re-encrypts with new costs; wrong password is refused
+    #[test]
+    fn test_set_kdf_params() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        let mut mgr = VaultManager::open_or_create_at(test_vault(&dir), path.clone()).unwrap();
+        mgr.add(make_secret("GitHub", "s3cr3t")).unwrap();
+
+        let params = KdfParams {
+            m_cost: 16,
+            t_cost: 2,
+            p_cost: 1,
+        };
+        assert!(mgr.set_kdf_params("wrong", params).is_err());
+        mgr.set_kdf_params("test_password", params).unwrap();
+        assert_eq!(mgr.kdf_params(), Some(params));
+
+        // Subsequent saves keep the new parameters.
+        mgr.add(make_secret("GitLab", "x")).unwrap();
+        assert_eq!(KdfParams::read(&path).unwrap(), params);
+        assert_eq!(
+            VaultManager::open(test_vault(&dir)).unwrap().list().len(),
+            2
+        );
+    }
+
+    // 24. add_all persists every secret in one go
+    #[test]
+    fn test_add_all() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        mgr.add(make_secret("GitHub", "a")).unwrap();
+        mgr.add_all(vec![make_secret("GitLab", "b"), make_secret("AWS", "c")])
+            .unwrap();
+
+        let reopened = VaultManager::open(test_vault(&dir)).unwrap();
+        assert_eq!(reopened.list().len(), 3);
+    }
+
+    // 25. audit reports reused passwords as one group
+    #[test]
+    fn test_audit() {
+        let dir = tempdir().unwrap();
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        mgr.add_all(vec![
+            make_secret("GitHub", "hunter2"),
+            make_secret("GitLab", "hunter2"),
+        ])
+        .unwrap();
+
+        let findings = mgr.audit(audit::DEFAULT_MAX_AGE_DAYS);
+        let reused: Vec<_> = findings
+            .iter()
+            .filter(|f| f.issue == audit::Issue::Reused)
+            .collect();
+        assert_eq!(reused.len(), 1);
+        assert_eq!(reused[0].ids.len(), 2);
+        assert!(findings.iter().any(|f| f.issue == audit::Issue::Weak));
+    }
+
+    // 26. check_breaches flags compromised secrets; changing the password clears the flag
+    #[test]
+    fn test_check_breaches() {
+        let dir = tempdir().unwrap();
+        let dump = dir.path().join("pwned.txt");
+        // SHA-1 of "password".
+        std::fs::write(&dump, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:42\r\n").unwrap();
+
+        let mut mgr = VaultManager::open_or_create(test_vault(&dir)).unwrap();
+        let weak = make_secret("Weak", "password");
+        let weak_id = weak.id;
+        mgr.add_all(vec![weak, make_secret("Strong", "k#9Lw!q2Vz$8mR@4xT&e")])
+            .unwrap();
+
+        let flagged = mgr
+            .check_breaches(&mut PwnedFile::open(&dump).unwrap())
+            .unwrap();
+        assert_eq!(flagged, [weak_id]);
+        let reopened = VaultManager::open(test_vault(&dir)).unwrap();
+        assert_eq!(reopened.get(weak_id).unwrap().breach_count, Some(42));
+
+        // Editing other fields keeps the flag; a new password clears it.
+        let mut edited = mgr.get(weak_id).unwrap().clone();
+        edited.breach_count = None;
+        edited.notes = Some("note".to_string().into());
+        mgr.update(
+            weak_id,
+            edited.clone(),
+            mgr.get(weak_id).unwrap().updated_at,
+        )
+        .unwrap();
+        assert_eq!(mgr.get(weak_id).unwrap().breach_count, Some(42));
+        edited.password = "new one".to_string().into();
+        mgr.update(weak_id, edited, mgr.get(weak_id).unwrap().updated_at)
+            .unwrap();
+        assert_eq!(mgr.get(weak_id).unwrap().breach_count, None);
+    }
+
+    // 27. save snapshots the previous file within the retention, unless backups are off
+    #[test]
+    fn test_backups_rotate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        let mut mgr = VaultManager::open_or_create_at(test_vault(&dir), path.clone())
+            .unwrap()
+            .with_backups(None);
+        mgr.add(make_secret("GitHub", "s3cr3t")).unwrap();
+        assert!(backup::list(&path).unwrap().is_empty());
+
+        let mut mgr = mgr.with_backups(Some(Retention {
+            keep: 2,
+            daily: 0,
+            weekly: 0,
+        }));
+        for name in ["GitLab", "Gitea", "Codeberg"] {
+            mgr.add(make_secret(name, "x")).unwrap();
+        }
+        let generations = backup::list(&path).unwrap();
+        assert_eq!(generations.len(), 2);
+        // The newest snapshot is the vault as it was before the last save.
+        let previous = VaultFile::open(&generations[0].path, "test_password").with_params(M, T, P);
+        assert_eq!(VaultManager::open(previous).unwrap().list().len(), 3);
+    }
+
+    fn open_at(dir: &tempfile::TempDir) -> VaultManager {
+        VaultManager::open_or_create_at(test_vault(dir), dir.path().join("vault.svlt"))
+            .unwrap()
+            .with_backups(None)
+    }
+
+    // 28. two writers on the same file: the second applies its change on top of the first
+    #[test]
+    fn test_concurrent_writers_do_not_clobber() {
+        let dir = tempdir().unwrap();
+        let mut tui = open_at(&dir);
+        let mut cli = open_at(&dir);
+
+        cli.add(make_secret("From CLI", "a")).unwrap();
+        tui.add(make_secret("From TUI", "b")).unwrap();
+        assert_eq!(tui.list().len(), 2);
+
+        let reopened = VaultManager::open(test_vault(&dir)).unwrap();
+        let mut names: Vec<_> = reopened.list().iter().map(|s| s.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["From CLI", "From TUI"]);
+    }
+
+    // 29. refresh notices outside writes; a vault re-keyed elsewhere is never overwritten
+    #[test]
+    fn test_refresh_and_conflict() {
+        let dir = tempdir().unwrap();
+        let mut tui = open_at(&dir);
+        let mut cli = open_at(&dir);
+        assert!(!tui.refresh().unwrap());
+
+        cli.add(make_secret("GitHub", "a")).unwrap();
+        assert!(tui.refresh().unwrap());
+        assert_eq!(tui.list().len(), 1);
+        assert!(!tui.refresh().unwrap());
+
+        cli.rekey("n3w-master").unwrap();
+        assert!(matches!(
+            tui.add(make_secret("GitLab", "b")),
+            Err(CoreError::Conflict)
+        ));
+        let on_disk =
+            VaultManager::open(VaultFile::open(dir.path().join("vault.svlt"), "n3w-master"));
+        assert_eq!(on_disk.unwrap().list().len(), 1);
+    }
+
+    // 30. merge folds a conflicted copy in: additions, deletions and newer edits
+    #[test]
+    fn test_merge_conflicted_copy() {
+        let dir = tempdir().unwrap();
+        let mut ours = open_at(&dir);
+        let github = make_secret("GitHub", "a");
+        let old = make_secret("Old", "x");
+        let (github_id, old_id) = (github.id, old.id);
+        ours.add_all(vec![github, old]).unwrap();
+
+        std::fs::copy(dir.path().join("vault.svlt"), dir.path().join("copy.svlt")).unwrap();
+        let copy_path = dir.path().join("copy.svlt");
+        let mut theirs = VaultManager::open_at(
+            VaultFile::open(&copy_path, "test_password").with_params(M, T, P),
+            copy_path.clone(),
+        )
+        .unwrap()
+        .with_backups(None);
+        let mut edited = theirs.get(github_id).unwrap().clone();
+        edited.password = SecretString::new("b".to_string());
+        theirs
+            .update(github_id, edited, theirs.get(github_id).unwrap().updated_at)
+            .unwrap();
+        theirs.delete(old_id).unwrap();
+        theirs.add(make_secret("Mail", "m")).unwrap();
+
+        assert_eq!(ours.preview_merge(&theirs, None).added, ["Mail"]);
+        assert_eq!(ours.list().len(), 2);
+
+        let report = ours.merge(&theirs, None).unwrap();
+        assert_eq!(report.updated, ["GitHub"]);
+        assert_eq!(report.deleted, ["Old"]);
+        assert!(report.conflicts.is_empty());
+
+        let reopened = VaultManager::open(test_vault(&dir)).unwrap();
+        let names: Vec<_> = reopened.list().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["GitHub", "Mail"]);
+        assert_eq!(reopened.get(github_id).unwrap().password.as_str(), "b");
+        assert!(ours.merge(&theirs, None).unwrap().is_empty());
+    }
+
+    // 31. delete moves to the trash; restore, purge and expiry
+    #[test]
+    fn test_trash() {
+        let dir = tempdir().unwrap();
+        let mut mgr = open_at(&dir);
+        let (a, b) = (make_secret("GitHub", "a"), make_secret("GitLab", "b"));
+        let (a_id, b_id) = (a.id, b.id);
+        mgr.add_all(vec![a, b]).unwrap();
+
+        mgr.delete(a_id).unwrap();
+        assert_eq!(mgr.list().len(), 1);
+        assert_eq!(mgr.search("GitHub").len(), 0);
+        assert_eq!(mgr.search_trash("hub")[0].id, a_id);
+        assert!(mgr.purge_due(a_id).is_some());
+
+        mgr.restore(a_id).unwrap();
+        assert!(mgr.get(a_id).unwrap().deleted_at.is_none());
+        assert!(mgr.trash().is_empty());
+        assert!(matches!(mgr.restore(a_id), Err(CoreError::NotFound(_))));
+
+        mgr.delete(a_id).unwrap();
+        mgr.purge(a_id).unwrap();
+        assert!(mgr.trash().is_empty());
+        assert_eq!(mgr.data.tombstones[0].id, a_id);
+
+        // Anything in the trash longer than the retention goes on the next save.
+        mgr.delete(b_id).unwrap();
+        mgr.data.trash[0].deleted_at = Some(Utc::now() - TimeDelta::days(31));
+        mgr.add(make_secret("Mail", "m")).unwrap();
+        assert!(mgr.trash().is_empty());
+        assert_eq!(
+            VaultManager::open(test_vault(&dir)).unwrap().list().len(),
+            1
+        );
+    }
+
+    // 32. undo and redo add, update and delete, each persisted as a save
+    #[test]
+    fn test_undo_redo() {
+        let dir = tempdir().unwrap();
+        let mut mgr = open_at(&dir);
+        assert_eq!(mgr.undo().unwrap(), None);
+
+        let s = make_secret("GitHub", "old");
+        let id = s.id;
+        mgr.add(s).unwrap();
+        let mut edited = mgr.get(id).unwrap().clone();
+        edited.password = SecretString::new("typo".to_string());
+        mgr.update(id, edited, mgr.get(id).unwrap().updated_at)
+            .unwrap();
+        mgr.delete(id).unwrap();
+        assert_eq!(mgr.undo_label(), Some("deletion of 'GitHub'"));
+
+        assert_eq!(mgr.undo().unwrap().as_deref(), Some("deletion of 'GitHub'"));
+        assert!(mgr.trash().is_empty());
+        assert_eq!(mgr.undo().unwrap().as_deref(), Some("edit of 'GitHub'"));
+        assert_eq!(mgr.get(id).unwrap().password.as_str(), "old");
+        assert!(mgr.get(id).unwrap().history.is_empty());
+        let on_disk = VaultManager::open(test_vault(&dir)).unwrap();
+        assert_eq!(on_disk.get(id).unwrap().password.as_str(), "old");
+
+        assert_eq!(mgr.redo_label(), Some("edit of 'GitHub'"));
+        mgr.redo().unwrap();
+        assert_eq!(mgr.get(id).unwrap().password.as_str(), "typo");
+
+        // A new change drops what was left to redo.
+        mgr.add(make_secret("GitLab", "x")).unwrap();
+        assert_eq!(mgr.redo().unwrap(), None);
+
+        mgr.undo().unwrap();
+        mgr.undo().unwrap();
+        assert_eq!(mgr.undo().unwrap().as_deref(), Some("add of 'GitHub'"));
+        assert!(mgr.list().is_empty());
+        assert_eq!(mgr.data.tombstones.len(), 2);
+    }
+
+    // 33. edits and undos based on a version another process changed since are refused
+    #[test]
+    fn test_stale_edits_refused() {
+        let dir = tempdir().unwrap();
+        let mut tui = open_at(&dir);
+        let s = make_secret("GitHub", "old");
+        let id = s.id;
+        tui.add(s).unwrap();
+        let draft = tui.get(id).unwrap().clone();
+        let based_on = draft.updated_at;
+
+        let mut cli = open_at(&dir);
+        let mut theirs = cli.get(id).unwrap().clone();
+        theirs.password = SecretString::new("theirs".to_string());
+        cli.update(id, theirs, based_on).unwrap();
+
+        let mut ours = draft.clone();
+        ours.password = SecretString::new("ours".to_string());
+        assert!(matches!(
+            tui.update(id, ours, based_on),
+            Err(CoreError::Stale(_))
+        ));
+        assert_eq!(tui.get(id).unwrap().password.as_str(), "theirs");
+
+        // Undoing the add would delete their edit: refused, and kept for later.
+        assert!(matches!(tui.undo(), Err(CoreError::Stale(_))));
+        assert_eq!(tui.undo_label(), Some("add of 'GitHub'"));
+        assert_eq!(
+            VaultManager::open(test_vault(&dir))
+                .unwrap()
+                .get(id)
+                .unwrap()
+                .password
+                .as_str(),
+            "theirs"
+        );
+    }
+
+    // 34. a vault re-encrypted with new KDF costs elsewhere keeps them on our next save
+    #[test]
+    fn test_reload_adopts_kdf_params() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        let mut ours = open_at(&dir);
+        let mut theirs = open_at(&dir);
+
+        let params = KdfParams {
+            m_cost: 16,
+            t_cost: 2,
+            p_cost: 1,
+        };
+        theirs.set_kdf_params("test_password", params).unwrap();
+        ours.add(make_secret("GitHub", "s3cr3t")).unwrap();
+        assert_eq!(KdfParams::read(&path).unwrap(), params);
+
+        // So does an explicit `refresh`.
+        theirs.add(make_secret("GitLab", "x")).unwrap();
+        ours.refresh().unwrap();
+        ours.add(make_secret("Gitea", "y")).unwrap();
+        assert_eq!(KdfParams::read(&path).unwrap(), params);
+        assert_eq!(
+            VaultManager::open(test_vault(&dir)).unwrap().list().len(),
+            3
+        );
+    }
+
+    // 35. restoring from the trash can be undone, putting the secret back in the trash
+    #[test]
+    fn test_undo_restore() {
+        let dir = tempdir().unwrap();
+        let mut mgr = open_at(&dir);
+        let s = make_secret("GitHub", "s3cr3t");
+        let id = s.id;
+        mgr.add(s).unwrap();
+        mgr.delete(id).unwrap();
+        mgr.restore(id).unwrap();
+        assert_eq!(mgr.undo_label(), Some("restore of 'GitHub'"));
+
+        mgr.undo().unwrap();
+        assert!(mgr.get(id).is_none());
+        assert_eq!(mgr.trash()[0].id, id);
+        mgr.redo().unwrap();
+        assert!(mgr.trash().is_empty());
+        assert!(mgr.get(id).is_some());
+    }
+
+    // 36. undo keeps what using the secret recorded: HOTP counter and breach count
+    #[test]
+    fn test_undo_keeps_usage_state() {
+        let dir = tempdir().unwrap();
+        let mut mgr = open_at(&dir);
+        let mut s = make_secret("GitHub", "s3cr3t");
+        s.otp = Some(
+            crate::core::Otp::parse("otpauth://hotp/x?secret=JBSWY3DPEHPK3PXP&counter=1").unwrap(),
+        );
+        let id = s.id;
+        mgr.add(s).unwrap();
+        let mut edited = mgr.get(id).unwrap().clone();
+        edited.username = Some("jb".to_string());
+        mgr.update(id, edited, mgr.get(id).unwrap().updated_at)
+            .unwrap();
+
+        mgr.advance_otp(id).unwrap();
+        mgr.data.secrets[0].breach_count = Some(3);
+        mgr.undo().unwrap();
+
+        let found = mgr.get(id).unwrap();
+        assert_eq!(found.username, None);
+        assert_eq!(
+            found.otp.as_ref().unwrap().kind,
+            OtpKind::Hotp { counter: 2 }
+        );
+        assert_eq!(found.breach_count, Some(3));
+    }
+
+    // 37. undo after reloading another process's changes keeps theirs
+    #[test]
+    fn test_undo_after_outside_reload() {
+        let dir = tempdir().unwrap();
+        let mut tui = open_at(&dir);
+        let s = make_secret("GitHub", "old");
+        let id = s.id;
+        tui.add(s).unwrap();
+        let mut edited = tui.get(id).unwrap().clone();
+        edited.password = SecretString::new("typo".to_string());
+        tui.update(id, edited, tui.get(id).unwrap().updated_at)
+            .unwrap();
+
+        let mut cli = open_at(&dir);
+        cli.add(make_secret("From CLI", "x")).unwrap();
+        assert!(tui.refresh().unwrap());
+
+        assert_eq!(tui.undo().unwrap().as_deref(), Some("edit of 'GitHub'"));
+        let reopened = VaultManager::open(test_vault(&dir)).unwrap();
+        assert_eq!(reopened.get(id).unwrap().password.as_str(), "old");
+        assert_eq!(reopened.list().len(), 2);
+    }
+
+    // 38. a breach check keeps changes made elsewhere while it looked passwords up
+    #[test]
+    fn test_check_breaches_keeps_outside_changes() {
+        let dir = tempdir().unwrap();
+        let dump = dir.path().join("pwned.txt");
+        // SHA-1 of "password".
+        std::fs::write(&dump, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:42\r\n").unwrap();
+
+        let mut checker = open_at(&dir);
+        let (changed, deleted) = (
+            make_secret("Changed", "password"),
+            make_secret("Gone", "password"),
+        );
+        let (changed_id, deleted_id) = (changed.id, deleted.id);
+        checker.add_all(vec![changed, deleted]).unwrap();
+
+        let mut other = open_at(&dir);
+        let mut edited = other.get(changed_id).unwrap().clone();
+        edited.password = SecretString::new("k#9Lw!q2Vz$8mR@4xT&e".to_string());
+        other
+            .update(
+                changed_id,
+                edited,
+                other.get(changed_id).unwrap().updated_at,
+            )
+            .unwrap();
+        other.delete(deleted_id).unwrap();
+
+        // The lookups saw the stale copy, in which both passwords are breached.
+        let flagged = checker
+            .check_breaches(&mut PwnedFile::open(&dump).unwrap())
+            .unwrap();
+        assert!(flagged.is_empty());
+        let reopened = VaultManager::open(test_vault(&dir)).unwrap();
+        let changed = reopened.get(changed_id).unwrap();
+        assert_eq!(changed.password.as_str(), "k#9Lw!q2Vz$8mR@4xT&e");
+        assert_eq!(changed.breach_count, None);
+        assert!(reopened.get(deleted_id).is_none());
+    }
+
+    // 39. password restores and batch additions can be undone too
+    #[test]
+    fn test_undo_password_restore_and_add_all() {
+        let dir = tempdir().unwrap();
+        let mut mgr = open_at(&dir);
+        let (a, b) = (make_secret("GitHub", "old"), make_secret("GitLab", "x"));
+        let (a_id, b_id) = (a.id, b.id);
+        mgr.add_all(vec![a, b]).unwrap();
+        let mut edited = mgr.get(a_id).unwrap().clone();
+        edited.password = SecretString::new("new".to_string());
+        mgr.update(a_id, edited, mgr.get(a_id).unwrap().updated_at)
+            .unwrap();
+        mgr.restore_password(a_id, 0).unwrap();
+        assert_eq!(mgr.undo_label(), Some("password restore of 'GitHub'"));
+
+        mgr.undo().unwrap();
+        let github = mgr.get(a_id).unwrap();
+        assert_eq!(github.password.as_str(), "new");
+        assert_eq!(github.history.len(), 1);
+        assert_eq!(github.history[0].password.as_str(), "old");
+        mgr.redo().unwrap();
+        assert_eq!(mgr.get(a_id).unwrap().password.as_str(), "old");
+
+        mgr.undo().unwrap();
+        mgr.undo().unwrap();
+        assert_eq!(mgr.undo().unwrap().as_deref(), Some("add of 'GitLab'"));
+        assert!(mgr.get(b_id).is_none());
+        assert_eq!(mgr.undo().unwrap().as_deref(), Some("add of 'GitHub'"));
+        let on_disk = VaultManager::open(test_vault(&dir)).unwrap();
+        assert!(on_disk.list().is_empty());
+    }
 }
@@ -0,0 +1,147 @@
+//! Process-level protection for decrypted secrets.
+//!
+//! Values themselves are wiped on drop by `SecretString`; this module keeps
+//! them out of places the process does not control: swap and core dumps.
+
+use super::secret::SecretString;
+
+/// Disable core dumps and, on Linux, mark the process non-dumpable so that
+/// other processes of the same user cannot `ptrace` it or read
+/// `/proc/<pid>/mem`. Call once at startup. Best effort: failures are ignored.
+pub fn harden_process() {
+    #[cfg(unix)]
+    unsafe {
+        let no_core = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        libc::setrlimit(libc::RLIMIT_CORE, &no_core);
+    }
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+    }
+}
+
+/// Ask the kernel to keep the pages holding `bytes` in RAM, so they are never
+/// written to swap. Returns whether it did: unprivileged processes are limited
+/// by `RLIMIT_MEMLOCK` (often 8 MiB), and other platforms are not supported.
+///
+/// Pages are never unlocked, as other values may share them; they count
+/// against the limit until the process exits.
+pub fn lock(bytes: &[u8]) -> bool {
+    mlock(bytes.as_ptr(), bytes.len())
+}
+
+/// An empty `SecretString` with room for `capacity` bytes, locked in memory
+/// where permitted. Pushing up to `capacity` bytes never reallocates, so no
+/// unwiped copy of a partial input is left behind.
+pub fn locked_string(capacity: usize) -> SecretString {
+    let s = SecretString::new(String::with_capacity(capacity));
+    mlock(s.as_ptr(), s.capacity());
+    s
+}
+
+/// Remove the last character of `s` and zero the bytes it occupied, which
+/// `String::pop` leaves behind in the buffer.
+pub fn backspace(s: &mut String) {
+    let Some(c) = s.pop() else { return };
+    // SAFETY: only bytes past the end of the string are written, so its
+    // contents stay valid UTF-8.
+    let spare = unsafe { s.as_mut_vec() }.spare_capacity_mut();
+    for byte in &mut spare[..c.len_utf8()] {
+        byte.write(0);
+    }
+}
+
+#[cfg(unix)]
+fn mlock(ptr: *const u8, len: usize) -> bool {
+    if len == 0 {
+        return true;
+    }
+    // SAFETY: mlock only changes paging for the given range, which belongs
+    // to a live allocation; it neither reads nor writes it.
+    unsafe { libc::mlock(ptr.cast(), len) == 0 }
+}
+
+#[cfg(not(unix))]
+fn mlock(_ptr: *const u8, _len: usize) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::otp::Otp;
+    use crate::core::secret::{CustomField, FieldKind, PasswordEntry, Secret};
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+
+    fn wipes_on_drop<T: ZeroizeOnDrop>(_: &T) {}
+
+    #[test]
+    fn test_sensitive_fields_wipe_on_drop() {
+        // Compile-time: every sensitive value is a type that zeroizes on drop.
+        let mut secret = Secret::new("GitHub", "s3cr3t");
+        secret.notes = Some(SecretString::new("recovery: 1234".to_string()));
+        secret.fields = vec![CustomField::new("PIN", "0000", FieldKind::Hidden)];
+        secret.history = vec![PasswordEntry {
+            password: SecretString::new("old".to_string()),
+            retired_at: secret.created_at,
+        }];
+        secret.otp = Some(Otp::parse("JBSWY3DPEHPK3PXP").unwrap());
+        wipes_on_drop(&secret.password);
+        wipes_on_drop(secret.notes.as_ref().unwrap());
+        wipes_on_drop(&secret.fields[0].value);
+        wipes_on_drop(&secret.history[0].password);
+        wipes_on_drop(&secret.otp.as_ref().unwrap().secret);
+    }
+
+    #[test]
+    fn test_wipe_covers_whole_buffer() {
+        // What drop does before freeing: zero every byte of the allocation,
+        // including spare capacity left over from a shorter value.
+        let mut s = locked_string(64);
+        s.push_str("correct horse battery staple");
+        s.truncate(7);
+        let (ptr, cap) = (s.as_ptr(), s.capacity());
+        s.zeroize();
+
+        // SAFETY: the allocation is still alive (zeroize does not free) and
+        // every byte of it was just written by zeroize.
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, cap) };
+        assert!(bytes.iter().all(|b| *b == 0));
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_locked_string_never_reallocates() {
+        let mut s = locked_string(32);
+        let ptr = s.as_ptr();
+        for _ in 0..32 {
+            s.push('x');
+        }
+        assert_eq!(s.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_backspace_wipes_removed_char() {
+        let mut s = locked_string(16);
+        s.push_str("pé");
+        let ptr = s.as_ptr();
+        backspace(&mut s);
+        backspace(&mut s);
+        backspace(&mut s);
+        assert!(s.is_empty());
+
+        // SAFETY: the three bytes were written by push_str, then zeroed.
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, 3) };
+        assert_eq!(bytes, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_lock() {
+        // Locking may be refused by RLIMIT_MEMLOCK, but never fails on empty input.
+        assert!(lock(&[]));
+        let _ = lock(b"secret");
+    }
+}
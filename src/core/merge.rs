@@ -0,0 +1,555 @@
+use chrono::DateTime;
+
+use super::manager::{keep_usage_state, retire_password, PASSWORD_HISTORY_LIMIT};
+use super::otp::OtpKind;
+use super::secret::Secret;
+use super::vault_data::{Tombstone, VaultData};
+
+/// What `merge` did, by secret name.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeReport {
+    /// Only in the other vault, now copied into this one.
+    pub added: Vec<String>,
+    /// Newer in the other vault, now replaced here.
+    pub updated: Vec<String>,
+    /// Deleted in the other vault, now in the trash (or purged) here too.
+    pub deleted: Vec<String>,
+    /// Changed on both sides. The newer version was kept; see `ConflictKind`
+    /// for what happened to the other one.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.deleted.is_empty()
+            && self.conflicts.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    pub kind: ConflictKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both sides changed the password: the older one is now in the history.
+    BothChanged,
+    /// Both sides changed another value, e.g. the username or notes, or with
+    /// no common ancestor the two versions differ in more than the password:
+    /// the older one's values were dropped.
+    Overwritten,
+    /// Deleted in this vault but edited later in the other one: restored.
+    DeletedHere,
+    /// Deleted in the other vault but edited later in this one: kept.
+    DeletedThere,
+}
+
+impl ConflictKind {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::BothChanged => {
+                "changed in both vaults; kept the newer version, the other password is in its history"
+            }
+            Self::Overwritten => {
+                "differs between the vaults; kept the newer version, check that nothing was lost"
+            }
+            Self::DeletedHere => "deleted here but edited later in the other vault; restored",
+            Self::DeletedThere => "deleted in the other vault but edited later here; kept",
+        }
+    }
+}
+
+/// Fold `theirs` into `ours`, matching secrets by id.
+///
+/// When `base`, the copy both sides started from, holds a secret, the two
+/// versions are merged value by value against it (see `three_way`): only a
+/// value both sides changed differently is a conflict.
+///
+/// Without it, timestamps decide: when both sides hold a different version
+/// of a secret, the one changed last wins, be it an edit or a move to the
+/// trash. The losing password is kept in the winner's history unless it
+/// already is there, in which case the winner simply descends from it;
+/// otherwise both sides changed the password and the secret is reported as
+/// a conflict. Any other value the loser holds is dropped, so a loser that
+/// differs in more than timestamps and history is reported as a conflict
+/// too. Purged secrets travel as tombstones and win over changes made
+/// before them.
+pub fn merge(ours: &mut VaultData, theirs: &VaultData, base: Option<&VaultData>) -> MergeReport {
+    let mut report = MergeReport::default();
+    let mut all = std::mem::take(&mut ours.secrets);
+    all.append(&mut ours.trash);
+
+    for their in theirs.secrets.iter().chain(&theirs.trash) {
+        if let Some(our) = all.iter_mut().find(|s| s.id == their.id) {
+            if our == their {
+                continue;
+            }
+            let (merged, conflict, took_theirs) = match base.and_then(|b| b.find(their.id)) {
+                Some(base) => {
+                    let (merged, conflict) = three_way(base, our, their);
+                    (merged, conflict, true)
+                }
+                None => {
+                    let theirs_newer = their.changed_at() > our.changed_at();
+                    let (winner, loser) = if theirs_newer {
+                        (their.clone(), &*our)
+                    } else {
+                        (our.clone(), their)
+                    };
+                    let (merged, conflict) = reconcile(winner, loser);
+                    (merged, conflict, theirs_newer)
+                }
+            };
+            if let Some(kind) = conflict {
+                // Reported even if ours won unchanged: theirs was dropped.
+                report.conflicts.push(Conflict {
+                    name: merged.name.clone(),
+                    kind,
+                });
+            } else if merged == *our {
+                continue;
+            } else if took_theirs && merged.deleted_at.is_some() && our.deleted_at.is_none() {
+                report.deleted.push(merged.name.clone());
+            } else if took_theirs {
+                report.updated.push(merged.name.clone());
+            }
+            *our = merged;
+        } else {
+            match ours.tombstone(their.id) {
+                Some(t) if t.deleted_at >= their.changed_at() => continue,
+                Some(_) => report.conflicts.push(Conflict {
+                    name: their.name.clone(),
+                    kind: ConflictKind::DeletedHere,
+                }),
+                None if their.deleted_at.is_some() => {}
+                None => report.added.push(their.name.clone()),
+            }
+            all.push(their.clone());
+        }
+    }
+
+    let mut purged = Vec::new();
+    for our in &all {
+        let Some(t) = theirs.tombstone(our.id) else {
+            continue;
+        };
+        if t.deleted_at >= our.changed_at() {
+            if our.deleted_at.is_none() {
+                report.deleted.push(our.name.clone());
+            }
+            purged.push(our.id);
+        } else {
+            report.conflicts.push(Conflict {
+                name: our.name.clone(),
+                kind: ConflictKind::DeletedThere,
+            });
+        }
+    }
+    all.retain(|s| !purged.contains(&s.id));
+
+    (ours.secrets, ours.trash) = all.into_iter().partition(|s| s.deleted_at.is_none());
+    merge_tombstones(ours, &theirs.tombstones);
+    report
+}
+
+/// `winner` with what is worth keeping from `loser`: the union of both
+/// histories, the loser's password if it was never retired by the winner,
+/// the earlier creation date and the usage state (see `keep_usage_state`).
+/// Also returns how the two had diverged, if they had.
+fn reconcile(mut winner: Secret, loser: &Secret) -> (Secret, Option<ConflictKind>) {
+    winner.created_at = winner.created_at.min(loser.created_at);
+    keep_usage_state(&mut winner, loser);
+
+    for entry in &loser.history {
+        if !winner.history.iter().any(|e| e.password == entry.password) {
+            winner.history.push(entry.clone());
+        }
+    }
+    winner
+        .history
+        .sort_by_key(|e| std::cmp::Reverse(e.retired_at));
+    winner.history.truncate(PASSWORD_HISTORY_LIMIT);
+
+    let diverged = loser.password != winner.password
+        && !winner.history.iter().any(|e| e.password == loser.password);
+    if diverged {
+        retire_password(&mut winner.history, loser.password.clone());
+        return (winner, Some(ConflictKind::BothChanged));
+    }
+    let overwritten = edited_values(&winner) != edited_values(loser);
+    (winner, overwritten.then_some(ConflictKind::Overwritten))
+}
+
+/// `ours` and `theirs` merged against `base`, the version both started from.
+/// A value changed on one side only is taken from that side. A value both
+/// sides changed differently is settled as `reconcile` would: the newer
+/// version wins, and a losing password goes to the history. Histories and
+/// usage state are combined as there.
+fn three_way(base: &Secret, ours: &Secret, theirs: &Secret) -> (Secret, Option<ConflictKind>) {
+    let theirs_newer = theirs.changed_at() > ours.changed_at();
+    let mut clash = false;
+    let mut merged = ours.clone();
+    merged.name = pick(
+        &base.name,
+        &ours.name,
+        &theirs.name,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.kind = pick(
+        &base.kind,
+        &ours.kind,
+        &theirs.kind,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.username = pick(
+        &base.username,
+        &ours.username,
+        &theirs.username,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.url = pick(&base.url, &ours.url, &theirs.url, theirs_newer, &mut clash);
+    merged.notes = pick(
+        &base.notes,
+        &ours.notes,
+        &theirs.notes,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.tags = pick(
+        &base.tags,
+        &ours.tags,
+        &theirs.tags,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.group = pick(
+        &base.group,
+        &ours.group,
+        &theirs.group,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.fields = pick(
+        &base.fields,
+        &ours.fields,
+        &theirs.fields,
+        theirs_newer,
+        &mut clash,
+    );
+    merged.deleted_at = pick(
+        &base.deleted_at,
+        &ours.deleted_at,
+        &theirs.deleted_at,
+        theirs_newer,
+        &mut clash,
+    );
+    // Counters are usage state, restored below from both sides.
+    let [base_otp, our_otp, their_otp] = [base, ours, theirs].map(|s| edited_values(s).otp);
+    merged.otp = pick(&base_otp, &our_otp, &their_otp, theirs_newer, &mut clash);
+
+    let mut password_clash = false;
+    merged.password = pick(
+        &base.password,
+        &ours.password,
+        &theirs.password,
+        theirs_newer,
+        &mut password_clash,
+    );
+    for entry in &theirs.history {
+        if !merged.history.iter().any(|e| e.password == entry.password) {
+            merged.history.push(entry.clone());
+        }
+    }
+    merged
+        .history
+        .sort_by_key(|e| std::cmp::Reverse(e.retired_at));
+    merged.history.truncate(PASSWORD_HISTORY_LIMIT);
+    if password_clash {
+        let loser = if theirs_newer { ours } else { theirs };
+        retire_password(&mut merged.history, loser.password.clone());
+    }
+
+    merged.created_at = ours.created_at.min(theirs.created_at);
+    merged.updated_at = ours.updated_at.max(theirs.updated_at);
+    merged.breach_count = None;
+    keep_usage_state(&mut merged, ours);
+    keep_usage_state(&mut merged, theirs);
+
+    let conflict = if password_clash {
+        Some(ConflictKind::BothChanged)
+    } else {
+        clash.then_some(ConflictKind::Overwritten)
+    };
+    (merged, conflict)
+}
+
+/// The side that changed `base`, or the newer one if both did and disagree,
+/// which sets `clash`.
+fn pick<T: Clone + PartialEq>(
+    base: &T,
+    ours: &T,
+    theirs: &T,
+    theirs_newer: bool,
+    clash: &mut bool,
+) -> T {
+    if ours == theirs || theirs == base {
+        ours.clone()
+    } else if ours == base {
+        theirs.clone()
+    } else {
+        *clash = true;
+        if theirs_newer {
+            theirs.clone()
+        } else {
+            ours.clone()
+        }
+    }
+}
+
+/// `secret` without what `reconcile` keeps from both sides or decides by
+/// itself: timestamps, the password and its history, and usage state.
+fn edited_values(secret: &Secret) -> Secret {
+    let mut s = secret.clone();
+    s.created_at = DateTime::UNIX_EPOCH;
+    s.updated_at = DateTime::UNIX_EPOCH;
+    s.deleted_at = None;
+    s.password = Default::default();
+    s.history.clear();
+    s.breach_count = None;
+    if let Some(OtpKind::Hotp { counter }) = s.otp.as_mut().map(|o| &mut o.kind) {
+        *counter = 0;
+    }
+    s
+}
+
+/// Add `theirs` to the tombstones of `ours`, keeping the latest deletion of
+/// each id, and drop tombstones of secrets that are present again.
+fn merge_tombstones(ours: &mut VaultData, theirs: &[Tombstone]) {
+    for t in theirs {
+        match ours.tombstones.iter_mut().find(|o| o.id == t.id) {
+            Some(o) => o.deleted_at = o.deleted_at.max(t.deleted_at),
+            None => ours.tombstones.push(*t),
+        }
+    }
+    let present: Vec<_> = ours
+        .secrets
+        .iter()
+        .chain(&ours.trash)
+        .map(|s| s.id)
+        .collect();
+    ours.tombstones.retain(|t| !present.contains(&t.id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn vault(secrets: &[&Secret]) -> VaultData {
+        VaultData {
+            secrets: secrets.iter().map(|s| (*s).clone()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// `secret` as `VaultManager::update` leaves it after a password change.
+    fn edited(secret: &Secret, password: &str, minutes: i64) -> Secret {
+        let mut s = secret.clone();
+        retire_password(&mut s.history, s.password.clone());
+        s.password = password.to_string().into();
+        s.updated_at += TimeDelta::minutes(minutes);
+        s
+    }
+
+    #[test]
+    fn test_added_updated_and_unchanged() {
+        let shared = Secret::new("GitHub", "a");
+        let new = Secret::new("Mail", "m");
+        let mut ours = vault(&[&shared]);
+        let theirs = vault(&[&edited(&shared, "b", 1), &new]);
+
+        let report = merge(&mut ours, &theirs, None);
+        assert_eq!(report.added, ["Mail"]);
+        assert_eq!(report.updated, ["GitHub"]);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(ours.secrets.len(), 2);
+        assert_eq!(ours.secrets[0].password.as_str(), "b");
+        assert_eq!(ours.secrets[0].history[0].password.as_str(), "a");
+
+        // Merging the same copy again changes nothing.
+        assert!(merge(&mut ours, &theirs, None).is_empty());
+    }
+
+    #[test]
+    fn test_newer_wins_and_loser_goes_to_history() {
+        let shared = Secret::new("GitHub", "a");
+        let mut ours = vault(&[&edited(&shared, "ours", 2)]);
+        let theirs = vault(&[&edited(&shared, "theirs", 1)]);
+
+        let report = merge(&mut ours, &theirs, None);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::BothChanged);
+        assert_eq!(ours.secrets[0].password.as_str(), "ours");
+        assert_eq!(ours.secrets[0].history[0].password.as_str(), "theirs");
+
+        // Our side already retired their password: it is simply ahead.
+        let mut ours = vault(&[&edited(&shared, "b", 1)]);
+        assert!(merge(&mut ours, &vault(&[&shared]), None).is_empty());
+        assert_eq!(ours.secrets[0].password.as_str(), "b");
+        assert_eq!(ours.secrets[0].history.len(), 1);
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let gone = Secret::new("Old", "x");
+        let edited_later = Secret::new("Kept", "y");
+        let mut ours = vault(&[&gone, &edited(&edited_later, "z", 5)]);
+        let mut theirs = vault(&[]);
+        theirs.tombstones = vec![
+            Tombstone {
+                id: gone.id,
+                deleted_at: gone.updated_at + TimeDelta::minutes(1),
+            },
+            Tombstone {
+                id: edited_later.id,
+                deleted_at: edited_later.updated_at + TimeDelta::minutes(1),
+            },
+        ];
+
+        let report = merge(&mut ours, &theirs, None);
+        assert_eq!(report.deleted, ["Old"]);
+        assert!(ours.trash.is_empty());
+        assert_eq!(report.conflicts[0].kind, ConflictKind::DeletedThere);
+        assert_eq!(ours.secrets.len(), 1);
+        assert_eq!(ours.tombstones.len(), 1);
+        assert_eq!(ours.tombstones[0].id, gone.id);
+
+        // The deleted secret does not come back from a stale copy.
+        assert!(merge(&mut ours, &vault(&[&gone]), None).is_empty());
+        assert_eq!(ours.secrets.len(), 1);
+    }
+
+    #[test]
+    fn test_trash_travels_like_an_edit() {
+        let shared = Secret::new("GitHub", "a");
+        let mut trashed = shared.clone();
+        trashed.deleted_at = Some(shared.updated_at + TimeDelta::minutes(1));
+
+        let mut ours = vault(&[&shared]);
+        let mut theirs = vault(&[]);
+        theirs.trash.push(trashed.clone());
+        let report = merge(&mut ours, &theirs, None);
+        assert_eq!(report.deleted, ["GitHub"]);
+        assert!(ours.secrets.is_empty());
+        assert_eq!(ours.trash, [trashed.clone()]);
+
+        // Restored later on the other side: back out of the trash.
+        let mut restored = trashed.clone();
+        restored.deleted_at = None;
+        restored.updated_at += TimeDelta::minutes(2);
+        let report = merge(&mut ours, &vault(&[&restored]), None);
+        assert_eq!(report.updated, ["GitHub"]);
+        assert!(ours.trash.is_empty());
+        assert_eq!(ours.secrets.len(), 1);
+    }
+
+    #[test]
+    fn test_other_differences_are_conflicts() {
+        let shared = Secret::new("GitHub", "a");
+        let mut ours = shared.clone();
+        ours.username = Some("ours".to_string());
+        ours.updated_at += TimeDelta::minutes(2);
+        let mut theirs = shared.clone();
+        theirs.notes = Some("theirs".to_string().into());
+        theirs.updated_at += TimeDelta::minutes(1);
+
+        let mut merged = vault(&[&ours]);
+        let report = merge(&mut merged, &vault(&[&theirs]), None);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::Overwritten);
+        assert_eq!(merged.secrets[0].username.as_deref(), Some("ours"));
+        assert_eq!(merged.secrets[0].notes, None);
+    }
+
+    #[test]
+    fn test_base_keeps_changes_to_different_values() {
+        let base = Secret::new("GitHub", "a");
+        let mut ours = base.clone();
+        ours.username = Some("ours".to_string());
+        ours.updated_at += TimeDelta::minutes(2);
+        let mut theirs = edited(&base, "b", 1);
+        theirs.notes = Some("theirs".to_string().into());
+
+        let mut merged = vault(&[&ours]);
+        let report = merge(&mut merged, &vault(&[&theirs]), Some(&vault(&[&base])));
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.updated, ["GitHub"]);
+        let s = &merged.secrets[0];
+        assert_eq!(s.username.as_deref(), Some("ours"));
+        assert_eq!(s.notes.as_deref().map(String::as_str), Some("theirs"));
+        assert_eq!(s.password.as_str(), "b");
+        assert_eq!(s.history[0].password.as_str(), "a");
+        assert_eq!(s.updated_at, ours.updated_at);
+
+        // Nothing left to take on a second pass.
+        let report = merge(&mut merged, &vault(&[&theirs]), Some(&vault(&[&base])));
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_base_reports_values_changed_on_both_sides() {
+        let base = Secret::new("GitHub", "a");
+        let mut ours = edited(&base, "ours", 2);
+        ours.username = Some("ours".to_string());
+        let mut theirs = base.clone();
+        theirs.username = Some("theirs".to_string());
+        theirs.url = Some("https://github.com".to_string());
+        theirs.updated_at += TimeDelta::minutes(1);
+
+        let mut merged = vault(&[&ours]);
+        let report = merge(&mut merged, &vault(&[&theirs]), Some(&vault(&[&base])));
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::Overwritten);
+        let s = &merged.secrets[0];
+        assert_eq!(s.username.as_deref(), Some("ours"));
+        assert_eq!(s.url.as_deref(), Some("https://github.com"));
+        assert_eq!(s.password.as_str(), "ours");
+
+        let theirs = edited(&base, "theirs", 1);
+        let mut merged = vault(&[&edited(&base, "ours", 2)]);
+        let report = merge(&mut merged, &vault(&[&theirs]), Some(&vault(&[&base])));
+        assert_eq!(report.conflicts[0].kind, ConflictKind::BothChanged);
+        assert_eq!(merged.secrets[0].password.as_str(), "ours");
+        assert_eq!(merged.secrets[0].history[0].password.as_str(), "theirs");
+    }
+
+    #[test]
+    fn test_hotp_counter_never_goes_back() {
+        let mut shared = Secret::new("GitHub", "a");
+        shared.otp = Some(
+            crate::core::Otp::parse("otpauth://hotp/x?secret=JBSWY3DPEHPK3PXP&counter=1").unwrap(),
+        );
+        let mut used = shared.clone();
+        used.otp.as_mut().unwrap().advance().unwrap();
+        let renamed = {
+            let mut s = shared.clone();
+            s.updated_at += TimeDelta::minutes(1);
+            s
+        };
+
+        let mut ours = vault(&[&used]);
+        let report = merge(&mut ours, &vault(&[&renamed]), None);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(
+            ours.secrets[0].otp.as_ref().unwrap().kind,
+            OtpKind::Hotp { counter: 2 }
+        );
+    }
+}
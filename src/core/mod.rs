@@ -1,9 +1,27 @@
+pub mod audit;
+pub mod backup;
+pub mod breach;
+pub mod entry;
 pub mod error;
+pub mod export;
 pub mod generator;
+pub mod group;
+pub mod import;
+pub mod kdf;
+pub mod lock;
 pub mod manager;
+pub mod memory;
+pub mod merge;
+pub mod otp;
 pub mod secret;
+pub mod strength;
+pub mod sync;
+pub mod undo;
 pub mod vault_data;
 
-pub use generator::{generate, GeneratorConfig};
+pub use entry::EntryKind;
+pub use generator::{generate, GeneratorConfig, GeneratorMode, PassphraseConfig};
 pub use manager::VaultManager;
-pub use secret::Secret;
+pub use otp::Otp;
+pub use secret::{CustomField, FieldKind, Secret};
+pub use strength::Strength;
@@ -0,0 +1,418 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{digest::KeyInit, Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use url::Url;
+use zeroize::Zeroizing;
+
+use super::error::CoreError;
+use super::secret::SecretString;
+
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+/// HMAC hash function used to derive one-time codes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    fn parse(s: &str) -> Result<Self, CoreError> {
+        match s.to_ascii_uppercase().as_str() {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            other => Err(CoreError::InvalidOtp(format!(
+                "unsupported algorithm '{other}'"
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+}
+
+/// Time-based (RFC 6238) or counter-based (RFC 4226) code generation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpKind {
+    Totp { period: u64 },
+    Hotp { counter: u64 },
+}
+
+/// A one-time password seed attached to a secret.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Otp {
+    /// Base32-encoded shared secret, upper-case, without padding.
+    pub secret: SecretString,
+    pub kind: OtpKind,
+    pub digits: u32,
+    pub algorithm: OtpAlgorithm,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+impl Otp {
+    /// Parse either a raw base32 seed (TOTP, 6 digits, 30 s, SHA1) or a full
+    /// `otpauth://totp/...` / `otpauth://hotp/...` URI.
+    pub fn parse(input: &str) -> Result<Self, CoreError> {
+        let input = input.trim();
+        if input.to_ascii_lowercase().starts_with("otpauth://") {
+            Self::parse_uri(input)
+        } else {
+            let secret = normalize_base32(input)?;
+            Ok(Self {
+                secret,
+                kind: OtpKind::Totp {
+                    period: DEFAULT_PERIOD,
+                },
+                digits: DEFAULT_DIGITS,
+                algorithm: OtpAlgorithm::Sha1,
+                issuer: None,
+                account: None,
+            })
+        }
+    }
+
+    fn parse_uri(input: &str) -> Result<Self, CoreError> {
+        let url = Url::parse(input).map_err(|e| CoreError::InvalidOtp(e.to_string()))?;
+
+        let mut secret = None;
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+        let mut counter = None;
+        let mut algorithm = OtpAlgorithm::Sha1;
+        let mut issuer = None;
+
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "secret" => secret = Some(normalize_base32(&value)?),
+                "digits" => {
+                    digits = value
+                        .parse()
+                        .map_err(|_| CoreError::InvalidOtp(format!("invalid digits '{value}'")))?
+                }
+                "period" => {
+                    period = value
+                        .parse()
+                        .map_err(|_| CoreError::InvalidOtp(format!("invalid period '{value}'")))?
+                }
+                "counter" => {
+                    counter =
+                        Some(value.parse().map_err(|_| {
+                            CoreError::InvalidOtp(format!("invalid counter '{value}'"))
+                        })?)
+                }
+                "algorithm" => algorithm = OtpAlgorithm::parse(&value)?,
+                "issuer" => issuer = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        let secret = secret.ok_or_else(|| CoreError::InvalidOtp("missing secret".to_string()))?;
+        check_digits(digits)?;
+        check_period(period)?;
+
+        let kind = match url.host_str().map(|h| h.to_ascii_lowercase()).as_deref() {
+            Some("totp") => OtpKind::Totp { period },
+            Some("hotp") => OtpKind::Hotp {
+                counter: counter.unwrap_or(0),
+            },
+            other => {
+                return Err(CoreError::InvalidOtp(format!(
+                    "unknown type '{}'",
+                    other.unwrap_or("")
+                )))
+            }
+        };
+
+        // Label is `Issuer:account` or just `account`, percent-encoded.
+        let label = url.path().trim_start_matches('/');
+        let label = percent_decode(label);
+        let (label_issuer, account) = match label.split_once(':') {
+            Some((i, a)) => (Some(i.trim().to_string()), a.trim().to_string()),
+            None => (None, label.trim().to_string()),
+        };
+
+        Ok(Self {
+            secret,
+            kind,
+            digits,
+            algorithm,
+            issuer: issuer.or(label_issuer).filter(|s| !s.is_empty()),
+            account: Some(account).filter(|s| !s.is_empty()),
+        })
+    }
+
+    /// Render this seed back as an `otpauth://` URI.
+    pub fn to_uri(&self) -> SecretString {
+        let kind = match self.kind {
+            OtpKind::Totp { .. } => "totp",
+            OtpKind::Hotp { .. } => "hotp",
+        };
+        let account = self.account.as_deref().unwrap_or("");
+        let label = match &self.issuer {
+            Some(issuer) => format!("{issuer}:{account}"),
+            None => account.to_string(),
+        };
+
+        let mut url = Url::parse(&format!("otpauth://{kind}/")).expect("static URI is valid");
+        url.set_path(&label);
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("secret", &self.secret)
+                .append_pair("digits", &self.digits.to_string())
+                .append_pair("algorithm", self.algorithm.as_str());
+            match self.kind {
+                OtpKind::Totp { period } => query.append_pair("period", &period.to_string()),
+                OtpKind::Hotp { counter } => query.append_pair("counter", &counter.to_string()),
+            };
+            if let Some(issuer) = &self.issuer {
+                query.append_pair("issuer", issuer);
+            }
+        }
+        SecretString::new(url.into())
+    }
+
+    /// Compute the code for the current moment (TOTP) or current counter (HOTP).
+    pub fn generate(&self, unix_time: u64) -> Result<String, CoreError> {
+        let counter = match self.kind {
+            OtpKind::Totp { period } => unix_time / check_period(period)?,
+            OtpKind::Hotp { counter } => counter,
+        };
+        self.code_for_counter(counter)
+    }
+
+    /// Seconds left before the current TOTP code expires. `None` for HOTP.
+    pub fn seconds_remaining(&self, unix_time: u64) -> Result<Option<u64>, CoreError> {
+        match self.kind {
+            OtpKind::Totp { period } => Ok(Some(period - unix_time % check_period(period)?)),
+            OtpKind::Hotp { .. } => Ok(None),
+        }
+    }
+
+    /// Advance the HOTP counter. No-op for TOTP seeds.
+    pub fn advance(&mut self) -> Result<(), CoreError> {
+        if let OtpKind::Hotp { counter } = &mut self.kind {
+            *counter = counter
+                .checked_add(1)
+                .ok_or_else(|| CoreError::InvalidOtp("counter overflow".to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// RFC 4226 HOTP value for the given counter, zero-padded to `digits`.
+    fn code_for_counter(&self, counter: u64) -> Result<String, CoreError> {
+        // Seeds loaded from a vault file were not checked by `parse`.
+        check_digits(self.digits)?;
+        let key = BASE32_NOPAD
+            .decode(self.secret.as_bytes())
+            .map(Zeroizing::new)
+            .map_err(|e| CoreError::InvalidOtp(e.to_string()))?;
+        let msg = counter.to_be_bytes();
+
+        let digest = match self.algorithm {
+            OtpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&key, &msg),
+            OtpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&key, &msg),
+            OtpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&key, &msg),
+        };
+
+        // Dynamic truncation (RFC 4226 §5.3).
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        let code = binary % 10u32.pow(self.digits);
+        Ok(format!("{code:0width$}", width = self.digits as usize))
+    }
+}
+
+fn check_digits(digits: u32) -> Result<u32, CoreError> {
+    if !(6..=8).contains(&digits) {
+        return Err(CoreError::InvalidOtp(format!(
+            "digits must be between 6 and 8, got {digits}"
+        )));
+    }
+    Ok(digits)
+}
+
+fn check_period(period: u64) -> Result<u64, CoreError> {
+    if period == 0 {
+        return Err(CoreError::InvalidOtp("period must be positive".to_string()));
+    }
+    Ok(period)
+}
+
+/// Current Unix time in seconds, as used by TOTP.
+pub fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+fn hmac_digest<M: Mac + KeyInit>(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(msg);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Strip spaces/dashes/padding, upper-case, and check the result decodes.
+fn normalize_base32(input: &str) -> Result<SecretString, CoreError> {
+    let cleaned = SecretString::new(
+        input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+            .map(|c| c.to_ascii_uppercase())
+            .collect(),
+    );
+    if cleaned.is_empty() {
+        return Err(CoreError::InvalidOtp("empty secret".to_string()));
+    }
+    BASE32_NOPAD
+        .decode(cleaned.as_bytes())
+        .map(Zeroizing::new)
+        .map_err(|_| CoreError::InvalidOtp("secret is not valid base32".to_string()))?;
+    Ok(cleaned)
+}
+
+fn percent_decode(s: &str) -> String {
+    url::form_urlencoded::parse(format!("x={s}").as_bytes())
+        .next()
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(ascii: &str, algorithm: OtpAlgorithm, kind: OtpKind) -> Otp {
+        Otp {
+            secret: SecretString::new(BASE32_NOPAD.encode(ascii.as_bytes())),
+            kind,
+            digits: 8,
+            algorithm,
+            issuer: None,
+            account: None,
+        }
+    }
+
+    // RFC 6238 Appendix B test vectors
+    #[test]
+    fn test_totp_rfc6238_vectors() {
+        let totp = OtpKind::Totp { period: 30 };
+        let sha1 = seed("12345678901234567890", OtpAlgorithm::Sha1, totp);
+        let sha256 = seed(
+            "12345678901234567890123456789012",
+            OtpAlgorithm::Sha256,
+            totp,
+        );
+        let sha512 = seed(
+            "1234567890123456789012345678901234567890123456789012345678901234",
+            OtpAlgorithm::Sha512,
+            totp,
+        );
+
+        assert_eq!(sha1.generate(59).unwrap(), "94287082");
+        assert_eq!(sha256.generate(59).unwrap(), "46119246");
+        assert_eq!(sha512.generate(59).unwrap(), "90693936");
+        assert_eq!(sha1.generate(1111111109).unwrap(), "07081804");
+        assert_eq!(sha1.generate(20000000000).unwrap(), "65353130");
+    }
+
+    // RFC 4226 Appendix D test vectors
+    #[test]
+    fn test_hotp_rfc4226_vectors() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        let mut otp = seed(
+            "12345678901234567890",
+            OtpAlgorithm::Sha1,
+            OtpKind::Hotp { counter: 0 },
+        );
+        otp.digits = 6;
+        for code in expected {
+            assert_eq!(otp.generate(0).unwrap(), code);
+            otp.advance().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_raw_base32() {
+        let otp = Otp::parse("jbsw y3dp ehpk 3pxp").unwrap();
+        assert_eq!(otp.secret.as_str(), "JBSWY3DPEHPK3PXP");
+        assert_eq!(otp.kind, OtpKind::Totp { period: 30 });
+        assert_eq!(otp.digits, 6);
+    }
+
+    #[test]
+    fn test_parse_uri() {
+        let otp = Otp::parse(
+            "otpauth://totp/ACME%20Co:john@example.com?secret=JBSWY3DPEHPK3PXP\
+             &issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(otp.kind, OtpKind::Totp { period: 60 });
+        assert_eq!(otp.digits, 8);
+        assert_eq!(otp.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(otp.issuer.as_deref(), Some("ACME Co"));
+        assert_eq!(otp.account.as_deref(), Some("john@example.com"));
+    }
+
+    #[test]
+    fn test_uri_roundtrip() {
+        let otp = Otp::parse("otpauth://hotp/Svc:me?secret=JBSWY3DPEHPK3PXP&counter=7").unwrap();
+        assert_eq!(Otp::parse(&otp.to_uri()).unwrap(), otp);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Otp::parse("not base32!").is_err());
+        assert!(Otp::parse("otpauth://totp/x?digits=6").is_err());
+        assert!(Otp::parse("otpauth://totp/x?secret=JBSWY3DP&digits=12").is_err());
+        assert!(Otp::parse("otpauth://push/x?secret=JBSWY3DP").is_err());
+    }
+
+    #[test]
+    fn test_seconds_remaining() {
+        let otp = Otp::parse("JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(otp.seconds_remaining(0).unwrap(), Some(30));
+        assert_eq!(otp.seconds_remaining(59).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_out_of_range_seed_from_a_vault() {
+        let mut otp = Otp::parse("JBSWY3DPEHPK3PXP").unwrap();
+        otp.kind = OtpKind::Totp { period: 0 };
+        assert!(matches!(otp.generate(59), Err(CoreError::InvalidOtp(_))));
+        assert!(matches!(
+            otp.seconds_remaining(59),
+            Err(CoreError::InvalidOtp(_))
+        ));
+
+        otp.kind = OtpKind::Totp { period: 30 };
+        for digits in [0, 5, 9, 10, u32::MAX] {
+            otp.digits = digits;
+            assert!(matches!(otp.generate(59), Err(CoreError::InvalidOtp(_))));
+        }
+
+        otp.kind = OtpKind::Hotp { counter: u64::MAX };
+        assert!(matches!(otp.advance(), Err(CoreError::InvalidOtp(_))));
+        assert_eq!(otp.kind, OtpKind::Hotp { counter: u64::MAX });
+    }
+}
@@ -1,19 +1,55 @@
-use chrono::{DateTime, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use super::entry::EntryKind;
+use super::otp::Otp;
+
+/// A string that is overwritten with zeros when dropped. Used for every
+/// value that would be dangerous to leave behind in freed memory.
+pub type SecretString = Zeroizing<String>;
 
 /// A single secret entry stored in the vault.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Secret {
     pub id: Uuid,
     pub name: String,
+    /// Entries saved before kinds existed load as logins.
+    #[serde(default)]
+    pub kind: EntryKind,
     pub username: Option<String>,
-    pub password: String,
+    pub password: SecretString,
     pub url: Option<String>,
-    pub notes: Option<String>,
+    pub notes: Option<SecretString>,
     pub tags: Vec<String>,
+    /// Group path such as `clients/acme/prod`, see `group::normalize`;
+    /// `None` at the top level.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// User-defined extra fields, in display order.
+    #[serde(default)]
+    pub fields: Vec<CustomField>,
+    /// Previous passwords, most recently retired first. Maintained by
+    /// `VaultManager::update`; bounded to `PASSWORD_HISTORY_LIMIT` entries.
+    #[serde(default)]
+    pub history: Vec<PasswordEntry>,
+    /// Optional TOTP/HOTP seed for two-factor codes.
+    #[serde(default)]
+    pub otp: Option<Otp>,
+    /// How many times the current password appears in the Pwned Passwords
+    /// dump, as found by the last breach check. Maintained by `VaultManager`;
+    /// cleared when the password changes.
+    #[serde(default)]
+    pub breach_count: Option<u64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the secret was moved to the trash; `None` while it is live.
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl Secret {
@@ -24,13 +60,20 @@ impl Secret {
         Self {
             id: Uuid::new_v4(),
             name: name.into(),
+            kind: EntryKind::Login,
             username: None,
-            password: password.into(),
+            password: SecretString::new(password.into()),
             url: None,
             notes: None,
             tags: Vec::new(),
+            group: None,
+            fields: Vec::new(),
+            history: Vec::new(),
+            otp: None,
+            breach_count: None,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
         }
     }
 
@@ -38,4 +81,187 @@ impl Secret {
     pub fn touch(&mut self) {
         self.updated_at = Utc::now();
     }
+
+    /// The value of the custom field labelled `label`.
+    pub fn field(&self, label: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| f.label == label)
+            .map(|f| f.value.as_str())
+    }
+
+    /// The value named by `EntryKind::main_field`, if set.
+    pub fn main_value(&self) -> Option<&str> {
+        let value = match self.kind {
+            EntryKind::Login | EntryKind::Wifi => Some(self.password.as_str()),
+            EntryKind::Note => self.notes.as_deref().map(String::as_str),
+            kind => self.field(kind.main_field()),
+        };
+        value.filter(|v| !v.is_empty())
+    }
+
+    /// The last time the secret was edited or moved to the trash.
+    pub fn changed_at(&self) -> DateTime<Utc> {
+        self.deleted_at
+            .map_or(self.updated_at, |d| d.max(self.updated_at))
+    }
+}
+
+/// A password that was replaced, and when.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PasswordEntry {
+    pub password: SecretString,
+    pub retired_at: DateTime<Utc>,
+}
+
+/// How a custom field's value is interpreted and displayed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    #[default]
+    Text,
+    /// Masked in the UI and excluded from fuzzy search.
+    Hidden,
+    Url,
+    Email,
+    /// ISO 8601 calendar date (`YYYY-MM-DD`).
+    Date,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 5] = [
+        FieldKind::Text,
+        FieldKind::Hidden,
+        FieldKind::Url,
+        FieldKind::Email,
+        FieldKind::Date,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FieldKind::Text => "text",
+            FieldKind::Hidden => "hidden",
+            FieldKind::Url => "url",
+            FieldKind::Email => "email",
+            FieldKind::Date => "date",
+        }
+    }
+
+    /// The next kind in `ALL`, wrapping around. Used to cycle kinds in the form.
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|k| *k == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// Returns an error message if `value` is not acceptable for this kind.
+    pub fn check(self, value: &str) -> Option<&'static str> {
+        match self {
+            FieldKind::Email if !value.contains('@') => Some("Email field needs an '@'"),
+            FieldKind::Url if url::Url::parse(value).is_err() => {
+                Some("URL field must be a full URL, e.g. https://example.com")
+            }
+            FieldKind::Date if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_err() => {
+                Some("Date field must be YYYY-MM-DD")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FieldKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown field kind '{s}'"))
+    }
+}
+
+/// A labelled, user-defined value attached to a secret (API key ID,
+/// security question, recovery code, account number…).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CustomField {
+    pub label: String,
+    pub value: SecretString,
+    #[serde(default)]
+    pub kind: FieldKind,
+}
+
+impl CustomField {
+    pub fn new(label: impl Into<String>, value: impl Into<String>, kind: FieldKind) -> Self {
+        Self {
+            label: label.into(),
+            value: SecretString::new(value.into()),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_kind_check() {
+        assert_eq!(FieldKind::Email.check("jb@example.com"), None);
+        assert!(FieldKind::Email.check("jb.example.com").is_some());
+        assert_eq!(FieldKind::Url.check("https://example.com/login"), None);
+        assert!(FieldKind::Url.check("example.com").is_some());
+        assert!(FieldKind::Url.check("https://exa mple.com").is_some());
+        assert_eq!(FieldKind::Date.check("2026-02-28"), None);
+        assert!(FieldKind::Date.check("2026-02-30").is_some());
+        assert!(FieldKind::Date.check("28/02/2026").is_some());
+        assert_eq!(FieldKind::Text.check("anything"), None);
+        assert_eq!(FieldKind::Hidden.check(""), None);
+    }
+
+    #[test]
+    fn test_field_kind_parse_and_cycle() {
+        for kind in FieldKind::ALL {
+            assert_eq!(kind.as_str().parse::<FieldKind>(), Ok(kind));
+        }
+        assert_eq!("EMAIL".parse::<FieldKind>(), Ok(FieldKind::Email));
+        assert!("number".parse::<FieldKind>().is_err());
+
+        // `next` visits every kind once before coming back.
+        let mut kind = FieldKind::Text;
+        let mut seen = Vec::new();
+        for _ in FieldKind::ALL {
+            seen.push(kind);
+            kind = kind.next();
+        }
+        assert_eq!(seen, FieldKind::ALL);
+        assert_eq!(kind, FieldKind::Text);
+    }
+
+    #[test]
+    fn test_secret_from_before_fields_loads() {
+        // As saved before custom fields, kinds, groups and history existed.
+        let json = r#"{
+            "id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "name": "GitHub",
+            "username": "jb",
+            "password": "s3cr3t",
+            "url": null,
+            "notes": null,
+            "tags": [],
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z"
+        }"#;
+        let secret: Secret = serde_json::from_str(json).unwrap();
+        assert_eq!(secret.password.as_str(), "s3cr3t");
+        assert!(secret.fields.is_empty());
+        assert_eq!(secret.kind, EntryKind::Login);
+        assert_eq!(secret.group, None);
+        assert!(secret.history.is_empty());
+        assert_eq!(secret.deleted_at, None);
+    }
 }
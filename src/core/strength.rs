@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use super::generator::eff_words;
+
+/// Guesses per second assumed for the crack-time estimate: an offline attack
+/// on a fast, unsalted hash (MD5, SHA-1) with a few GPUs, the worst case once
+/// a site that stored the password leaks its database.
+const GUESSES_PER_SECOND: f64 = 1e10;
+
+/// Entropy thresholds (bits) between the five scores.
+const SCORE_THRESHOLDS: [f64; 4] = [28.0, 40.0, 56.0, 72.0];
+
+/// The most common passwords, most common first: the top of SecLists'
+/// `10-million-password-list-top-100.txt` (Daniel Miessler, MIT license),
+/// with its vulgar entries and `klaster` left out, then the default
+/// passwords of devices and services. One per line.
+const COMMON_PASSWORDS: &str = include_str!("wordlists/common_passwords.txt");
+
+/// Keyboard rows, for spotting runs like `qwerty` or `asdf`.
+const KEYBOARD_ROWS: [&str; 4] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+
+/// What a pattern found in a password is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weakness {
+    CommonPassword,
+    DictionaryWord,
+    KeyboardPattern,
+    Sequence,
+    Repetition,
+    Year,
+    TooShort,
+}
+
+impl Weakness {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Weakness::CommonPassword => "common password",
+            Weakness::DictionaryWord => "dictionary word",
+            Weakness::KeyboardPattern => "keyboard pattern",
+            Weakness::Sequence => "sequence like abc or 123",
+            Weakness::Repetition => "repeated characters",
+            Weakness::Year => "looks like a year",
+            Weakness::TooShort => "too short",
+        }
+    }
+}
+
+/// Estimated strength of a password.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    /// Estimated entropy, in bits, of the cheapest way to guess the password.
+    pub bits: f64,
+    /// 0 (very weak) to 4 (very strong).
+    pub score: u8,
+    /// Patterns that made the password weaker, in order of appearance.
+    pub weaknesses: Vec<Weakness>,
+}
+
+impl Strength {
+    pub fn label(&self) -> &'static str {
+        match self.score {
+            0 => "very weak",
+            1 => "weak",
+            2 => "fair",
+            3 => "strong",
+            _ => "very strong",
+        }
+    }
+
+    /// Average time to guess the password (half the search space), e.g.
+    /// `3 days` or `centuries`.
+    pub fn crack_time(&self) -> String {
+        let seconds = 2f64.powf(self.bits - 1.0) / GUESSES_PER_SECOND;
+        let units = [
+            ("second", 60.0),
+            ("minute", 60.0),
+            ("hour", 24.0),
+            ("day", 365.0),
+            ("year", 100.0),
+        ];
+        if seconds < 1.0 {
+            return "instant".to_string();
+        }
+        let mut value = seconds;
+        for (unit, next) in units {
+            if value < next {
+                let n = value.floor() as u64;
+                return format!("{n} {unit}{}", if n == 1 { "" } else { "s" });
+            }
+            value /= next;
+        }
+        "centuries".to_string()
+    }
+}
+
+/// A pattern covering `chars[start..end]`, guessable with `bits` of entropy.
+struct Match {
+    start: usize,
+    end: usize,
+    bits: f64,
+    weakness: Weakness,
+}
+
+/// Estimate the strength of `password`, zxcvbn-style: find the patterns an
+/// attacker would try (common passwords, dictionary words with capitals and
+/// l33t substitutions, keyboard runs, sequences, repeats, years), then take
+/// the cheapest way to cover the whole password with patterns and
+/// brute-forced characters.
+pub fn estimate(password: &str) -> Strength {
+    let chars: Vec<char> = password.chars().collect();
+    if chars.is_empty() {
+        return Strength {
+            bits: 0.0,
+            score: 0,
+            weaknesses: Vec::new(),
+        };
+    }
+
+    let per_char = cardinality(&chars).log2();
+    let mut matches = Vec::new();
+    dictionary_matches(&chars, &mut matches);
+    keyboard_matches(&chars, &mut matches);
+    sequence_matches(&chars, &mut matches);
+    repeat_matches(&chars, per_char, &mut matches);
+    year_matches(&chars, &mut matches);
+
+    // best[i]: cheapest entropy covering chars[..i], and how we got there.
+    let n = chars.len();
+    let mut best: Vec<(f64, Option<usize>)> = vec![(f64::INFINITY, None); n + 1];
+    best[0] = (0.0, None);
+    for i in 1..=n {
+        best[i] = (best[i - 1].0 + per_char, None);
+        for (m_idx, m) in matches.iter().enumerate().filter(|(_, m)| m.end == i) {
+            let bits = best[m.start].0 + m.bits;
+            if bits < best[i].0 {
+                best[i] = (bits, Some(m_idx));
+            }
+        }
+    }
+
+    let mut weaknesses = Vec::new();
+    let mut i = n;
+    while i > 0 {
+        match best[i].1 {
+            Some(m_idx) => {
+                let m = &matches[m_idx];
+                if !weaknesses.contains(&m.weakness) {
+                    weaknesses.push(m.weakness);
+                }
+                i = m.start;
+            }
+            None => i -= 1,
+        }
+    }
+    weaknesses.reverse();
+
+    let bits = best[n].0;
+    let score = SCORE_THRESHOLDS.iter().filter(|t| bits >= **t).count() as u8;
+    if n < 10 && score < 3 {
+        weaknesses.push(Weakness::TooShort);
+    }
+    Strength {
+        bits,
+        score,
+        weaknesses,
+    }
+}
+
+/// Size of the alphabet a brute-force attack needs for these characters.
+fn cardinality(chars: &[char]) -> f64 {
+    let mut size = 0.0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        size += 26.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        size += 26.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        size += 10.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        size += 33.0;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100.0;
+    }
+    size
+}
+
+fn dictionary() -> &'static HashSet<&'static str> {
+    static WORDS: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| eff_words().filter(|w| w.len() >= 3).collect())
+}
+
+/// Undo common l33t substitutions.
+fn unleet(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c.to_ascii_lowercase(),
+    }
+}
+
+fn dictionary_matches(chars: &[char], out: &mut Vec<Match>) {
+    let dict = dictionary();
+    let dict_bits = (dict.len() as f64).log2();
+    let n = chars.len();
+
+    for start in 0..n {
+        for end in (start + 3)..=n.min(start + 12) {
+            let slice = &chars[start..end];
+            let plain: String = slice.iter().map(|c| c.to_ascii_lowercase()).collect();
+            let unleeted: String = slice.iter().map(|c| unleet(*c)).collect();
+
+            // Extra bits for capitals and substitutions an attacker must try.
+            let uppercase = slice.iter().filter(|c| c.is_ascii_uppercase()).count();
+            let mut extra = match uppercase {
+                0 => 0.0,
+                u if u == slice.len() || (u == 1 && slice[0].is_ascii_uppercase()) => 1.0,
+                u => u as f64,
+            };
+            if unleeted != plain {
+                extra += 1.0;
+            }
+
+            let common = COMMON_PASSWORDS
+                .lines()
+                .position(|p| p == plain || p == unleeted);
+            if let Some(rank) = common {
+                out.push(Match {
+                    start,
+                    end,
+                    bits: ((rank + 2) as f64).log2() + extra,
+                    weakness: Weakness::CommonPassword,
+                });
+            } else if end - start >= 4
+                && (dict.contains(plain.as_str()) || dict.contains(unleeted.as_str()))
+            {
+                out.push(Match {
+                    start,
+                    end,
+                    bits: dict_bits + extra,
+                    weakness: Weakness::DictionaryWord,
+                });
+            }
+        }
+    }
+}
+
+fn keyboard_matches(chars: &[char], out: &mut Vec<Match>) {
+    let position = |c: char| {
+        let c = c.to_ascii_lowercase();
+        KEYBOARD_ROWS
+            .iter()
+            .enumerate()
+            .find_map(|(row, keys)| keys.find(c).map(|col| (row, col as isize)))
+    };
+    let keys: usize = KEYBOARD_ROWS.iter().map(|r| r.len()).sum();
+
+    runs(chars, 4, |a, b| match (position(a), position(b)) {
+        (Some((ra, ca)), Some((rb, cb))) => ra == rb && (ca - cb).abs() == 1,
+        _ => false,
+    })
+    .into_iter()
+    .for_each(|(start, end)| {
+        out.push(Match {
+            start,
+            end,
+            bits: (keys as f64 * 2.0 * (end - start) as f64).log2(),
+            weakness: Weakness::KeyboardPattern,
+        })
+    });
+}
+
+fn sequence_matches(chars: &[char], out: &mut Vec<Match>) {
+    runs(chars, 3, |a, b| {
+        a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() && (a as i32 - b as i32).abs() == 1
+    })
+    .into_iter()
+    .for_each(|(start, end)| {
+        let base = if chars[start].is_ascii_digit() {
+            10.0
+        } else {
+            26.0
+        };
+        out.push(Match {
+            start,
+            end,
+            bits: (base * 2.0 * (end - start) as f64).log2(),
+            weakness: Weakness::Sequence,
+        })
+    });
+}
+
+fn repeat_matches(chars: &[char], per_char: f64, out: &mut Vec<Match>) {
+    runs(chars, 3, |a, b| a == b)
+        .into_iter()
+        .for_each(|(start, end)| {
+            out.push(Match {
+                start,
+                end,
+                bits: per_char + ((end - start) as f64).log2(),
+                weakness: Weakness::Repetition,
+            })
+        });
+}
+
+fn year_matches(chars: &[char], out: &mut Vec<Match>) {
+    for start in 0..chars.len().saturating_sub(3) {
+        let slice: String = chars[start..start + 4].iter().collect();
+        if let Ok(year) = slice.parse::<u32>() {
+            if (1900..=2099).contains(&year) {
+                out.push(Match {
+                    start,
+                    end: start + 4,
+                    bits: 200f64.log2(),
+                    weakness: Weakness::Year,
+                });
+            }
+        }
+    }
+}
+
+/// Maximal runs of at least `min_len` characters where every neighbouring
+/// pair satisfies `linked`, as `(start, end)` ranges.
+fn runs(
+    chars: &[char],
+    min_len: usize,
+    linked: impl Fn(char, char) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    for i in 1..=chars.len() {
+        if i == chars.len() || !linked(chars[i - 1], chars[i]) {
+            if i - start >= min_len {
+                found.push((start, i));
+            }
+            start = i;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        let s = estimate("");
+        assert_eq!(s.score, 0);
+        assert_eq!(s.bits, 0.0);
+    }
+
+    #[test]
+    fn test_common_passwords_are_very_weak() {
+        for pwd in ["password", "123456", "qwerty", "P@ssw0rd", "letmein"] {
+            let s = estimate(pwd);
+            assert_eq!(s.score, 0, "{pwd}: {} bits", s.bits);
+            assert!(s.weaknesses.contains(&Weakness::CommonPassword), "{pwd}");
+        }
+    }
+
+    #[test]
+    fn test_patterns_detected() {
+        assert!(estimate("asdfghjk")
+            .weaknesses
+            .contains(&Weakness::KeyboardPattern));
+        assert!(estimate("abcdefgh")
+            .weaknesses
+            .contains(&Weakness::Sequence));
+        assert!(estimate("zzzzzzzz")
+            .weaknesses
+            .contains(&Weakness::Repetition));
+        assert!(estimate("Kitten1987").weaknesses.contains(&Weakness::Year));
+        assert!(estimate("Kitten1987")
+            .weaknesses
+            .contains(&Weakness::DictionaryWord));
+    }
+
+    #[test]
+    fn test_patterns_cost_less_than_random() {
+        let random = estimate("xq7RvM2p");
+        let word = estimate("sunshine");
+        assert!(word.bits < random.bits);
+        assert!(random.weaknesses.contains(&Weakness::TooShort));
+    }
+
+    #[test]
+    fn test_generated_passwords_are_very_strong() {
+        let s = estimate("k#9Lw!q2Vz$8mR@4xT&e");
+        assert_eq!(s.score, 4);
+        assert!(s.weaknesses.is_empty());
+        assert_eq!(s.crack_time(), "centuries");
+    }
+
+    #[test]
+    fn test_passphrase_counts_words() {
+        // Six diceware words: ~13 bits each, plus the separators.
+        let s = estimate("abacus-cactus-gopher-lantern-mosaic-zoom");
+        assert!(s.weaknesses.contains(&Weakness::DictionaryWord));
+        assert!(s.bits > 72.0, "{} bits", s.bits);
+        assert_eq!(s.score, 4);
+    }
+
+    #[test]
+    fn test_crack_time_units() {
+        let at = |bits: f64| {
+            Strength {
+                bits,
+                score: 0,
+                weaknesses: Vec::new(),
+            }
+            .crack_time()
+        };
+        assert_eq!(at(30.0), "instant");
+        assert_eq!(at(40.0), "54 seconds");
+        assert_eq!(at(50.0), "15 hours");
+        assert_eq!(at(60.0), "1 year");
+    }
+}
@@ -0,0 +1,357 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use super::error::CoreError;
+use super::merge::MergeReport;
+
+/// Files that live next to the vault but must not be versioned.
+const IGNORED: &[&str] = &["*.lock", "*.backups/", "*.rekey", "*.restore"];
+
+/// What `VaultManager::sync` brought in from the remote.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pulled {
+    /// The remote had nothing new.
+    Nothing,
+    /// Only the remote changed the vault: it was taken as is.
+    Changes,
+    /// Both sides changed the vault: their secrets were merged one by one.
+    Merged(MergeReport),
+}
+
+/// How `Repo::merge` went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeOutcome {
+    UpToDate,
+    Merged,
+    /// Both sides changed the vault file. The merge is left in progress.
+    Conflict,
+}
+
+/// The git repository whose working tree is the vault's directory. Every
+/// operation shells out to the `git` binary, so the user's own settings
+/// (identity, credentials, signing) apply.
+pub struct Repo {
+    dir: PathBuf,
+}
+
+impl Repo {
+    /// The repository of the directory holding `vault_path`, if it is one.
+    pub fn open(vault_path: &Path) -> Option<Self> {
+        let dir = vault_dir(vault_path);
+        dir.join(".git").exists().then_some(Self { dir })
+    }
+
+    /// Turn the directory holding `vault_path` into a repository (unless it
+    /// already is one) that ignores locks, backups and staging files, and
+    /// commit the vault.
+    pub fn init(vault_path: &Path) -> Result<Self, CoreError> {
+        let repo = Self {
+            dir: vault_dir(vault_path),
+        };
+        if !repo.dir.join(".git").exists() {
+            repo.git(&["init", "--quiet"])?;
+        }
+        let gitignore = repo.dir.join(".gitignore");
+        if !gitignore.exists() {
+            std::fs::write(&gitignore, IGNORED.join("\n") + "\n")
+                .map_err(|e| CoreError::Sync(format!("{}: {e}", gitignore.display())))?;
+        }
+        repo.commit(&[".gitignore", file_name(vault_path)?], "Add vault")?;
+        Ok(repo)
+    }
+
+    pub fn add_remote(&self, name: &str, url: &str) -> Result<(), CoreError> {
+        self.git(&["remote", "add", name, url]).map(drop)
+    }
+
+    /// Commit the vault file if it changed since the last commit, or to
+    /// conclude a merge in progress. Returns whether a commit was made.
+    pub fn commit_vault(&self, vault_path: &Path, message: &str) -> Result<bool, CoreError> {
+        self.commit(&[file_name(vault_path)?], message)
+    }
+
+    pub fn fetch(&self, remote: &str) -> Result<(), CoreError> {
+        self.git(&["fetch", "--quiet", remote]).map(drop)
+    }
+
+    /// The branch to pull from: the upstream of the current branch, or else
+    /// the branch of the same name on `remote`. `None` before the first push.
+    pub fn upstream(&self, remote: &str) -> Result<Option<String>, CoreError> {
+        if let Ok(upstream) = self.git(&["rev-parse", "--abbrev-ref", "@{upstream}"]) {
+            return Ok(Some(upstream));
+        }
+        let branch = self.git(&["symbolic-ref", "--short", "HEAD"])?;
+        let candidate = format!("{remote}/{branch}");
+        let exists = self
+            .run(&["rev-parse", "--verify", "--quiet", &candidate])?
+            .status
+            .success();
+        Ok(exists.then_some(candidate))
+    }
+
+    /// Merge `upstream` into the current branch. A conflict on anything but
+    /// the vault file is not ours to resolve: the merge is aborted.
+    pub fn merge(&self, vault_path: &Path, upstream: &str) -> Result<MergeOutcome, CoreError> {
+        let before = self.git(&["rev-parse", "HEAD"])?;
+        let merged = self
+            .run(&[
+                "merge",
+                "--quiet",
+                "--no-edit",
+                "--allow-unrelated-histories",
+                upstream,
+            ])?
+            .status
+            .success();
+        if merged {
+            let after = self.git(&["rev-parse", "HEAD"])?;
+            return Ok(if after == before {
+                MergeOutcome::UpToDate
+            } else {
+                MergeOutcome::Merged
+            });
+        }
+
+        let conflicted = self.git(&["diff", "--name-only", "--diff-filter=U"])?;
+        if conflicted == file_name(vault_path)? {
+            return Ok(MergeOutcome::Conflict);
+        }
+        self.abort_merge();
+        Err(CoreError::Sync(if conflicted.is_empty() {
+            format!(
+                "cannot merge {upstream}; see `git status` in {}",
+                self.dir.display()
+            )
+        } else {
+            format!(
+                "conflicts outside the vault: {}",
+                conflicted.replace('\n', ", ")
+            )
+        }))
+    }
+
+    /// The other side's version of the vault file during a conflict.
+    pub fn theirs(&self, vault_path: &Path) -> Result<Vec<u8>, CoreError> {
+        let output = self.run(&["show", &format!(":3:{}", file_name(vault_path)?)])?;
+        if !output.status.success() {
+            return Err(git_error("show", &output));
+        }
+        Ok(output.stdout)
+    }
+
+    /// The version of the vault file both sides of a conflict started from,
+    /// or `None` if they have none, e.g. when each side created its vault.
+    pub fn base(&self, vault_path: &Path) -> Result<Option<Vec<u8>>, CoreError> {
+        let output = self.run(&["show", &format!(":1:{}", file_name(vault_path)?)])?;
+        Ok(output.status.success().then_some(output.stdout))
+    }
+
+    /// Best effort: put the working tree back as it was before `merge`.
+    pub fn abort_merge(&self) {
+        let _ = self.run(&["merge", "--abort"]);
+    }
+
+    /// Push the current branch to `remote`, making it the upstream.
+    pub fn push(&self, remote: &str) -> Result<(), CoreError> {
+        self.git(&["push", "--quiet", "--set-upstream", remote, "HEAD"])
+            .map(drop)
+    }
+
+    fn commit(&self, paths: &[&str], message: &str) -> Result<bool, CoreError> {
+        let mut add = vec!["add", "--"];
+        add.extend_from_slice(paths);
+        self.git(&add)?;
+
+        // A merge is concluded as a whole; outside one, commit only `paths`
+        // so that whatever else the user staged is left alone.
+        let mut commit = vec!["commit", "--quiet", "-m", message];
+        if !self.dir.join(".git").join("MERGE_HEAD").exists() {
+            let mut diff = vec!["diff", "--cached", "--quiet", "--"];
+            diff.extend_from_slice(paths);
+            if self.run(&diff)?.status.success() {
+                return Ok(false);
+            }
+            commit.push("--");
+            commit.extend_from_slice(paths);
+        }
+        self.git(&commit)?;
+        Ok(true)
+    }
+
+    /// Run git and return its trimmed standard output, or its error message.
+    fn git(&self, args: &[&str]) -> Result<String, CoreError> {
+        let output = self.run(args)?;
+        if !output.status.success() {
+            return Err(git_error(args[0], &output));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Output, CoreError> {
+        Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .map_err(|e| CoreError::Sync(format!("cannot run git: {e}")))
+    }
+}
+
+fn git_error(command: &str, output: &Output) -> CoreError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message: Vec<_> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("hint:"))
+        .collect();
+    CoreError::Sync(format!("git {command}: {}", message.join("; ")))
+}
+
+fn vault_dir(vault_path: &Path) -> PathBuf {
+    match vault_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+fn file_name(vault_path: &Path) -> Result<&str, CoreError> {
+    vault_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| CoreError::Sync(format!("unusable vault path {}", vault_path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{secret::Secret, VaultManager};
+    use serdevault::VaultFile;
+    use tempfile::tempdir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git").arg("-C").arg(dir).args(args).output();
+        assert!(output.unwrap().status.success(), "git {args:?}");
+    }
+
+    /// A working tree with an identity, so that commits do not depend on
+    /// the machine running the tests.
+    fn configure(dir: &Path) {
+        git(dir, &["config", "user.name", "valt"]);
+        git(dir, &["config", "user.email", "valt@example.invalid"]);
+        git(dir, &["config", "commit.gpgsign", "false"]);
+    }
+
+    fn open(vault_path: &Path, password: &str) -> VaultManager {
+        let vault = VaultFile::open(vault_path, password).with_params(8, 1, 1);
+        VaultManager::open_or_create_at(vault, vault_path.to_path_buf())
+            .unwrap()
+            .with_backups(None)
+            .with_git_commits(true)
+    }
+
+    /// A bare remote and two clones of a vault in it, `a` and `b`.
+    fn setup(root: &Path) -> (PathBuf, PathBuf) {
+        let remote = root.join("remote.git");
+        git(
+            root,
+            &[
+                "init",
+                "--quiet",
+                "--bare",
+                "--initial-branch=main",
+                "remote.git",
+            ],
+        );
+
+        let a = root.join("a").join("vault.svlt");
+        std::fs::create_dir(root.join("a")).unwrap();
+        git(
+            &root.join("a"),
+            &["init", "--quiet", "--initial-branch=main"],
+        );
+        configure(&root.join("a"));
+        open(&a, "pw").add(Secret::new("Shared", "s")).unwrap();
+        let repo = Repo::init(&a).unwrap();
+        repo.add_remote("origin", remote.to_str().unwrap()).unwrap();
+        assert_eq!(open(&a, "pw").sync("origin").unwrap(), Pulled::Nothing);
+
+        git(root, &["clone", "--quiet", remote.to_str().unwrap(), "b"]);
+        configure(&root.join("b"));
+        (a, root.join("b").join("vault.svlt"))
+    }
+
+    #[test]
+    fn test_sync_merges_changes_from_both_sides() {
+        let dir = tempdir().unwrap();
+        let (a_path, b_path) = setup(dir.path());
+        let (mut a, mut b) = (open(&a_path, "pw"), open(&b_path, "pw"));
+        assert_eq!(b.list().len(), 1);
+
+        a.add(Secret::new("FromA", "a")).unwrap();
+        b.add(Secret::new("FromB", "b")).unwrap();
+        assert_eq!(a.sync("origin").unwrap(), Pulled::Nothing);
+
+        // Both sides changed the encrypted file: git alone cannot merge it.
+        match b.sync("origin").unwrap() {
+            Pulled::Merged(report) => assert_eq!(report.added, ["FromA"]),
+            other => panic!("expected a merge, got {other:?}"),
+        }
+        assert_eq!(a.sync("origin").unwrap(), Pulled::Changes);
+
+        for vault in [&a, &b, &open(&a_path, "pw")] {
+            let mut names: Vec<_> = vault.list().iter().map(|s| s.name.clone()).collect();
+            names.sort();
+            assert_eq!(names, ["FromA", "FromB", "Shared"]);
+        }
+    }
+
+    #[test]
+    fn test_sync_merges_edits_of_one_secret_against_the_base() {
+        let dir = tempdir().unwrap();
+        let (a_path, b_path) = setup(dir.path());
+        let (mut a, mut b) = (open(&a_path, "pw"), open(&b_path, "pw"));
+        let id = a.list()[0].id;
+
+        let mut edited = a.get(id).unwrap().clone();
+        edited.username = Some("from-a".to_string());
+        a.update(id, edited, a.get(id).unwrap().updated_at).unwrap();
+        let mut edited = b.get(id).unwrap().clone();
+        edited.notes = Some("from b".to_string().into());
+        b.update(id, edited, b.get(id).unwrap().updated_at).unwrap();
+        a.sync("origin").unwrap();
+
+        // Each side changed a different value: both are kept, no conflict.
+        match b.sync("origin").unwrap() {
+            Pulled::Merged(report) => {
+                assert!(report.conflicts.is_empty(), "{report:?}");
+                assert_eq!(report.updated, ["Shared"]);
+            }
+            other => panic!("expected a merge, got {other:?}"),
+        }
+        let shared = b.get(id).unwrap();
+        assert_eq!(shared.username.as_deref(), Some("from-a"));
+        assert_eq!(shared.notes.as_deref().map(String::as_str), Some("from b"));
+    }
+
+    #[test]
+    fn test_sync_aborts_when_remote_does_not_decrypt() {
+        let dir = tempdir().unwrap();
+        let (a_path, b_path) = setup(dir.path());
+        let mut b = open(&b_path, "pw");
+        b.rekey("other").unwrap();
+        b.sync("origin").unwrap();
+
+        let mut a = open(&a_path, "pw");
+        a.add(Secret::new("FromA", "a")).unwrap();
+        assert!(matches!(a.sync("origin"), Err(CoreError::Sync(_))));
+
+        // The merge was abandoned: our vault and history are as they were.
+        assert!(!a_path.with_file_name(".git").join("MERGE_HEAD").exists());
+        assert_eq!(open(&a_path, "pw").list().len(), 2);
+
+        let outside = tempdir().unwrap();
+        let mut lone = open(&outside.path().join("vault.svlt"), "pw");
+        lone.add(Secret::new("Alone", "x")).unwrap();
+        assert!(matches!(lone.sync("origin"), Err(CoreError::Sync(_))));
+    }
+}
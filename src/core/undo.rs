@@ -0,0 +1,82 @@
+use uuid::Uuid;
+
+use super::secret::Secret;
+
+/// How many changes `undo` can go back.
+const DEPTH: usize = 100;
+
+/// One change to a secret, as the secret was before and after it. `None`
+/// means the secret did not exist; a secret with `deleted_at` set was in the
+/// trash.
+#[derive(Debug, Clone)]
+pub struct Change {
+    /// What the change did, e.g. "edit of 'GitHub'".
+    pub label: String,
+    pub id: Uuid,
+    pub before: Option<Secret>,
+    pub after: Option<Secret>,
+}
+
+/// The changes made in this session, for undo and redo. Held in memory only:
+/// undoing a change is itself saved like any other change.
+#[derive(Debug, Default)]
+pub struct History {
+    done: Vec<Change>,
+    undone: Vec<Change>,
+}
+
+impl History {
+    /// Remember a change that was just made. Anything undone before it can
+    /// no longer be redone.
+    pub fn record(&mut self, change: Change) {
+        self.undone.clear();
+        self.done.push(change);
+        if self.done.len() > DEPTH {
+            self.done.remove(0);
+        }
+    }
+
+    /// The change `undo` would revert.
+    pub fn next_undo(&self) -> Option<&Change> {
+        self.done.last()
+    }
+
+    /// The change `redo` would make again.
+    pub fn next_redo(&self) -> Option<&Change> {
+        self.undone.last()
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Change> {
+        self.done.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Change> {
+        self.undone.pop()
+    }
+
+    /// `change` was reverted: it can now be redone.
+    pub fn push_undone(&mut self, change: Change) {
+        self.undone.push(change);
+    }
+
+    /// `change` was made again by a redo, or put back after a failed undo.
+    pub fn push_done(&mut self, change: Change) {
+        self.done.push(change);
+    }
+
+    /// An undo left secret `id` as `state`: the change before it, if it
+    /// touched the same secret, now expects that state on its own undo.
+    pub fn undone_to(&mut self, id: Uuid, state: Option<Secret>) {
+        if let Some(change) = self.done.iter_mut().rev().find(|c| c.id == id) {
+            change.after = state;
+        }
+    }
+
+    /// A redo left secret `id` as `state`: the next change to redo, if it
+    /// touches the same secret, now expects that state.
+    pub fn redone_to(&mut self, id: Uuid, state: Option<Secret>) {
+        if let Some(change) = self.undone.iter_mut().rev().find(|c| c.id == id) {
+            change.before = state;
+        }
+    }
+}
@@ -1,12 +1,28 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::secret::Secret;
 
 /// Root structure serialized inside the encrypted vault file.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VaultData {
     pub version: u8,
     pub secrets: Vec<Secret>,
+    /// Deleted secrets, each with `deleted_at` set, until they are purged.
+    #[serde(default)]
+    pub trash: Vec<Secret>,
+    /// Secrets purged from this vault, so that `merge` removes them from
+    /// other copies instead of bringing them back.
+    #[serde(default)]
+    pub tombstones: Vec<Tombstone>,
+}
+
+/// Records that the secret `id` was purged for good, and when.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Tombstone {
+    pub id: Uuid,
+    pub deleted_at: DateTime<Utc>,
 }
 
 pub const CURRENT_VERSION: u8 = 1;
@@ -16,6 +32,19 @@ impl Default for VaultData {
         Self {
             version: CURRENT_VERSION,
             secrets: Vec::new(),
+            trash: Vec::new(),
+            tombstones: Vec::new(),
         }
     }
 }
+
+impl VaultData {
+    /// The secret with `id`, live or in the trash.
+    pub fn find(&self, id: Uuid) -> Option<&Secret> {
+        self.secrets.iter().chain(&self.trash).find(|s| s.id == id)
+    }
+
+    pub fn tombstone(&self, id: Uuid) -> Option<&Tombstone> {
+        self.tombstones.iter().find(|t| t.id == id)
+    }
+}
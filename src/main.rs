@@ -1,32 +1,72 @@
 mod cli;
+mod config;
 mod core;
 mod tui;
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::Parser;
 
 fn main() {
+    core::memory::harden_process();
     let args = cli::Cli::parse();
-    let vault_path = default_vault_path();
 
-    let result = match args.command {
-        None => tui::run(vault_path),
-        Some(cmd) => cli::run_command(cmd, &vault_path),
-    };
-
-    if let Err(e) = result {
+    if let Err(e) = run(args) {
         eprintln!("Error: {e}");
         std::process::exit(1);
     }
 }
 
-fn default_vault_path() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let dir = PathBuf::from(home)
-        .join(".local")
-        .join("share")
-        .join("valt");
-    let _ = std::fs::create_dir_all(&dir);
-    dir.join("vault.svlt")
+fn run(args: cli::Cli) -> Result<(), Box<dyn std::error::Error>> {
+    // `valt config` must work whatever vault the config points at.
+    if let Some(cli::Command::Config(cmd)) = args.command {
+        return cli::cmd_config(cmd);
+    }
+
+    let config = config::Config::load()?;
+    let (vault_path, vaults) = select_vault(&args, &config)?;
+
+    match args.command {
+        None => {
+            let idle_timeout = (args.lock_after > 0).then(|| Duration::from_secs(args.lock_after));
+            tui::run(vault_path, vaults, idle_timeout, config)
+        }
+        Some(cmd) => cli::run_command(
+            cmd,
+            &cli::Context {
+                vault_path: &vault_path,
+                config: &config,
+            },
+        ),
+    }
+}
+
+/// The vault to open, and every vault the TUI may switch to as
+/// `(label, path)` pairs.
+type VaultChoice = (PathBuf, Vec<(String, PathBuf)>);
+
+fn select_vault(
+    args: &cli::Cli,
+    config: &config::Config,
+) -> Result<VaultChoice, Box<dyn std::error::Error>> {
+    let default = config.default_vault();
+    if let Some(dir) = default.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let vault_path = config::resolve_vault(
+        config,
+        args.vault.clone(),
+        args.profile.as_deref(),
+        std::env::var_os("VALT_VAULT")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from),
+        default.clone(),
+    )?;
+
+    let mut vaults = config.known_vaults(default);
+    if !vaults.iter().any(|(_, p)| *p == vault_path) {
+        vaults.insert(0, ("custom".to_string(), vault_path.clone()));
+    }
+    Ok((vault_path, vaults))
 }
@@ -1,97 +1,356 @@
+use std::collections::BTreeSet;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::core::{generate, GeneratorConfig, Secret, VaultManager};
+use crate::config::Config;
+use crate::core::{
+    audit::{Finding, Issue},
+    generate, group, memory,
+    secret::SecretString,
+    CustomField, EntryKind, GeneratorConfig, GeneratorMode, Otp, PassphraseConfig, Secret,
+    VaultManager,
+};
+
+/// Bytes reserved up front for password inputs, so that typing never
+/// reallocates and leaves a copy of the prefix behind.
+pub const INPUT_CAPACITY: usize = 256;
+
+/// A row of the secret form. Which rows show depends on the kind of the
+/// secret, see `SecretDraft::slots`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormSlot {
+    Name,
+    Kind,
+    Username,
+    Password,
+    Url,
+    /// Field `i` of the kind's schema.
+    Detail(usize),
+    Group,
+    Tags,
+    Notes,
+    Otp,
+    /// Label of custom field row `i`.
+    FieldLabel(usize),
+    /// Value of custom field row `i`.
+    FieldValue(usize),
+}
 
 /// Fields of a secret being added or edited.
 #[derive(Debug, Clone)]
 pub struct SecretDraft {
     pub name: String,
+    pub kind: EntryKind,
     pub username: String,
-    pub password: String,
+    pub password: SecretString,
     pub url: String,
-    pub tags: String, // comma-separated
-    pub notes: String,
+    pub group: String, // levels separated by '/'
+    pub tags: String,  // comma-separated
+    pub notes: SecretString,
+    pub otp: SecretString, // raw base32 or otpauth:// URI
+    /// Values of the kind's schema fields, in schema order.
+    pub details: Vec<SecretString>,
+    pub fields: Vec<CustomField>,
+    /// `updated_at` of the secret being edited, so that a change made by
+    /// another process meanwhile is not overwritten; `None` for a new one.
+    pub edited_from: Option<DateTime<Utc>>,
 }
 
 impl SecretDraft {
     pub fn empty() -> Self {
         Self {
             name: String::new(),
+            kind: EntryKind::Login,
             username: String::new(),
-            password: String::new(),
+            password: SecretString::default(),
             url: String::new(),
+            group: String::new(),
             tags: String::new(),
-            notes: String::new(),
+            notes: SecretString::default(),
+            otp: SecretString::default(),
+            details: Vec::new(),
+            fields: Vec::new(),
+            edited_from: None,
         }
     }
 
     pub fn from_secret(s: &Secret) -> Self {
-        Self {
+        let mut draft = Self {
             name: s.name.clone(),
+            kind: EntryKind::Login,
             username: s.username.clone().unwrap_or_default(),
             password: s.password.clone(),
             url: s.url.clone().unwrap_or_default(),
+            group: s.group.clone().unwrap_or_default(),
             tags: s.tags.join(", "),
             notes: s.notes.clone().unwrap_or_default(),
+            otp: s.otp.as_ref().map(Otp::to_uri).unwrap_or_default(),
+            details: Vec::new(),
+            fields: s.fields.clone(),
+            edited_from: Some(s.updated_at),
+        };
+        draft.set_kind(s.kind);
+        draft
+    }
+
+    /// Switch to `kind`. Schema fields move between `details` and the
+    /// custom fields by label, so that nothing typed is lost on the way.
+    pub fn set_kind(&mut self, kind: EntryKind) {
+        for (spec, value) in self.kind.schema().iter().zip(self.details.drain(..)) {
+            if !value.is_empty() {
+                self.fields
+                    .push(CustomField::new(spec.label, value.as_str(), spec.kind));
+            }
         }
+        self.kind = kind;
+        self.details = kind
+            .schema()
+            .iter()
+            .map(
+                |spec| match self.fields.iter().position(|f| f.label == spec.label) {
+                    Some(i) => self.fields.remove(i).value,
+                    None => SecretString::default(),
+                },
+            )
+            .collect();
+    }
+
+    /// The rows of the form, in display order.
+    pub fn slots(&self) -> Vec<FormSlot> {
+        let kind = self.kind;
+        let mut slots = vec![FormSlot::Name, FormSlot::Kind];
+        if kind.has_login() {
+            slots.push(FormSlot::Username);
+        }
+        if kind.password_label().is_some() {
+            slots.push(FormSlot::Password);
+        }
+        if kind.has_login() {
+            slots.push(FormSlot::Url);
+        }
+        slots.extend((0..self.details.len()).map(FormSlot::Detail));
+        slots.extend([FormSlot::Group, FormSlot::Tags, FormSlot::Notes]);
+        if kind == EntryKind::Login {
+            slots.push(FormSlot::Otp);
+        }
+        for row in 0..self.fields.len() {
+            slots.extend([FormSlot::FieldLabel(row), FormSlot::FieldValue(row)]);
+        }
+        slots
+    }
+
+    /// Position of `slot` among `slots`, or 0 (the name) if it is not shown.
+    pub fn position(&self, slot: FormSlot) -> usize {
+        self.slots().iter().position(|s| *s == slot).unwrap_or(0)
     }
 
     /// Returns an error message if the draft is not valid, or `None` if OK.
-    pub fn validate(&self) -> Option<&'static str> {
+    pub fn validate(&self) -> Option<String> {
         if self.name.trim().is_empty() {
-            return Some("Name is required");
+            return Some("Name is required".to_string());
         }
-        if self.password.is_empty() {
-            return Some("Password is required");
+        match self.kind {
+            EntryKind::Login if self.password.is_empty() => {
+                return Some("Password is required".to_string());
+            }
+            EntryKind::Note if self.notes.trim().is_empty() => {
+                return Some("Notes is required".to_string());
+            }
+            _ => {}
+        }
+        let mut details = self.kind.schema().iter().zip(&self.details);
+        if let Some(msg) = details.find_map(|(spec, value)| spec.check(value)) {
+            return Some(msg);
+        }
+        if self.kind == EntryKind::Login
+            && !self.otp.trim().is_empty()
+            && Otp::parse(&self.otp).is_err()
+        {
+            return Some("OTP must be a base32 seed or an otpauth:// URI".to_string());
+        }
+        for field in self.filled_fields() {
+            if field.label.trim().is_empty() {
+                return Some("Custom field label is required".to_string());
+            }
+            if let Some(msg) = field.kind.check(&field.value) {
+                return Some(msg.to_string());
+            }
         }
         None
     }
+
+    /// Custom field rows, skipping rows left entirely blank.
+    pub fn filled_fields(&self) -> impl Iterator<Item = &CustomField> {
+        self.fields
+            .iter()
+            .filter(|f| !f.label.trim().is_empty() || !f.value.is_empty())
+    }
 }
 
 /// State for the generator popup overlay.
 pub struct GeneratorDraft {
+    pub mode: GeneratorMode,
     pub length_str: String,
     pub uppercase: bool,
     pub lowercase: bool,
     pub digits: bool,
     pub symbols: bool,
+    /// Minimum counts of uppercase letters, lowercase letters, digits and
+    /// symbols, in that order.
+    pub mins: [String; 4],
+    pub avoid_ambiguous: bool,
+    pub no_repeat: bool,
+    pub include: String,
+    pub exclude: String,
+    pub words_str: String,
+    pub separator: String,
+    pub capitalize: bool,
+    pub add_digit: bool,
+    /// 0 is the mode toggle; the rows after it depend on the mode.
     pub focused: usize,
-    pub preview: String,
+    /// The generated password, or why the settings cannot produce one.
+    pub preview: Result<SecretString, String>,
 }
 
 impl GeneratorDraft {
-    pub fn new() -> Self {
+    /// A draft starting from `defaults` (the `[generator]` config section).
+    pub fn new(defaults: &GeneratorConfig) -> Self {
         let mut draft = Self {
-            length_str: "20".to_string(),
-            uppercase: true,
-            lowercase: true,
-            digits: true,
-            symbols: true,
+            mode: defaults.mode,
+            length_str: defaults.length.to_string(),
+            uppercase: defaults.uppercase,
+            lowercase: defaults.lowercase,
+            digits: defaults.digits,
+            symbols: defaults.symbols,
+            mins: [
+                defaults.min_uppercase,
+                defaults.min_lowercase,
+                defaults.min_digits,
+                defaults.min_symbols,
+            ]
+            .map(|min| min.to_string()),
+            avoid_ambiguous: defaults.exclude_ambiguous,
+            no_repeat: defaults.no_repeat,
+            include: defaults.extra_chars.clone(),
+            exclude: defaults.excluded_chars.clone(),
+            words_str: defaults.passphrase.words.to_string(),
+            separator: defaults.passphrase.separator.clone(),
+            capitalize: defaults.passphrase.capitalize,
+            add_digit: defaults.passphrase.digit,
             focused: 0,
-            preview: String::new(),
+            preview: Ok(SecretString::default()),
         };
         draft.regenerate();
         draft
     }
 
+    /// Number of focusable rows, mode toggle included.
+    pub fn field_count(&self) -> usize {
+        match self.mode {
+            GeneratorMode::Characters => 14,
+            GeneratorMode::Passphrase => 5,
+        }
+    }
+
+    /// The minimum being edited, when the focus is on one. In characters
+    /// mode, rows 2 to 9 alternate between a class toggle and its minimum.
+    pub fn focused_min(&mut self) -> Option<&mut String> {
+        match (self.mode, self.focused) {
+            (GeneratorMode::Characters, f @ (3 | 5 | 7 | 9)) => Some(&mut self.mins[(f - 3) / 2]),
+            _ => None,
+        }
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            GeneratorMode::Characters => GeneratorMode::Passphrase,
+            GeneratorMode::Passphrase => GeneratorMode::Characters,
+        };
+    }
+
     pub fn to_config(&self) -> GeneratorConfig {
+        let [min_uppercase, min_lowercase, min_digits, min_symbols] = self
+            .mins
+            .each_ref()
+            .map(|min| min.parse::<usize>().unwrap_or(0));
         GeneratorConfig {
+            mode: self.mode,
             length: self.length_str.parse::<usize>().unwrap_or(20).max(1),
             uppercase: self.uppercase,
             lowercase: self.lowercase,
             digits: self.digits,
             symbols: self.symbols,
+            min_uppercase,
+            min_lowercase,
+            min_digits,
+            min_symbols,
+            exclude_ambiguous: self.avoid_ambiguous,
+            extra_chars: self.include.clone(),
+            excluded_chars: self.exclude.clone(),
+            no_repeat: self.no_repeat,
+            passphrase: PassphraseConfig {
+                words: self.words_str.parse::<usize>().unwrap_or(6).max(1),
+                separator: self.separator.clone(),
+                capitalize: self.capitalize,
+                digit: self.add_digit,
+            },
         }
     }
 
     pub fn regenerate(&mut self) {
-        self.preview = generate(&self.to_config()).unwrap_or_else(|_| "(invalid)".to_string());
+        self.preview = generate(&self.to_config())
+            .map(SecretString::new)
+            .map_err(|e| e.to_string());
     }
 }
 
+/// One line of the audit dashboard: an issue and one entry it concerns.
+/// Grouped findings (reused passwords, duplicate names) give one row per
+/// entry so that each can be opened.
+#[derive(Debug, Clone)]
+pub struct AuditRow {
+    pub issue: Issue,
+    pub secret_id: Uuid,
+    pub detail: String,
+}
+
+impl AuditRow {
+    pub fn from_findings(findings: Vec<Finding>) -> Vec<Self> {
+        findings
+            .into_iter()
+            .flat_map(|f| {
+                f.ids.into_iter().map(move |secret_id| AuditRow {
+                    issue: f.issue,
+                    secret_id,
+                    detail: f.detail.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The group tree beside the list. Its cursor is the group the list is
+/// narrowed to.
+#[derive(Debug, Default)]
+pub struct Sidebar {
+    /// Keys move through the tree instead of the list.
+    pub focused: bool,
+    /// The list shows this group and its subgroups; `None` shows everything.
+    pub group: Option<String>,
+    /// Groups whose subgroups are hidden.
+    pub collapsed: BTreeSet<String>,
+}
+
+/// A permanent deletion from the trash view, waiting for `y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purge {
+    One(Uuid),
+    All,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FormMode {
     Add,
@@ -101,7 +360,7 @@ pub enum FormMode {
 #[derive(Debug)]
 pub enum AppView {
     Locked {
-        input: String,
+        input: SecretString,
         error: Option<String>,
     },
     List {
@@ -112,13 +371,36 @@ pub enum AppView {
         secret_id: Uuid,
         show_password: bool,
     },
+    History {
+        secret_id: Uuid,
+        selected_idx: usize,
+        show_password: bool,
+    },
     Form {
         mode: FormMode,
-        draft: SecretDraft,
+        draft: Box<SecretDraft>,
         focused_field: usize,
         show_password: bool,
         error: Option<String>,
     },
+    /// Change-master-password dialog: current, new, confirm.
+    ChangePassword {
+        inputs: [SecretString; 3],
+        focused: usize,
+        error: Option<String>,
+    },
+    /// Vault health dashboard, computed when opened.
+    Audit {
+        rows: Vec<AuditRow>,
+        selected_idx: usize,
+    },
+    /// Deleted secrets, most recently deleted first.
+    Trash {
+        selected_idx: usize,
+        confirm: Option<Purge>,
+    },
+    /// Vault information: location, size, entry count, KDF parameters.
+    Info,
     Help,
 }
 
@@ -126,28 +408,43 @@ pub struct AppState {
     pub view: AppView,
     pub vault: Option<VaultManager>,
     pub vault_path: PathBuf,
+    /// Known vaults as `(label, path)`, cycled through on the unlock screen.
+    pub vaults: Vec<(String, PathBuf)>,
+    pub config: Config,
     /// When set, the clipboard will be cleared at this instant.
     pub clipboard_clear_at: Option<Instant>,
     pub should_quit: bool,
     /// Transient status message shown in the list status bar.
     pub status: Option<String>,
+    pub sidebar: Sidebar,
     /// When Some, the generator popup is active over the form.
     pub generator_popup: Option<GeneratorDraft>,
+    /// Lock the vault after this long without a key press.
+    pub idle_timeout: Option<Duration>,
+    pub last_activity: Instant,
+    /// The view that was open when the vault locked, restored on unlock.
+    pub resume_view: Option<AppView>,
 }
 
 impl AppState {
-    pub fn new(vault_path: PathBuf) -> Self {
+    pub fn new(vault_path: PathBuf, config: Config) -> Self {
         Self {
             view: AppView::Locked {
-                input: String::new(),
+                input: memory::locked_string(INPUT_CAPACITY),
                 error: None,
             },
             vault: None,
             vault_path,
+            vaults: Vec::new(),
+            config,
             clipboard_clear_at: None,
             should_quit: false,
             status: None,
+            sidebar: Sidebar::default(),
             generator_popup: None,
+            idle_timeout: None,
+            last_activity: Instant::now(),
+            resume_view: None,
         }
     }
 
@@ -155,6 +452,90 @@ impl AppState {
         self.vault_path.exists()
     }
 
+    /// Label of the current vault among `vaults`, if it is one of them.
+    pub fn vault_label(&self) -> Option<&str> {
+        self.vaults
+            .iter()
+            .find(|(_, p)| *p == self.vault_path)
+            .map(|(label, _)| label.as_str())
+    }
+
+    /// Switch the unlock screen to the next (`forward`) or previous known
+    /// vault. The view saved by `lock` belongs to the old vault and is dropped.
+    pub fn cycle_vault(&mut self, forward: bool) {
+        let n = self.vaults.len();
+        if n < 2 {
+            return;
+        }
+        let idx = self
+            .vaults
+            .iter()
+            .position(|(_, p)| *p == self.vault_path)
+            .unwrap_or(0);
+        let next = if forward {
+            (idx + 1) % n
+        } else {
+            (idx + n - 1) % n
+        };
+        self.vault_path = self.vaults[next].1.clone();
+        self.resume_view = None;
+        self.sidebar = Sidebar::default();
+        self.status = None;
+    }
+
+    /// Rows of the group tree, minus the subgroups of collapsed groups.
+    pub fn group_rows(&self) -> Vec<group::Node> {
+        let Some(vault) = &self.vault else {
+            return Vec::new();
+        };
+        let collapsed = &self.sidebar.collapsed;
+        group::tree(vault.list())
+            .into_iter()
+            .filter(|n| {
+                !collapsed
+                    .iter()
+                    .any(|c| n.path.starts_with(&format!("{c}{}", group::SEPARATOR)))
+            })
+            .collect()
+    }
+
+    /// The group the list is narrowed to, unless it no longer holds any
+    /// secret (all of them moved or deleted).
+    pub fn group_filter(&self) -> Option<&str> {
+        let filter = self.sidebar.group.as_deref()?;
+        self.vault
+            .as_ref()?
+            .list()
+            .iter()
+            .any(|s| group::contains(filter, s.group.as_deref()))
+            .then_some(filter)
+    }
+
+    /// Secrets shown by the list: matching `query`, in the current group.
+    pub fn list_results(&self, query: &str) -> Vec<&Secret> {
+        let Some(vault) = &self.vault else {
+            return Vec::new();
+        };
+        let mut results = vault.search(query);
+        if let Some(filter) = self.group_filter() {
+            results.retain(|s| group::contains(filter, s.group.as_deref()));
+        }
+        results
+    }
+
+    /// Status-bar hint naming the change `u` would undo, or else the one
+    /// `Ctrl+R` would redo.
+    pub fn undo_hint(&self) -> String {
+        let Some(vault) = &self.vault else {
+            return String::new();
+        };
+        match (vault.undo_label(), vault.redo_label()) {
+            (Some(label), _) => format!("[u] Undo {label}  "),
+            (None, Some(label)) => format!("[Ctrl+R] Redo {label}  "),
+            (None, None) => String::new(),
+        }
+    }
+
     pub fn clipboard_secs_remaining(&self) -> Option<u32> {
         self.clipboard_clear_at.map(|deadline| {
             let now = Instant::now();
@@ -166,6 +547,42 @@ impl AppState {
         })
     }
 
+    /// Drop the decrypted vault (which wipes its secrets) and show the
+    /// unlock screen. The current view is kept so that unlocking returns to
+    /// it, with passwords hidden again; the clipboard is cleared on the next
+    /// tick.
+    ///
+    /// A form keeps its unsaved draft, whose secret values are zeroizing
+    /// strings. The change-password dialog, which holds master passwords, is
+    /// dropped.
+    pub fn lock(&mut self) {
+        if self.vault.take().is_none() {
+            return;
+        }
+        let mut previous = std::mem::replace(
+            &mut self.view,
+            AppView::Locked {
+                input: memory::locked_string(INPUT_CAPACITY),
+                error: None,
+            },
+        );
+        match &mut previous {
+            AppView::Detail { show_password, .. }
+            | AppView::History { show_password, .. }
+            | AppView::Form { show_password, .. } => *show_password = false,
+            AppView::Trash { confirm, .. } => *confirm = None,
+            _ => {}
+        }
+        self.resume_view = match previous {
+            AppView::ChangePassword { .. } => None,
+            view => Some(view),
+        };
+        self.generator_popup = None;
+        if self.clipboard_clear_at.is_some() {
+            self.clipboard_clear_at = Some(Instant::now());
+        }
+    }
+
     pub fn go_to_list(&mut self) {
         self.view = AppView::List {
             search_query: String::new(),
@@ -173,3 +590,129 @@ impl AppState {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdevault::VaultFile;
+    use tempfile::tempdir;
+
+    fn unlocked(dir: &tempfile::TempDir) -> AppState {
+        let path = dir.path().join("vault.svlt");
+        let vault = VaultFile::open(&path, "test_password").with_params(8, 1, 1);
+        let mut app = AppState::new(path, Config::default());
+        app.vault = Some(VaultManager::open_or_create(vault).unwrap());
+        app
+    }
+
+    #[test]
+    fn test_generator_draft_keeps_each_class_minimum() {
+        let defaults = GeneratorConfig {
+            length: 8,
+            min_uppercase: 0,
+            min_lowercase: 1,
+            min_digits: 4,
+            min_symbols: 2,
+            ..Default::default()
+        };
+        let mut draft = GeneratorDraft::new(&defaults);
+        assert_eq!(draft.mins, ["0", "1", "4", "2"]);
+
+        // Rows 3, 5, 7 and 9 edit the minimums, class by class.
+        draft.focused = 9;
+        *draft.focused_min().unwrap() = "3".to_string();
+        draft.focused = 8;
+        assert!(draft.focused_min().is_none());
+
+        let config = draft.to_config();
+        assert_eq!(
+            (
+                config.min_uppercase,
+                config.min_lowercase,
+                config.min_digits,
+                config.min_symbols
+            ),
+            (0, 1, 4, 3)
+        );
+        draft.regenerate();
+        let pwd = draft.preview.unwrap();
+        assert!(pwd.chars().filter(char::is_ascii_digit).count() >= 4);
+        assert!(pwd.chars().filter(char::is_ascii_punctuation).count() >= 3);
+    }
+
+    #[test]
+    fn test_lock_keeps_view_with_passwords_hidden() {
+        let dir = tempdir().unwrap();
+        let mut app = unlocked(&dir);
+        let id = Uuid::new_v4();
+        app.view = AppView::Detail {
+            secret_id: id,
+            show_password: true,
+        };
+
+        app.lock();
+        assert!(app.vault.is_none());
+        assert!(matches!(app.view, AppView::Locked { .. }));
+        assert!(matches!(
+            app.resume_view,
+            Some(AppView::Detail { secret_id, show_password: false }) if secret_id == id
+        ));
+
+        // Locking again while locked keeps what there is to resume.
+        app.lock();
+        assert!(app.resume_view.is_some());
+    }
+
+    #[test]
+    fn test_lock_keeps_forms_and_drops_master_passwords() {
+        let dir = tempdir().unwrap();
+        let mut app = unlocked(&dir);
+        let id = Uuid::new_v4();
+        let mut draft = SecretDraft::empty();
+        draft.password = SecretString::new("half typed".to_string());
+        app.view = AppView::Form {
+            mode: FormMode::Edit(id),
+            draft: Box::new(draft),
+            focused_field: 3,
+            show_password: true,
+            error: None,
+        };
+        app.lock();
+        assert!(matches!(
+            &app.resume_view,
+            Some(AppView::Form {
+                mode: FormMode::Edit(secret_id),
+                draft,
+                focused_field: 3,
+                show_password: false,
+                ..
+            }) if *secret_id == id && draft.password.as_str() == "half typed"
+        ));
+
+        let mut app = unlocked(&dir);
+        app.view = AppView::Form {
+            mode: FormMode::Add,
+            draft: Box::new(SecretDraft::empty()),
+            focused_field: 0,
+            show_password: false,
+            error: None,
+        };
+        app.lock();
+        assert!(matches!(
+            app.resume_view,
+            Some(AppView::Form {
+                mode: FormMode::Add,
+                ..
+            })
+        ));
+
+        let mut app = unlocked(&dir);
+        app.view = AppView::ChangePassword {
+            inputs: Default::default(),
+            focused: 0,
+            error: None,
+        };
+        app.lock();
+        assert!(app.resume_view.is_none());
+    }
+}
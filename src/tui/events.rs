@@ -1,12 +1,23 @@
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
-use crate::core::{Secret, VaultManager};
+use crate::core::{
+    audit::{Issue, DEFAULT_MAX_AGE_DAYS},
+    error::CoreError,
+    group,
+    kdf::open_vault_file,
+    memory,
+    otp::{unix_now, OtpKind},
+    secret::SecretString,
+    CustomField, EntryKind, FieldKind, GeneratorMode, Otp, Secret, VaultManager,
+};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use serdevault::VaultFile;
+use uuid::Uuid;
+use zeroize::Zeroize;
 
-use super::app::{AppState, AppView, FormMode, GeneratorDraft, SecretDraft};
-
-const CLIPBOARD_TIMEOUT: Duration = Duration::from_secs(30);
+use super::app::{
+    AppState, AppView, AuditRow, FormMode, FormSlot, GeneratorDraft, Purge, SecretDraft,
+    INPUT_CAPACITY,
+};
 
 pub fn handle_key(app: &mut AppState, key: KeyEvent) {
     if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
@@ -18,7 +29,12 @@ pub fn handle_key(app: &mut AppState, key: KeyEvent) {
         AppView::Locked { .. } => handle_locked(app, key),
         AppView::List { .. } => handle_list(app, key),
         AppView::Detail { .. } => handle_detail(app, key),
+        AppView::History { .. } => handle_history(app, key),
         AppView::Form { .. } => handle_form(app, key),
+        AppView::ChangePassword { .. } => handle_change_password(app, key),
+        AppView::Audit { .. } => handle_audit(app, key),
+        AppView::Trash { .. } => handle_trash(app, key),
+        AppView::Info => handle_help(app, key),
         AppView::Help => handle_help(app, key),
     }
 }
@@ -33,7 +49,7 @@ fn handle_locked(app: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Backspace => {
             if let AppView::Locked { input, .. } = &mut app.view {
-                input.pop();
+                memory::backspace(input);
             }
         }
         KeyCode::Enter => {
@@ -42,20 +58,34 @@ fn handle_locked(app: &mut AppState, key: KeyEvent) {
                 AppView::Locked { input, .. } => input.clone(),
                 _ => return,
             };
-            let vault_file = VaultFile::open(&app.vault_path, &password);
-            match VaultManager::open_or_create(vault_file) {
+            let vault_file = open_vault_file(&app.vault_path, &password);
+            match VaultManager::open_or_create_at(vault_file, app.vault_path.clone()) {
                 Ok(manager) => {
-                    app.vault = Some(manager.with_backup_path(app.vault_path.clone()));
-                    app.go_to_list();
+                    app.vault = Some(
+                        manager
+                            .with_backups(app.config.backup.on_save())
+                            .with_git_commits(app.config.sync.auto_commit)
+                            .with_trash_retention(app.config.trash.retention()),
+                    );
+                    app.status = None;
+                    match app.resume_view.take() {
+                        Some(view) => app.view = view,
+                        None => app.go_to_list(),
+                    }
                 }
-                Err(_) => {
+                Err(e) => {
                     if let AppView::Locked { error, input } = &mut app.view {
-                        *error = Some("Wrong password or corrupted vault".to_string());
-                        input.clear();
+                        *error = Some(match e {
+                            CoreError::Vault(_) => "Wrong password or corrupted vault".to_string(),
+                            e => e.to_string(),
+                        });
+                        input.zeroize();
                     }
                 }
             }
         }
+        KeyCode::Tab | KeyCode::Down => app.cycle_vault(true),
+        KeyCode::BackTab | KeyCode::Up => app.cycle_vault(false),
         KeyCode::Esc => {
             app.should_quit = true;
         }
@@ -66,6 +96,11 @@ fn handle_locked(app: &mut AppState, key: KeyEvent) {
 fn handle_list(app: &mut AppState, key: KeyEvent) {
     app.status = None;
 
+    if app.sidebar.focused {
+        handle_sidebar(app, key);
+        return;
+    }
+
     let (search_query, selected_idx) = match &app.view {
         AppView::List {
             search_query,
@@ -74,76 +109,95 @@ fn handle_list(app: &mut AppState, key: KeyEvent) {
         _ => return,
     };
 
-    let count = app
-        .vault
-        .as_ref()
-        .map(|v| v.search(&search_query).len())
-        .unwrap_or(0);
+    let count = app.list_results(&search_query).len();
 
     match key.code {
         KeyCode::Char('q') => {
             app.should_quit = true;
         }
+        KeyCode::Tab => {
+            app.sidebar.focused = !app.group_rows().is_empty();
+        }
         KeyCode::Char('?') => {
             app.view = AppView::Help;
         }
         KeyCode::Char('j') | KeyCode::Down => {
-            if count > 0 {
-                let new_idx = (selected_idx + 1).min(count - 1);
-                if let AppView::List { selected_idx, .. } = &mut app.view {
-                    *selected_idx = new_idx;
-                }
+            if let AppView::List { selected_idx, .. } = &mut app.view {
+                *selected_idx = (*selected_idx + 1).min(count.saturating_sub(1));
             }
         }
         KeyCode::Char('k') | KeyCode::Up => {
-            if selected_idx > 0 {
-                let new_idx = selected_idx - 1;
-                if let AppView::List { selected_idx, .. } = &mut app.view {
-                    *selected_idx = new_idx;
-                }
+            if let AppView::List { selected_idx, .. } = &mut app.view {
+                *selected_idx = selected_idx.saturating_sub(1);
             }
         }
         KeyCode::Enter | KeyCode::Right => {
-            if count > 0 {
-                let idx = selected_idx.min(count - 1);
-                let secret_id = app
-                    .vault
-                    .as_ref()
-                    .and_then(|v| v.search(&search_query).get(idx).map(|s| s.id));
-                if let Some(id) = secret_id {
-                    app.view = AppView::Detail {
-                        secret_id: id,
-                        show_password: false,
-                    };
-                }
+            if let Some(id) = selected_secret_id(app, &search_query, selected_idx) {
+                app.view = AppView::Detail {
+                    secret_id: id,
+                    show_password: false,
+                };
             }
         }
+        KeyCode::Char('I') => {
+            app.view = AppView::Info;
+        }
+        KeyCode::Char('L') => {
+            app.lock();
+            app.status = Some("Vault locked.".to_string());
+        }
+        KeyCode::Char('A') => {
+            let findings = app
+                .vault
+                .as_ref()
+                .map(|v| v.audit(DEFAULT_MAX_AGE_DAYS))
+                .unwrap_or_default();
+            app.view = AppView::Audit {
+                rows: AuditRow::from_findings(findings),
+                selected_idx: 0,
+            };
+        }
+        KeyCode::Char('u') => {
+            undo_redo(app, false);
+            clamp_list_selection(app);
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            undo_redo(app, true);
+            clamp_list_selection(app);
+        }
+        KeyCode::Char('T') => {
+            app.view = AppView::Trash {
+                selected_idx: 0,
+                confirm: None,
+            };
+        }
+        KeyCode::Char('P') => {
+            app.view = AppView::ChangePassword {
+                inputs: std::array::from_fn(|_| memory::locked_string(INPUT_CAPACITY)),
+                focused: 0,
+                error: None,
+            };
+        }
         KeyCode::Char('n') => {
+            // A new secret goes into the group being browsed.
+            let mut draft = SecretDraft::empty();
+            draft.group = app.group_filter().unwrap_or_default().to_string();
             app.view = AppView::Form {
                 mode: FormMode::Add,
-                draft: SecretDraft::empty(),
+                draft: Box::new(draft),
                 focused_field: 0,
                 show_password: false,
                 error: None,
             };
         }
         KeyCode::Char('d') => {
-            if count > 0 {
-                let idx = selected_idx.min(count - 1);
-                let secret_id = app
-                    .vault
-                    .as_ref()
-                    .and_then(|v| v.search(&search_query).get(idx).map(|s| s.id));
-                if let Some(id) = secret_id {
-                    if let Some(vault) = &mut app.vault {
-                        let _ = vault.delete(id);
-                    }
-                    let new_count = app.vault.as_ref().map(|v| v.list().len()).unwrap_or(0);
-                    if let AppView::List { selected_idx, .. } = &mut app.view {
-                        *selected_idx = (*selected_idx).min(new_count.saturating_sub(1));
-                    }
-                    app.status = Some("Secret deleted.".to_string());
-                }
+            if let Some(id) = selected_secret_id(app, &search_query, selected_idx) {
+                let result = app.vault.as_mut().map(|vault| vault.delete(id));
+                clamp_list_selection(app);
+                app.status = Some(match result {
+                    Some(Err(e)) => format!("Delete failed: {e}"),
+                    _ => "Moved to the trash. T opens the trash.".to_string(),
+                });
             }
         }
         KeyCode::Backspace => {
@@ -180,6 +234,106 @@ fn handle_list(app: &mut AppState, key: KeyEvent) {
     }
 }
 
+/// Keep the list cursor on an existing row after secrets came or went.
+fn clamp_list_selection(app: &mut AppState) {
+    let count = match &app.view {
+        AppView::List { search_query, .. } => app.list_results(search_query).len(),
+        _ => return,
+    };
+    if let AppView::List { selected_idx, .. } = &mut app.view {
+        *selected_idx = (*selected_idx).min(count.saturating_sub(1));
+    }
+}
+
+/// Keys of the group tree. Row 0 stands for every secret; the list shows
+/// the group under the cursor as it moves.
+fn handle_sidebar(app: &mut AppState, key: KeyEvent) {
+    let rows = app.group_rows();
+    if rows.is_empty() {
+        app.sidebar.focused = false;
+        return;
+    }
+    let current = app.group_filter().map(str::to_string);
+    let cursor = current
+        .as_deref()
+        .and_then(|g| rows.iter().position(|n| n.path == g))
+        .map_or(0, |i| i + 1);
+    let node = cursor.checked_sub(1).map(|i| &rows[i]);
+
+    match key.code {
+        KeyCode::Char('q') => app.should_quit = true,
+        KeyCode::Char('?') => app.view = AppView::Help,
+        KeyCode::Tab | KeyCode::Enter | KeyCode::Esc => app.sidebar.focused = false,
+        KeyCode::Char('j') | KeyCode::Down => {
+            let next = rows.get(cursor).map(|n| n.path.clone());
+            if next.is_some() {
+                select_group(app, next);
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let Some(i) = cursor.checked_sub(1) {
+                select_group(app, i.checked_sub(1).map(|i| rows[i].path.clone()));
+            }
+        }
+        KeyCode::Char('l') | KeyCode::Right => {
+            if let Some(node) = node {
+                app.sidebar.collapsed.remove(&node.path);
+            }
+        }
+        KeyCode::Char('h') | KeyCode::Left => {
+            // Fold the group, or else move up to its parent.
+            let Some(node) = node else { return };
+            if node.has_children && app.sidebar.collapsed.insert(node.path.clone()) {
+                return;
+            }
+            let parent = node
+                .path
+                .rsplit_once(group::SEPARATOR)
+                .map(|(parent, _)| parent.to_string());
+            select_group(app, parent);
+        }
+        KeyCode::Char(' ') => {
+            if let Some(node) = node.filter(|n| n.has_children) {
+                if !app.sidebar.collapsed.remove(&node.path) {
+                    app.sidebar.collapsed.insert(node.path.clone());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Narrow the list to `group` (every secret for `None`), from its top.
+fn select_group(app: &mut AppState, group: Option<String>) {
+    app.sidebar.group = group;
+    if let AppView::List { selected_idx, .. } = &mut app.view {
+        *selected_idx = 0;
+    }
+}
+
+/// `u` / `Ctrl+R` in the list and detail views: undo or redo the last
+/// change and say which.
+fn undo_redo(app: &mut AppState, redo: bool) {
+    let Some(vault) = &mut app.vault else {
+        return;
+    };
+    let result = if redo { vault.redo() } else { vault.undo() };
+    app.status = Some(match result {
+        Ok(Some(label)) if redo => format!("Redid {label}."),
+        Ok(Some(label)) => format!("Undid {label}. Ctrl+R redoes it."),
+        Ok(None) if redo => "Nothing to redo.".to_string(),
+        Ok(None) => "Nothing to undo.".to_string(),
+        Err(e) => format!("{} failed: {e}", if redo { "Redo" } else { "Undo" }),
+    });
+}
+
+/// UUID of the secret under the cursor in the (filtered) list, if any.
+fn selected_secret_id(app: &AppState, search_query: &str, selected_idx: usize) -> Option<Uuid> {
+    let results = app.list_results(search_query);
+    let idx = selected_idx.min(results.len().checked_sub(1)?);
+    results.get(idx).map(|s| s.id)
+}
+
 fn handle_detail(app: &mut AppState, key: KeyEvent) {
     app.status = None;
 
@@ -201,26 +355,59 @@ fn handle_detail(app: &mut AppState, key: KeyEvent) {
             }
         }
         KeyCode::Char('c') => {
-            let password = app
+            let main = app.vault.as_ref().and_then(|v| v.get(secret_id)).map(|s| {
+                (
+                    s.main_value().map(|v| SecretString::new(v.to_string())),
+                    s.kind.main_field(),
+                )
+            });
+
+            match main {
+                Some((Some(value), what)) => copy_to_clipboard(app, &value, what),
+                Some((None, what)) => {
+                    app.status = Some(format!("No {} to copy", what.to_lowercase()))
+                }
+                None => {}
+            }
+        }
+        KeyCode::Char('o') => {
+            let otp = app
                 .vault
                 .as_ref()
                 .and_then(|v| v.get(secret_id))
-                .map(|s| s.password.clone());
+                .and_then(|s| s.otp.clone());
 
-            if let Some(pwd) = password {
-                match arboard::Clipboard::new() {
-                    Ok(mut cb) => {
-                        if cb.set_text(&pwd).is_ok() {
-                            app.clipboard_clear_at = Some(Instant::now() + CLIPBOARD_TIMEOUT);
-                            app.status = Some("Password copied — clears in 30s".to_string());
-                        } else {
-                            app.status = Some("Failed to copy to clipboard".to_string());
+            match otp.map(|o| (o.generate(unix_now()), o)) {
+                Some((Ok(code), otp)) => {
+                    copy_to_clipboard(app, &code, "Code");
+                    // HOTP codes are single-use: move on to the next counter.
+                    if matches!(otp.kind, OtpKind::Hotp { .. }) {
+                        let advanced = app.vault.as_mut().map(|v| v.advance_otp(secret_id));
+                        if let Some(Err(e)) = advanced {
+                            // The same code would come up again next time.
+                            app.status =
+                                Some(format!("Code copied, but the counter was not saved: {e}"));
                         }
                     }
-                    Err(_) => {
-                        app.status = Some("Clipboard not available".to_string());
-                    }
                 }
+                Some((Err(e), _)) => app.status = Some(format!("OTP error: {e}")),
+                None => app.status = Some("No OTP seed on this secret".to_string()),
+            }
+        }
+        KeyCode::Char('h') => {
+            let has_history = app
+                .vault
+                .as_ref()
+                .and_then(|v| v.get(secret_id))
+                .is_some_and(|s| !s.history.is_empty());
+            if has_history {
+                app.view = AppView::History {
+                    secret_id,
+                    selected_idx: 0,
+                    show_password: false,
+                };
+            } else {
+                app.status = Some("No password history".to_string());
             }
         }
         KeyCode::Char('e') => {
@@ -232,18 +419,105 @@ fn handle_detail(app: &mut AppState, key: KeyEvent) {
             if let Some(draft) = draft {
                 app.view = AppView::Form {
                     mode: FormMode::Edit(secret_id),
-                    draft,
+                    draft: Box::new(draft),
                     focused_field: 0,
                     show_password: false,
                     error: None,
                 };
             }
         }
+        KeyCode::Char('u') => undo_redo_in_detail(app, secret_id, false),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            undo_redo_in_detail(app, secret_id, true)
+        }
         KeyCode::Char('d') => {
             if let Some(vault) = &mut app.vault {
-                if vault.delete(secret_id).is_ok() {
-                    app.go_to_list();
-                    app.status = Some("Secret deleted.".to_string());
+                match vault.delete(secret_id) {
+                    Ok(()) => {
+                        app.go_to_list();
+                        app.status = Some("Moved to the trash. T opens the trash.".to_string());
+                    }
+                    Err(e) => app.status = Some(format!("Delete failed: {e}")),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// As `undo_redo`, going back to the list if the secret is no longer live.
+fn undo_redo_in_detail(app: &mut AppState, secret_id: Uuid, redo: bool) {
+    undo_redo(app, redo);
+    if app.vault.as_ref().and_then(|v| v.get(secret_id)).is_none() {
+        let status = app.status.take();
+        app.go_to_list();
+        app.status = status;
+    }
+}
+
+fn handle_history(app: &mut AppState, key: KeyEvent) {
+    app.status = None;
+
+    let (secret_id, selected_idx) = match &app.view {
+        AppView::History {
+            secret_id,
+            selected_idx,
+            ..
+        } => (*secret_id, *selected_idx),
+        _ => return,
+    };
+
+    let count = app
+        .vault
+        .as_ref()
+        .and_then(|v| v.get(secret_id))
+        .map(|s| s.history.len())
+        .unwrap_or(0);
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Left => {
+            app.view = AppView::Detail {
+                secret_id,
+                show_password: false,
+            };
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            if let AppView::History { selected_idx, .. } = &mut app.view {
+                *selected_idx = (*selected_idx + 1).min(count.saturating_sub(1));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let AppView::History { selected_idx, .. } = &mut app.view {
+                *selected_idx = selected_idx.saturating_sub(1);
+            }
+        }
+        KeyCode::Char(' ') => {
+            if let AppView::History { show_password, .. } = &mut app.view {
+                *show_password = !*show_password;
+            }
+        }
+        KeyCode::Char('c') => {
+            let password = app
+                .vault
+                .as_ref()
+                .and_then(|v| v.get(secret_id))
+                .and_then(|s| s.history.get(selected_idx))
+                .map(|e| e.password.clone());
+            if let Some(pwd) = password {
+                copy_to_clipboard(app, &pwd, "Old password");
+            }
+        }
+        KeyCode::Char('r') => {
+            if let Some(vault) = &mut app.vault {
+                match vault.restore_password(secret_id, selected_idx) {
+                    Ok(()) => {
+                        app.view = AppView::Detail {
+                            secret_id,
+                            show_password: false,
+                        };
+                        app.status = Some("Password restored.".to_string());
+                    }
+                    Err(e) => app.status = Some(format!("Restore failed: {e}")),
                 }
             }
         }
@@ -251,7 +525,137 @@ fn handle_detail(app: &mut AppState, key: KeyEvent) {
     }
 }
 
-const FORM_FIELD_COUNT: usize = 6;
+fn handle_trash(app: &mut AppState, key: KeyEvent) {
+    app.status = None;
+    let AppView::Trash {
+        selected_idx,
+        confirm,
+    } = &mut app.view
+    else {
+        return;
+    };
+    let Some(vault) = &mut app.vault else {
+        return;
+    };
+    let trash: Vec<_> = vault
+        .trash()
+        .iter()
+        .map(|s| (s.id, s.name.clone()))
+        .collect();
+    let selected = trash.get((*selected_idx).min(trash.len().saturating_sub(1)));
+
+    // A pending purge takes `y` to go ahead; any other key cancels it.
+    if let Some(purge) = confirm.take() {
+        if key.code != KeyCode::Char('y') {
+            return;
+        }
+        let result = match purge {
+            Purge::One(id) => vault.purge(id).map(|()| "Secret purged.".to_string()),
+            Purge::All => vault
+                .empty_trash()
+                .map(|n| format!("{n} secret(s) purged.")),
+        };
+        *selected_idx = (*selected_idx).min(vault.trash().len().saturating_sub(1));
+        app.status = Some(result.unwrap_or_else(|e| format!("Purge failed: {e}")));
+        return;
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Left => app.go_to_list(),
+        KeyCode::Char('?') => app.view = AppView::Help,
+        KeyCode::Char('j') | KeyCode::Down => {
+            *selected_idx = (*selected_idx + 1).min(trash.len().saturating_sub(1));
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            *selected_idx = selected_idx.saturating_sub(1);
+        }
+        KeyCode::Char('r') | KeyCode::Enter => {
+            if let Some((id, name)) = selected {
+                let result = vault.restore(*id);
+                *selected_idx = (*selected_idx).min(vault.trash().len().saturating_sub(1));
+                app.status = Some(match result {
+                    Ok(()) => format!("'{name}' restored."),
+                    Err(e) => format!("Restore failed: {e}"),
+                });
+            }
+        }
+        KeyCode::Char('x') | KeyCode::Delete => {
+            if let Some((id, _)) = selected {
+                *confirm = Some(Purge::One(*id));
+            }
+        }
+        KeyCode::Char('E') if !trash.is_empty() => *confirm = Some(Purge::All),
+        _ => {}
+    }
+}
+
+fn handle_audit(app: &mut AppState, key: KeyEvent) {
+    let AppView::Audit { rows, selected_idx } = &mut app.view else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Left => app.go_to_list(),
+        KeyCode::Char('j') | KeyCode::Down => {
+            *selected_idx = (*selected_idx + 1).min(rows.len().saturating_sub(1));
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            *selected_idx = selected_idx.saturating_sub(1);
+        }
+        KeyCode::Enter | KeyCode::Char('e') => {
+            let Some(row) = rows.get(*selected_idx) else {
+                return;
+            };
+            let secret_id = row.secret_id;
+            // Land on the field that needs fixing.
+            let slot = match row.issue {
+                Issue::DuplicateName => FormSlot::Name,
+                Issue::MissingUsername => FormSlot::Username,
+                Issue::Reused | Issue::Weak | Issue::Old => FormSlot::Password,
+                Issue::MissingUrl => FormSlot::Url,
+            };
+            let draft = app
+                .vault
+                .as_ref()
+                .and_then(|v| v.get(secret_id))
+                .map(SecretDraft::from_secret);
+            if let Some(draft) = draft {
+                let focused_field = draft.position(slot);
+                app.view = AppView::Form {
+                    mode: FormMode::Edit(secret_id),
+                    draft: Box::new(draft),
+                    focused_field,
+                    show_password: false,
+                    error: None,
+                };
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Copy `text` to the system clipboard and schedule it to be cleared.
+fn copy_to_clipboard(app: &mut AppState, text: &str, what: &str) {
+    match arboard::Clipboard::new() {
+        Ok(mut cb) => {
+            if cb.set_text(text).is_ok() {
+                match app.config.clipboard_timeout() {
+                    Some(timeout) => {
+                        app.clipboard_clear_at = Some(Instant::now() + timeout);
+                        app.status =
+                            Some(format!("{what} copied — clears in {}s", timeout.as_secs()));
+                    }
+                    None => app.status = Some(format!("{what} copied")),
+                }
+            } else {
+                app.status = Some("Failed to copy to clipboard".to_string());
+            }
+        }
+        Err(_) => {
+            app.status = Some("Clipboard not available".to_string());
+        }
+    }
+}
 
 fn handle_form(app: &mut AppState, key: KeyEvent) {
     if app.generator_popup.is_some() {
@@ -259,6 +663,49 @@ fn handle_form(app: &mut AppState, key: KeyEvent) {
         return;
     }
 
+    let (slot, field_count) = match &app.view {
+        AppView::Form {
+            draft,
+            focused_field,
+            ..
+        } => {
+            let slots = draft.slots();
+            (slots[*focused_field], slots.len())
+        }
+        _ => return,
+    };
+
+    // Enter starts a new line in a multi-line field (a pasted key sends one
+    // per line, as Enter or Ctrl+J): Tab away from it to save.
+    if is_multiline(app, slot)
+        && (key.code == KeyCode::Enter
+            || key.code == KeyCode::Char('j') && key.modifiers.contains(KeyModifiers::CONTROL))
+    {
+        type_char(app, '\n');
+        return;
+    }
+
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        handle_form_rows(app, key);
+        return;
+    }
+
+    if slot == FormSlot::Kind {
+        if matches!(
+            key.code,
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+        ) {
+            if let AppView::Form { draft, error, .. } = &mut app.view {
+                draft.set_kind(draft.kind.next());
+                *error = None;
+            }
+            return;
+        }
+        if matches!(key.code, KeyCode::Char(_) | KeyCode::Backspace) {
+            return;
+        }
+    }
+
     match key.code {
         KeyCode::Esc => {
             let mode = match &app.view {
@@ -277,28 +724,28 @@ fn handle_form(app: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Tab => {
             if let AppView::Form { focused_field, .. } = &mut app.view {
-                *focused_field = (*focused_field + 1) % FORM_FIELD_COUNT;
+                *focused_field = (*focused_field + 1) % field_count;
             }
         }
         KeyCode::BackTab => {
             if let AppView::Form { focused_field, .. } = &mut app.view {
-                *focused_field = (*focused_field + FORM_FIELD_COUNT - 1) % FORM_FIELD_COUNT;
+                *focused_field = (*focused_field + field_count - 1) % field_count;
             }
         }
         KeyCode::Enter => {
             save_form(app);
         }
         KeyCode::Char('g') => {
-            let is_pwd = is_password_field(app);
-            if is_pwd {
-                app.generator_popup = Some(GeneratorDraft::new());
+            if slot == FormSlot::Password {
+                app.generator_popup = Some(GeneratorDraft::new(
+                    &app.config.generator.to_generator_config(),
+                ));
             } else {
                 type_char(app, 'g');
             }
         }
         KeyCode::Char(' ') => {
-            let is_pwd = is_password_field(app);
-            if is_pwd {
+            if slot == FormSlot::Password {
                 if let AppView::Form { show_password, .. } = &mut app.view {
                     *show_password = !*show_password;
                 }
@@ -318,7 +765,53 @@ fn handle_form(app: &mut AppState, key: KeyEvent) {
             } = &mut app.view
             {
                 *error = None;
-                get_field_mut(draft, *focused_field).pop();
+                if let Some(field) = get_field_mut(draft, *focused_field) {
+                    memory::backspace(field);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Ctrl+N adds a custom field row, Ctrl+D removes the focused one and
+/// Ctrl+T cycles its kind.
+fn handle_form_rows(app: &mut AppState, key: KeyEvent) {
+    let AppView::Form {
+        draft,
+        focused_field,
+        error,
+        ..
+    } = &mut app.view
+    else {
+        return;
+    };
+    let row = match draft.slots()[*focused_field] {
+        FormSlot::FieldLabel(row) | FormSlot::FieldValue(row) => Some(row),
+        _ => None,
+    };
+
+    match key.code {
+        KeyCode::Char('n') => {
+            draft.fields.push(CustomField::new("", "", FieldKind::Text));
+            *focused_field = draft.position(FormSlot::FieldLabel(draft.fields.len() - 1));
+        }
+        KeyCode::Char('d') => {
+            if let Some(row) = row {
+                draft.fields.remove(row);
+                *error = None;
+                *focused_field = if draft.fields.is_empty() {
+                    draft.slots().len() - 1
+                } else {
+                    draft.position(FormSlot::FieldLabel(row.min(draft.fields.len() - 1)))
+                };
+            }
+        }
+        KeyCode::Char('t') => {
+            if let Some(row) = row {
+                let field = &mut draft.fields[row];
+                field.kind = field.kind.next();
+                *error = None;
             }
         }
         _ => {}
@@ -331,63 +824,112 @@ fn handle_generator_popup(app: &mut AppState, key: KeyEvent) {
             app.generator_popup = None;
         }
         KeyCode::Enter => {
+            // Settings that cannot produce a password keep the popup open.
             let preview = app.generator_popup.as_ref().map(|p| p.preview.clone());
-            if let Some(pwd) = preview {
-                if let AppView::Form { draft, .. } = &mut app.view {
-                    draft.password = pwd;
+            match preview {
+                Some(Err(_)) => return,
+                Some(Ok(pwd)) => {
+                    if let AppView::Form { draft, .. } = &mut app.view {
+                        draft.password = pwd;
+                    }
                 }
+                None => {}
             }
             app.generator_popup = None;
         }
         KeyCode::Tab => {
             if let Some(popup) = &mut app.generator_popup {
-                popup.focused = (popup.focused + 1) % 5;
+                popup.focused = (popup.focused + 1) % popup.field_count();
                 popup.regenerate();
             }
         }
         KeyCode::BackTab => {
             if let Some(popup) = &mut app.generator_popup {
-                popup.focused = (popup.focused + 4) % 5;
+                let count = popup.field_count();
+                popup.focused = (popup.focused + count - 1) % count;
                 popup.regenerate();
             }
         }
-        KeyCode::Char(' ') => {
+        KeyCode::Char(c) => {
             if let Some(popup) = &mut app.generator_popup {
-                match popup.focused {
-                    1 => popup.uppercase = !popup.uppercase,
-                    2 => popup.lowercase = !popup.lowercase,
-                    3 => popup.digits = !popup.digits,
-                    4 => popup.symbols = !popup.symbols,
-                    _ => {}
-                }
+                generator_char(popup, c);
                 popup.regenerate();
             }
         }
-        KeyCode::Char(c) if c.is_ascii_digit() => {
+        KeyCode::Backspace => {
             if let Some(popup) = &mut app.generator_popup {
-                if popup.focused == 0 && popup.length_str.len() < 3 {
-                    popup.length_str.push(c);
-                    popup.regenerate();
+                match (popup.mode, popup.focused) {
+                    (GeneratorMode::Characters, 1) => {
+                        popup.length_str.pop();
+                    }
+                    (GeneratorMode::Characters, 3 | 5 | 7 | 9) => {
+                        if let Some(min) = popup.focused_min() {
+                            min.pop();
+                        }
+                    }
+                    (GeneratorMode::Characters, 12) => {
+                        popup.include.pop();
+                    }
+                    (GeneratorMode::Characters, 13) => {
+                        popup.exclude.pop();
+                    }
+                    (GeneratorMode::Passphrase, 1) => {
+                        popup.words_str.pop();
+                    }
+                    (GeneratorMode::Passphrase, 2) => {
+                        popup.separator.pop();
+                    }
+                    _ => {}
                 }
+                popup.regenerate();
             }
         }
-        KeyCode::Backspace => {
-            if let Some(popup) = &mut app.generator_popup {
-                if popup.focused == 0 {
-                    popup.length_str.pop();
-                    popup.regenerate();
-                }
+        _ => {}
+    }
+}
+
+/// A typed character: Space flips the focused toggle, digits edit the
+/// numeric rows, and anything goes in the separator and character sets.
+fn generator_char(popup: &mut GeneratorDraft, c: char) {
+    match (popup.mode, popup.focused, c) {
+        (_, 0, ' ') => popup.toggle_mode(),
+        (GeneratorMode::Characters, 1, c) if c.is_ascii_digit() && popup.length_str.len() < 3 => {
+            popup.length_str.push(c)
+        }
+        (GeneratorMode::Characters, 2, ' ') => popup.uppercase = !popup.uppercase,
+        (GeneratorMode::Characters, 4, ' ') => popup.lowercase = !popup.lowercase,
+        (GeneratorMode::Characters, 6, ' ') => popup.digits = !popup.digits,
+        (GeneratorMode::Characters, 8, ' ') => popup.symbols = !popup.symbols,
+        (GeneratorMode::Characters, 3 | 5 | 7 | 9, c) if c.is_ascii_digit() => {
+            if let Some(min) = popup.focused_min().filter(|min| min.len() < 2) {
+                min.push(c);
             }
         }
+        (GeneratorMode::Characters, 10, ' ') => popup.avoid_ambiguous = !popup.avoid_ambiguous,
+        (GeneratorMode::Characters, 11, ' ') => popup.no_repeat = !popup.no_repeat,
+        (GeneratorMode::Characters, 12, c) if popup.include.chars().count() < 20 => {
+            popup.include.push(c)
+        }
+        (GeneratorMode::Characters, 13, c) if popup.exclude.chars().count() < 20 => {
+            popup.exclude.push(c)
+        }
+        (GeneratorMode::Passphrase, 1, c) if c.is_ascii_digit() && popup.words_str.len() < 2 => {
+            popup.words_str.push(c)
+        }
+        (GeneratorMode::Passphrase, 2, c) if popup.separator.chars().count() < 3 => {
+            popup.separator.push(c)
+        }
+        (GeneratorMode::Passphrase, 3, ' ') => popup.capitalize = !popup.capitalize,
+        (GeneratorMode::Passphrase, 4, ' ') => popup.add_digit = !popup.add_digit,
         _ => {}
     }
 }
 
-fn is_password_field(app: &AppState) -> bool {
-    if let AppView::Form { focused_field, .. } = &app.view {
-        *focused_field == 2
-    } else {
-        false
+/// Whether `slot` holds a field of the kind's schema that spans lines.
+fn is_multiline(app: &AppState, slot: FormSlot) -> bool {
+    match (&app.view, slot) {
+        (AppView::Form { draft, .. }, FormSlot::Detail(i)) => draft.kind.schema()[i].multiline,
+        _ => false,
     }
 }
 
@@ -400,20 +942,28 @@ fn type_char(app: &mut AppState, c: char) {
     } = &mut app.view
     {
         *error = None;
-        get_field_mut(draft, *focused_field).push(c);
+        if let Some(field) = get_field_mut(draft, *focused_field) {
+            field.push(c);
+        }
     }
 }
 
-fn get_field_mut(draft: &mut SecretDraft, field: usize) -> &mut String {
-    match field {
-        0 => &mut draft.name,
-        1 => &mut draft.username,
-        2 => &mut draft.password,
-        3 => &mut draft.url,
-        4 => &mut draft.tags,
-        5 => &mut draft.notes,
-        _ => &mut draft.name,
-    }
+/// The text behind the form row at `field`; `None` for the kind selector.
+fn get_field_mut(draft: &mut SecretDraft, field: usize) -> Option<&mut String> {
+    Some(match draft.slots()[field] {
+        FormSlot::Name => &mut draft.name,
+        FormSlot::Kind => return None,
+        FormSlot::Username => &mut draft.username,
+        FormSlot::Password => &mut draft.password,
+        FormSlot::Url => &mut draft.url,
+        FormSlot::Detail(i) => &mut draft.details[i],
+        FormSlot::Group => &mut draft.group,
+        FormSlot::Tags => &mut draft.tags,
+        FormSlot::Notes => &mut draft.notes,
+        FormSlot::Otp => &mut draft.otp,
+        FormSlot::FieldLabel(row) => &mut draft.fields[row].label,
+        FormSlot::FieldValue(row) => &mut draft.fields[row].value,
+    })
 }
 
 fn save_form(app: &mut AppState) {
@@ -424,21 +974,48 @@ fn save_form(app: &mut AppState) {
 
     if let Some(msg) = draft.validate() {
         if let AppView::Form { error, .. } = &mut app.view {
-            *error = Some(msg.to_string());
+            *error = Some(msg);
         }
         return;
     }
 
-    let mut secret = Secret::new(&draft.name, &draft.password);
-    if !draft.username.is_empty() {
+    // Rows hidden for the kind are dropped rather than saved unseen.
+    let kind = draft.kind;
+    let password = match kind.password_label() {
+        Some(_) => draft.password.as_str(),
+        None => "",
+    };
+    let mut secret = Secret::new(&draft.name, password);
+    secret.kind = kind;
+    if kind.has_login() && !draft.username.is_empty() {
         secret.username = Some(draft.username.clone());
     }
-    if !draft.url.is_empty() {
+    if kind.has_login() && !draft.url.is_empty() {
         secret.url = Some(draft.url.clone());
     }
     if !draft.notes.is_empty() {
         secret.notes = Some(draft.notes.clone());
     }
+    if kind == EntryKind::Login && !draft.otp.trim().is_empty() {
+        secret.otp = Otp::parse(&draft.otp).ok();
+    }
+    secret.fields = kind
+        .schema()
+        .iter()
+        .zip(&draft.details)
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(spec, value)| {
+            // Key files end with a newline that tools such as ssh expect.
+            let value = if spec.multiline { value } else { value.trim() };
+            CustomField::new(spec.label, value, spec.kind)
+        })
+        .chain(
+            draft
+                .filled_fields()
+                .map(|f| CustomField::new(f.label.trim(), f.value.as_str(), f.kind)),
+        )
+        .collect();
+    secret.group = group::normalize(&draft.group);
     secret.tags = draft
         .tags
         .split(',')
@@ -450,7 +1027,8 @@ fn save_form(app: &mut AppState) {
         FormMode::Add => app.vault.as_mut().map(|v| v.add(secret)),
         FormMode::Edit(id) => {
             let id = *id;
-            app.vault.as_mut().map(|v| v.update(id, secret))
+            let based_on = draft.edited_from.unwrap_or(secret.updated_at);
+            app.vault.as_mut().map(|v| v.update(id, secret, based_on))
         }
     };
 
@@ -468,6 +1046,62 @@ fn save_form(app: &mut AppState) {
     }
 }
 
+fn handle_change_password(app: &mut AppState, key: KeyEvent) {
+    let AppView::ChangePassword {
+        inputs,
+        focused,
+        error,
+    } = &mut app.view
+    else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc => app.go_to_list(),
+        KeyCode::Tab | KeyCode::Down => *focused = (*focused + 1) % 3,
+        KeyCode::BackTab | KeyCode::Up => *focused = (*focused + 2) % 3,
+        KeyCode::Char(c) => {
+            inputs[*focused].push(c);
+            *error = None;
+        }
+        KeyCode::Backspace => {
+            memory::backspace(&mut inputs[*focused]);
+        }
+        KeyCode::Enter if *focused < 2 => *focused += 1,
+        KeyCode::Enter => {
+            let [current, new, confirm] = std::mem::take(inputs);
+            let Some(vault) = &mut app.vault else {
+                return;
+            };
+
+            let result = if new.is_empty() {
+                Err("New password must not be empty".to_string())
+            } else if new != confirm {
+                Err("New passwords do not match".to_string())
+            } else if !vault.verify_password(&current) {
+                Err("Current password is wrong".to_string())
+            } else {
+                vault.rekey(&new).map_err(|e| e.to_string())
+            };
+
+            match result {
+                Ok(()) => {
+                    app.go_to_list();
+                    app.status = Some("Master password changed.".to_string());
+                }
+                Err(msg) => {
+                    if let AppView::ChangePassword { focused, error, .. } = &mut app.view {
+                        *focused = 0;
+                        *error = Some(msg);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Also used for the read-only vault info screen.
 fn handle_help(app: &mut AppState, key: KeyEvent) {
     match key.code {
         KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => {
@@ -476,3 +1110,68 @@ fn handle_help(app: &mut AppState, key: KeyEvent) {
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use serdevault::VaultFile;
+    use tempfile::tempdir;
+
+    fn press(app: &mut AppState, code: KeyCode) {
+        handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn test_unlock_resumes_view() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.svlt");
+        let vault = VaultFile::open(&path, "pw").with_params(8, 1, 1);
+        let mut manager = VaultManager::open_or_create(vault).unwrap();
+        let secret = Secret::new("GitHub", "s3cr3t");
+        let id = secret.id;
+        manager.add(secret).unwrap();
+
+        let mut app = AppState::new(path, Config::default());
+        app.vault = Some(manager);
+        app.view = AppView::History {
+            secret_id: id,
+            selected_idx: 0,
+            show_password: true,
+        };
+        app.lock();
+
+        for c in "wrong".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.view, AppView::Locked { error: Some(_), .. }));
+
+        for c in "pw".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(app.vault.is_some());
+        assert!(matches!(
+            app.view,
+            AppView::History { secret_id, show_password: false, .. } if secret_id == id
+        ));
+        assert!(app.resume_view.is_none());
+    }
+
+    #[test]
+    fn test_lowercase_i_searches_and_uppercase_opens_info() {
+        let dir = tempdir().unwrap();
+        let vault = VaultFile::open(dir.path().join("vault.svlt"), "pw").with_params(8, 1, 1);
+        let mut app = AppState::new(dir.path().join("vault.svlt"), Config::default());
+        app.vault = Some(VaultManager::open_or_create(vault).unwrap());
+        app.go_to_list();
+
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('i'));
+        assert!(matches!(&app.view, AppView::List { search_query, .. } if search_query == "gi"));
+
+        press(&mut app, KeyCode::Char('I'));
+        assert!(matches!(app.view, AppView::Info));
+    }
+}
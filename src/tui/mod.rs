@@ -13,10 +13,18 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
+use crate::config::Config;
 use app::{AppState, AppView};
 
 /// Set up the terminal, run the TUI event loop, and restore the terminal on exit.
-pub fn run(vault_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+/// `vaults` are the `(label, path)` pairs the unlock screen can switch between.
+/// With an `idle_timeout`, the vault locks after that long without a key press.
+pub fn run(
+    vault_path: PathBuf,
+    vaults: Vec<(String, PathBuf)>,
+    idle_timeout: Option<Duration>,
+    config: Config,
+) -> Result<(), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -24,7 +32,9 @@ pub fn run(vault_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = AppState::new(vault_path);
+    let mut app = AppState::new(vault_path, config);
+    app.vaults = vaults;
+    app.idle_timeout = idle_timeout;
     let result = run_loop(&mut terminal, &mut app);
 
     // Always restore the terminal, even on error.
@@ -48,10 +58,35 @@ fn run_loop(
 
         if event::poll(Duration::from_millis(250))? {
             if let Event::Key(key) = event::read()? {
+                app.last_activity = Instant::now();
                 events::handle_key(app, key);
             }
         }
 
+        // Pick up changes made by another process (e.g. `valt add`) while
+        // browsing; edits elsewhere reload on save instead.
+        if let (AppView::List { selected_idx, .. }, Some(vault)) = (&mut app.view, &mut app.vault) {
+            match vault.refresh() {
+                Ok(true) => {
+                    *selected_idx = (*selected_idx).min(vault.list().len().saturating_sub(1));
+                    app.status =
+                        Some("Vault reloaded: it was changed by another process.".to_string());
+                }
+                Ok(false) => {}
+                Err(e) => app.status = Some(format!("Cannot reload the vault: {e}")),
+            }
+        }
+
+        if let Some(timeout) = app.idle_timeout {
+            if app.vault.is_some() && app.last_activity.elapsed() >= timeout {
+                app.lock();
+                app.status = Some(format!(
+                    "Locked after {} of inactivity.",
+                    format_idle(timeout)
+                ));
+            }
+        }
+
         // Clipboard auto-clear: once the deadline passes, overwrite with empty string.
         if let Some(deadline) = app.clipboard_clear_at {
             if Instant::now() >= deadline {
@@ -70,12 +105,27 @@ fn run_loop(
     Ok(())
 }
 
+fn format_idle(timeout: Duration) -> String {
+    let secs = timeout.as_secs();
+    if secs.is_multiple_of(60) {
+        let mins = secs / 60;
+        format!("{mins} minute{}", if mins == 1 { "" } else { "s" })
+    } else {
+        format!("{secs} seconds")
+    }
+}
+
 fn render(f: &mut ratatui::Frame, app: &AppState) {
     match &app.view {
         AppView::Locked { .. } => views::unlock::render(f, app),
         AppView::List { .. } => views::list::render(f, app),
         AppView::Detail { .. } => views::detail::render(f, app),
+        AppView::History { .. } => views::history::render(f, app),
         AppView::Form { .. } => views::form::render(f, app),
+        AppView::ChangePassword { .. } => views::passwd::render(f, app),
+        AppView::Audit { .. } => views::audit::render(f, app),
+        AppView::Trash { .. } => views::trash::render(f, app),
+        AppView::Info => views::info::render(f, app),
         AppView::Help => views::help::render(f, app),
     }
 }
@@ -0,0 +1,100 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::core::audit::Issue;
+use crate::tui::app::{AppState, AppView};
+
+pub fn render(f: &mut Frame, app: &AppState) {
+    let AppView::Audit { rows, selected_idx } = &app.view else {
+        return;
+    };
+
+    let vault = match &app.vault {
+        Some(v) => v,
+        None => return,
+    };
+
+    let count = rows.len();
+    let selected = (*selected_idx).min(count.saturating_sub(1));
+
+    let area = f.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " Vault audit ── {count} issue{} ",
+            if count == 1 { "" } else { "s" }
+        ));
+
+    if rows.is_empty() {
+        let lines = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("  ✓ No issues found in {} secrets.", vault.list().len()),
+                Style::default().fg(Color::Green),
+            )),
+        ];
+        f.render_widget(Paragraph::new(lines).block(block), chunks[0]);
+    } else {
+        let items: Vec<ListItem> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let is_sel = i == selected;
+                let bg = if is_sel { Color::Cyan } else { Color::Reset };
+                let issue_fg = match (is_sel, row.issue) {
+                    (true, _) => Color::Black,
+                    (false, Issue::Reused | Issue::Weak) => Color::Red,
+                    (false, Issue::Old | Issue::DuplicateName) => Color::Yellow,
+                    (false, Issue::MissingUsername | Issue::MissingUrl) => Color::DarkGray,
+                };
+                let fg = if is_sel { Color::Black } else { Color::White };
+                let dim = if is_sel {
+                    Color::Black
+                } else {
+                    Color::DarkGray
+                };
+                let name = vault
+                    .get(row.secret_id)
+                    .map(|s| s.name.as_str())
+                    .unwrap_or("?");
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!(" {:<18}", row.issue.title()),
+                        Style::default().fg(issue_fg).bg(bg),
+                    ),
+                    Span::styled(
+                        format!("{name:<28}"),
+                        Style::default().fg(fg).bg(bg).add_modifier(if is_sel {
+                            Modifier::BOLD
+                        } else {
+                            Modifier::empty()
+                        }),
+                    ),
+                    Span::styled(row.detail.clone(), Style::default().fg(dim).bg(bg)),
+                ]))
+            })
+            .collect();
+
+        let mut list_state = ListState::default();
+        list_state.select(Some(selected));
+        f.render_stateful_widget(List::new(items).block(block), chunks[0], &mut list_state);
+    }
+
+    f.render_widget(
+        Paragraph::new("[↑↓/jk] Navigate  [↵/e] Edit entry  [Esc] Back")
+            .style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+}
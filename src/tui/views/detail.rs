@@ -6,6 +6,8 @@ use ratatui::{
     Frame,
 };
 
+use crate::core::otp::{unix_now, Otp, OtpKind};
+use crate::core::FieldKind;
 use crate::tui::app::{AppState, AppView};
 
 pub fn render(f: &mut Frame, app: &AppState) {
@@ -34,7 +36,7 @@ pub fn render(f: &mut Frame, app: &AppState) {
         .split(area);
 
     let pwd_display = if *show_password {
-        secret.password.clone()
+        secret.password.to_string()
     } else {
         "•".repeat(secret.password.len().min(30))
     };
@@ -46,7 +48,7 @@ pub fn render(f: &mut Frame, app: &AppState) {
 
     let clip_hint = match app.clipboard_secs_remaining() {
         Some(secs) => format!("[c] Copy  (clears in {secs}s)"),
-        None => "[c] Copy password".to_string(),
+        None => format!("[c] Copy {}", secret.kind.main_field().to_lowercase()),
     };
 
     let tags_str = if secret.tags.is_empty() {
@@ -55,31 +57,71 @@ pub fn render(f: &mut Frame, app: &AppState) {
         secret.tags.join(", ")
     };
 
-    let lines = vec![
+    let mut lines = vec![
         Line::from(""),
         field_line("Name    ", &secret.name, Color::White),
-        field_line(
+        field_line("Type    ", secret.kind.title(), Color::White),
+    ];
+    if secret.kind.has_login() {
+        lines.push(field_line(
             "Username",
             secret.username.as_deref().unwrap_or("—"),
             Color::White,
-        ),
-        Line::from(vec![
-            Span::styled("  Password : ", Style::default().fg(Color::DarkGray)),
+        ));
+    }
+    if let Some(label) = secret.kind.password_label() {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {label:<8} : "),
+                Style::default().fg(Color::DarkGray),
+            ),
             Span::styled(&pwd_display, Style::default().fg(Color::Yellow)),
             Span::raw("  "),
             Span::styled(pwd_hint, Style::default().fg(Color::DarkGray)),
-        ]),
-        field_line(
+        ]));
+    }
+    if let Some(count) = secret.breach_count {
+        lines.push(Line::from(Span::styled(
+            format!("             ⚠ Found in {count} data breaches — change this password"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if let Some(otp) = &secret.otp {
+        lines.push(otp_line(otp));
+    }
+    if secret.kind.has_login() {
+        lines.push(field_line(
             "URL     ",
             secret.url.as_deref().unwrap_or("—"),
             Color::White,
-        ),
-        field_line("Tags    ", &tags_str, Color::Blue),
+        ));
+    }
+    lines.extend([
         field_line(
-            "Notes   ",
-            secret.notes.as_deref().unwrap_or("—"),
+            "Group   ",
+            secret.group.as_deref().unwrap_or("—"),
             Color::White,
         ),
+        field_line("Tags    ", &tags_str, Color::Blue),
+    ]);
+    lines.extend(value_lines(
+        "Notes",
+        secret.notes.as_deref().map_or("—", String::as_str),
+        Color::White,
+    ));
+    for field in &secret.fields {
+        let (value, color) = match field.kind {
+            FieldKind::Hidden if !show_password => (
+                "•".repeat(field.value.chars().count().min(30)),
+                Color::Yellow,
+            ),
+            FieldKind::Hidden => (field.value.to_string(), Color::Yellow),
+            FieldKind::Url | FieldKind::Email => (field.value.to_string(), Color::Cyan),
+            FieldKind::Text | FieldKind::Date => (field.value.to_string(), Color::White),
+        };
+        lines.extend(value_lines(&field.label, &value, color));
+    }
+    lines.extend([
         Line::from(""),
         Line::from(vec![
             Span::styled("  Created : ", Style::default().fg(Color::DarkGray)),
@@ -89,7 +131,7 @@ pub fn render(f: &mut Frame, app: &AppState) {
             Span::styled("  Updated : ", Style::default().fg(Color::DarkGray)),
             Span::raw(secret.updated_at.format("%Y-%m-%d").to_string()),
         ]),
-    ];
+    ]);
 
     let block = Block::default()
         .borders(Borders::ALL)
@@ -98,7 +140,31 @@ pub fn render(f: &mut Frame, app: &AppState) {
 
     f.render_widget(Paragraph::new(lines).block(block), chunks[0]);
 
-    let status = format!("[e] Edit  {clip_hint}  [d] Delete  [Esc] Back  [?] Help");
+    let otp_hint = if secret.otp.is_some() {
+        "[o] Copy code  "
+    } else {
+        ""
+    };
+    let history_hint = if secret.history.is_empty() {
+        ""
+    } else {
+        "[h] History  "
+    };
+    // Without a password row, Space still reveals the hidden fields.
+    let reveal_hint = if secret.kind.password_label().is_none()
+        && secret.fields.iter().any(|f| f.kind == FieldKind::Hidden)
+    {
+        format!("{pwd_hint}  ")
+    } else {
+        String::new()
+    };
+    let status = match &app.status {
+        Some(msg) => msg.clone(),
+        None => format!(
+            "[e] Edit  {clip_hint}  {reveal_hint}{otp_hint}{history_hint}[d] Delete  {}[Esc] Back  [?] Help",
+            app.undo_hint()
+        ),
+    };
     f.render_widget(
         Paragraph::new(status).style(Style::default().fg(Color::DarkGray)),
         chunks[1],
@@ -114,3 +180,67 @@ fn field_line<'a>(label: &'a str, value: &'a str, value_color: Color) -> Line<'a
         Span::styled(value, Style::default().fg(value_color)),
     ])
 }
+
+/// `label : value`, with the further lines of a value that spans several
+/// (a note, a private key) aligned under the first.
+fn value_lines(label: &str, value: &str, color: Color) -> Vec<Line<'static>> {
+    let label = format!("  {label:<8} : ");
+    let indent = " ".repeat(label.chars().count());
+    value
+        .trim_end_matches('\n')
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 {
+                label.clone()
+            } else {
+                indent.clone()
+            };
+            Line::from(vec![
+                Span::styled(prefix, Style::default().fg(Color::DarkGray)),
+                Span::styled(line.to_string(), Style::default().fg(color)),
+            ])
+        })
+        .collect()
+}
+
+/// Current one-time code, grouped for readability, with a countdown bar for TOTP.
+fn otp_line(otp: &Otp) -> Line<'static> {
+    let now = unix_now();
+    let label = Span::styled("  OTP      : ", Style::default().fg(Color::DarkGray));
+
+    let OtpKind::Totp { period } = otp.kind else {
+        return Line::from(vec![
+            label,
+            Span::styled(
+                "HOTP — [o] copies the next code",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+    };
+
+    let Ok(code) = otp.generate(now) else {
+        return Line::from(vec![
+            label,
+            Span::styled("(invalid seed)", Style::default().fg(Color::Red)),
+        ]);
+    };
+    let (head, tail) = code.split_at(code.len() / 2);
+
+    let remaining = otp.seconds_remaining(now).ok().flatten().unwrap_or(period);
+    let filled = ((remaining * 10).div_ceil(period) as usize).min(10);
+    let bar = format!("{}{}", "█".repeat(filled), "░".repeat(10 - filled));
+    let color = if remaining <= 5 {
+        Color::Red
+    } else {
+        Color::Green
+    };
+
+    Line::from(vec![
+        label,
+        Span::styled(format!("{head} {tail}"), Style::default().fg(Color::Yellow)),
+        Span::raw("  "),
+        Span::styled(bar, Style::default().fg(color)),
+        Span::styled(format!(" {remaining:>2}s"), Style::default().fg(color)),
+    ])
+}
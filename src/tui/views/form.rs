@@ -6,9 +6,11 @@ use ratatui::{
     Frame,
 };
 
-use crate::tui::app::{AppState, AppView, FormMode};
+use crate::core::{strength, EntryKind, FieldKind, GeneratorMode, Strength};
+use crate::tui::app::{AppState, AppView, FormMode, FormSlot, SecretDraft};
 
-const LABELS: [&str; 6] = ["Name", "Username", "Password", "URL", "Tags", "Notes"];
+/// Hidden values show as at most this many bullets.
+const MASK_WIDTH: usize = 40;
 
 pub fn render(f: &mut Frame, app: &AppState) {
     let AppView::Form {
@@ -33,26 +35,36 @@ pub fn render(f: &mut Frame, app: &AppState) {
         FormMode::Edit(_) => " Edit Secret ",
     };
 
-    let field_values: [&str; 6] = [
-        &draft.name,
-        &draft.username,
-        &draft.password,
-        &draft.url,
-        &draft.tags,
-        &draft.notes,
-    ];
-
+    let slots = draft.slots();
+    let fixed = slots.len() - 2 * draft.fields.len();
+    // Rows are spaced out unless that would push the end of a long form
+    // (an identity) off the screen.
+    let spaced = 2 * fixed + draft.fields.len() + 6 <= chunks[0].height as usize;
     let mut lines = vec![Line::from("")];
 
-    for (i, (&label, &value)) in LABELS.iter().zip(field_values.iter()).enumerate() {
+    for (i, &slot) in slots[..fixed].iter().enumerate() {
         let is_focused = i == *focused_field;
+        let is_password = slot == FormSlot::Password;
+        let (label, value, hidden, multiline) = slot_text(draft, slot);
 
-        // Mask password unless show_password is active.
-        let display: String = if i == 2 && !show_password {
-            "•".repeat(value.len())
+        // Mask the password unless show_password is active; other hidden
+        // fields also show while being typed.
+        let masked = if is_password {
+            !show_password
+        } else {
+            hidden && !show_password && !is_focused
+        };
+        let mut display = if masked {
+            "•".repeat(value.chars().count().min(MASK_WIDTH))
+        } else if multiline {
+            // Only the line being typed fits: the others are counted.
+            value.rsplit('\n').next().unwrap_or("").to_string()
         } else {
             value.to_string()
         };
+        if multiline && value.contains('\n') {
+            display.push_str(&format!("  ({} lines)", value.lines().count()));
+        }
 
         let label_style = if is_focused {
             Style::default()
@@ -69,18 +81,68 @@ pub fn render(f: &mut Frame, app: &AppState) {
         };
 
         let mut spans = vec![
-            Span::styled(format!("  {:10}: ", label), label_style),
+            Span::styled(format!("  {:15}: ", label), label_style),
             Span::styled(format!("{display:<40}"), value_style),
         ];
 
-        if i == 2 && is_focused {
-            spans.push(Span::styled(
-                "  [g] Generate  [Space] Toggle",
-                Style::default().fg(Color::DarkGray),
-            ));
+        let hint = match slot {
+            FormSlot::Password => "  [g] Generate  [Space] Toggle",
+            FormSlot::Kind => "  [Space] Change type",
+            _ if multiline => "  [Enter] New line  [Tab] Done",
+            _ => "",
+        };
+        if is_focused && !hint.is_empty() {
+            spans.push(Span::styled(hint, Style::default().fg(Color::DarkGray)));
         }
 
         lines.push(Line::from(spans));
+        // Strength of a login or network password; a key passphrase is not
+        // typed often enough to be judged the same way.
+        if is_password
+            && matches!(draft.kind, EntryKind::Login | EntryKind::Wifi)
+            && !value.is_empty()
+        {
+            let mut meter = vec![Span::raw(format!("  {:15}  ", ""))];
+            meter.extend(strength_spans(&strength::estimate(value)));
+            lines.push(Line::from(meter));
+        } else if spaced {
+            lines.push(Line::from(""));
+        }
+    }
+
+    if !draft.fields.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  Custom fields",
+            Style::default().fg(Color::Cyan),
+        )));
+    }
+    for (row, field) in draft.fields.iter().enumerate() {
+        let label_idx = fixed + 2 * row;
+        let label_focused = *focused_field == label_idx;
+        let value_focused = *focused_field == label_idx + 1;
+
+        let value: String = if field.kind == FieldKind::Hidden && !show_password {
+            "•".repeat(field.value.chars().count().min(MASK_WIDTH))
+        } else {
+            field.value.to_string()
+        };
+
+        lines.push(Line::from(vec![
+            Span::raw("  "),
+            Span::styled(format!("{:<14}", field.label), cell_style(label_focused)),
+            Span::styled(": ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{value:<36}"), cell_style(value_focused)),
+            Span::styled(
+                format!("  ({})", field.kind),
+                Style::default().fg(if label_focused || value_focused {
+                    Color::Cyan
+                } else {
+                    Color::DarkGray
+                }),
+            ),
+        ]));
+    }
+    if !draft.fields.is_empty() {
         lines.push(Line::from(""));
     }
 
@@ -99,8 +161,11 @@ pub fn render(f: &mut Frame, app: &AppState) {
     f.render_widget(Paragraph::new(lines).block(block), chunks[0]);
 
     f.render_widget(
-        Paragraph::new("[Tab] Next field  [Shift+Tab] Prev  [Enter] Save  [Esc] Cancel")
-            .style(Style::default().fg(Color::DarkGray)),
+        Paragraph::new(
+            "[Tab] Next field  [Shift+Tab] Prev  [Ctrl+N] Add field  [Ctrl+D] Remove field  \
+             [Ctrl+T] Field type  [Enter] Save  [Esc] Cancel",
+        )
+        .style(Style::default().fg(Color::DarkGray)),
         chunks[1],
     );
 
@@ -109,6 +174,45 @@ pub fn render(f: &mut Frame, app: &AppState) {
     }
 }
 
+/// Label, value, whether the value is hidden and whether it spans lines,
+/// for a row of the form above the custom fields.
+fn slot_text(draft: &SecretDraft, slot: FormSlot) -> (&'static str, &str, bool, bool) {
+    match slot {
+        FormSlot::Name => ("Name", &draft.name, false, false),
+        FormSlot::Kind => ("Type", draft.kind.title(), false, false),
+        FormSlot::Username => ("Username", &draft.username, false, false),
+        FormSlot::Password => (
+            draft.kind.password_label().unwrap_or("Password"),
+            &draft.password,
+            true,
+            false,
+        ),
+        FormSlot::Url => ("URL", &draft.url, false, false),
+        FormSlot::Detail(i) => {
+            let spec = &draft.kind.schema()[i];
+            (
+                spec.label,
+                &draft.details[i],
+                spec.kind == FieldKind::Hidden,
+                spec.multiline,
+            )
+        }
+        FormSlot::Group => ("Group", &draft.group, false, false),
+        FormSlot::Tags => ("Tags", &draft.tags, false, false),
+        FormSlot::Notes => ("Notes", &draft.notes, false, false),
+        FormSlot::Otp => ("OTP", &draft.otp, false, false),
+        FormSlot::FieldLabel(_) | FormSlot::FieldValue(_) => ("", "", false, false),
+    }
+}
+
+fn cell_style(focused: bool) -> Style {
+    if focused {
+        Style::default().fg(Color::White).bg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    }
+}
+
 fn centered_fixed(w: u16, h: u16, area: Rect) -> Rect {
     let x = area.x + (area.width.saturating_sub(w)) / 2;
     let y = area.y + (area.height.saturating_sub(h)) / 2;
@@ -126,64 +230,117 @@ fn render_generator_popup(f: &mut Frame, app: &AppState) {
         None => return,
     };
 
-    // 36 wide × 11 tall (9 content lines + 2 border lines)
-    let popup_area = centered_fixed(36, 11, f.area());
-    f.render_widget(Clear, popup_area);
-
     let mut lines: Vec<Line> = Vec::new();
     lines.push(Line::from(""));
 
-    let (length_label_style, length_val_style) = if popup.focused == 0 {
-        (
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-            Style::default().fg(Color::White).bg(Color::DarkGray),
-        )
-    } else {
-        (
-            Style::default().fg(Color::DarkGray),
-            Style::default().fg(Color::White),
-        )
+    let mode = match popup.mode {
+        GeneratorMode::Characters => "Characters",
+        GeneratorMode::Passphrase => "Passphrase",
     };
-    lines.push(Line::from(vec![
-        Span::styled("  Length:  ", length_label_style),
-        Span::styled(format!("[{:<10}]", popup.length_str), length_val_style),
-    ]));
+    lines.push(value_row(
+        "Mode:    ",
+        &format!("< {mode} >"),
+        popup.focused == 0,
+    ));
     lines.push(Line::from(""));
 
-    lines.push(toggle_row(
-        "Uppercase",
-        popup.uppercase,
-        popup.focused == 1,
-        "Lowercase",
-        popup.lowercase,
-        popup.focused == 2,
-    ));
-    lines.push(toggle_row(
-        "Digits  ",
-        popup.digits,
-        popup.focused == 3,
-        "Symbols ",
-        popup.symbols,
-        popup.focused == 4,
-    ));
+    match popup.mode {
+        GeneratorMode::Characters => {
+            lines.push(value_row(
+                "Length:  ",
+                &format!("[{:<10}]", popup.length_str),
+                popup.focused == 1,
+            ));
+            lines.push(Line::from(""));
+            let classes = [
+                ("Uppercase", popup.uppercase),
+                ("Lowercase", popup.lowercase),
+                ("Digits   ", popup.digits),
+                ("Symbols  ", popup.symbols),
+            ];
+            for (i, ((label, checked), min)) in classes.into_iter().zip(&popup.mins).enumerate() {
+                let row = 2 + 2 * i;
+                lines.push(class_row(
+                    label,
+                    checked,
+                    popup.focused == row,
+                    min,
+                    popup.focused == row + 1,
+                ));
+            }
+            lines.push(toggle_row(
+                "No 0/O/l",
+                popup.avoid_ambiguous,
+                popup.focused == 10,
+                "No repeat",
+                popup.no_repeat,
+                popup.focused == 11,
+            ));
+            lines.push(Line::from(""));
+            lines.push(value_row(
+                "Include: ",
+                &format!("[{:<20}]", popup.include),
+                popup.focused == 12,
+            ));
+            lines.push(value_row(
+                "Exclude: ",
+                &format!("[{:<20}]", popup.exclude),
+                popup.focused == 13,
+            ));
+        }
+        GeneratorMode::Passphrase => {
+            lines.push(value_row(
+                "Words:   ",
+                &format!("[{:<10}]", popup.words_str),
+                popup.focused == 1,
+            ));
+            lines.push(value_row(
+                "Sep.:    ",
+                &format!("[{:<10}]", popup.separator),
+                popup.focused == 2,
+            ));
+            lines.push(Line::from(""));
+            lines.push(toggle_row(
+                "Capitals",
+                popup.capitalize,
+                popup.focused == 3,
+                "Number  ",
+                popup.add_digit,
+                popup.focused == 4,
+            ));
+        }
+    }
     lines.push(Line::from(""));
 
-    lines.push(Line::from(vec![
-        Span::raw("  "),
-        Span::styled(
-            format!("▶ {} ◀", popup.preview),
+    let preview = match &popup.preview {
+        Ok(pwd) => Span::styled(
+            format!("▶ {} ◀", pwd.as_str()),
             Style::default().fg(Color::Cyan),
         ),
-    ]));
-    lines.push(Line::from(""));
+        Err(e) => Span::styled(e.clone(), Style::default().fg(Color::Red)),
+    };
+    let preview_width = preview.content.chars().count() as u16;
+    lines.push(Line::from(vec![Span::raw("  "), preview]));
+    match &popup.preview {
+        Ok(pwd) => {
+            let mut meter = vec![Span::raw("  ")];
+            meter.extend(strength_spans(&strength::estimate(pwd)));
+            lines.push(Line::from(meter));
+        }
+        Err(_) => lines.push(Line::from("")),
+    }
 
     lines.push(Line::from(Span::styled(
         "  [Enter] Apply   [Esc] Cancel",
         Style::default().fg(Color::DarkGray),
     )));
 
+    // At least 36 wide, wider for long passphrases; as tall as the rows of
+    // the current mode plus the borders.
+    let width = (preview_width + 6).max(36);
+    let popup_area = centered_fixed(width, lines.len() as u16 + 2, f.area());
+    f.render_widget(Clear, popup_area);
+
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan))
@@ -191,6 +348,53 @@ fn render_generator_popup(f: &mut Frame, app: &AppState) {
     f.render_widget(Paragraph::new(lines).block(block), popup_area);
 }
 
+/// A five-block meter coloured by score, then the label, entropy, crack time
+/// and the first weakness found.
+fn strength_spans(strength: &Strength) -> Vec<Span<'static>> {
+    let color = match strength.score {
+        0 => Color::Red,
+        1 => Color::LightRed,
+        2 => Color::Yellow,
+        3 => Color::LightGreen,
+        _ => Color::Green,
+    };
+    let filled = strength.score as usize + 1;
+    let mut details = format!(
+        " {} · {:.0} bits · {}",
+        strength.label(),
+        strength.bits,
+        strength.crack_time()
+    );
+    if let Some(weakness) = strength.weaknesses.first() {
+        details.push_str(&format!(" · {}", weakness.describe()));
+    }
+    vec![
+        Span::styled("■".repeat(filled), Style::default().fg(color)),
+        Span::styled("■".repeat(5 - filled), Style::default().fg(Color::DarkGray)),
+        Span::styled(details, Style::default().fg(color)),
+    ]
+}
+
+fn value_row(label: &str, value: &str, focused: bool) -> Line<'static> {
+    let (label_style, value_style) = if focused {
+        (
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            Style::default().fg(Color::White).bg(Color::DarkGray),
+        )
+    } else {
+        (
+            Style::default().fg(Color::DarkGray),
+            Style::default().fg(Color::White),
+        )
+    };
+    Line::from(vec![
+        Span::styled(format!("  {label}"), label_style),
+        Span::styled(value.to_string(), value_style),
+    ])
+}
+
 fn toggle_row(
     label1: &str,
     checked1: bool,
@@ -206,6 +410,29 @@ fn toggle_row(
     Line::from(spans)
 }
 
+/// A character class toggle followed by the minimum count of that class.
+fn class_row(
+    label: &str,
+    checked: bool,
+    toggle_focused: bool,
+    min: &str,
+    min_focused: bool,
+) -> Line<'static> {
+    let min_style = if min_focused {
+        Style::default().fg(Color::White).bg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    let mut spans: Vec<Span<'static>> = vec![Span::raw("  ")];
+    spans.extend(toggle_spans(label, checked, toggle_focused));
+    spans.push(Span::styled(
+        "  at least ",
+        Style::default().fg(Color::DarkGray),
+    ));
+    spans.push(Span::styled(format!("[{min:<2}]"), min_style));
+    Line::from(spans)
+}
+
 fn toggle_spans(label: &str, checked: bool, focused: bool) -> Vec<Span<'static>> {
     let checkbox: &'static str = if checked { "[x]" } else { "[ ]" };
     let checkbox_style = if focused {
@@ -14,10 +14,32 @@ const SHORTCUTS: &[(&str, &str)] = &[
     ("↵ / →", "Open detail"),
     ("n", "New secret"),
     ("e", "Edit secret"),
-    ("d", "Delete secret"),
-    ("c", "Copy password (auto-clears in 30 s)"),
+    ("d", "Move secret to the trash"),
+    (
+        "u / Ctrl+R",
+        "Undo / redo the last add, edit, delete or restore",
+    ),
+    (
+        "c",
+        "Copy password, card number… (auto-clears, see clipboard_timeout)",
+    ),
+    ("o", "Copy one-time code (OTP)"),
+    ("h", "Password history (r: restore, c: copy)"),
     ("Space", "Toggle password visibility"),
     ("g", "Generate password (in password field)"),
+    ("Space", "Change entry type (form Type row)"),
+    (
+        "Space",
+        "Switch characters / passphrase (generator Mode row)",
+    ),
+    ("Ctrl+N / Ctrl+D", "Add / remove custom field (in form)"),
+    ("Ctrl+T", "Cycle custom field type (in form)"),
+    ("I", "Vault info (from list)"),
+    ("L", "Lock the vault (from list)"),
+    ("A", "Vault audit (from list; ↵ edits the entry)"),
+    ("P", "Change master password (from list)"),
+    ("Tab", "Group tree (from list; h/l or Space fold)"),
+    ("T", "Trash (from list; r: restore, x: purge, E: empty)"),
     ("Esc", "Back / cancel / clear search"),
     ("?", "This help screen"),
     ("q / Ctrl+C", "Quit"),
@@ -0,0 +1,96 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::{AppState, AppView};
+
+pub fn render(f: &mut Frame, app: &AppState) {
+    let AppView::History {
+        secret_id,
+        selected_idx,
+        show_password,
+    } = &app.view
+    else {
+        return;
+    };
+
+    let secret = match app.vault.as_ref().and_then(|v| v.get(*secret_id)) {
+        Some(s) => s,
+        None => return,
+    };
+
+    let count = secret.history.len();
+    let selected = (*selected_idx).min(count.saturating_sub(1));
+
+    let area = f.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let items: Vec<ListItem> = secret
+        .history
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let is_sel = i == selected;
+            let bg = if is_sel { Color::Cyan } else { Color::Reset };
+            let fg = if is_sel { Color::Black } else { Color::White };
+            let dim = if is_sel {
+                Color::Black
+            } else {
+                Color::DarkGray
+            };
+            let pwd_fg = if is_sel { Color::Black } else { Color::Yellow };
+
+            let password = if *show_password {
+                entry.password.to_string()
+            } else {
+                "•".repeat(entry.password.chars().count().min(30))
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {:>2}  ", i + 1), Style::default().fg(dim).bg(bg)),
+                Span::styled(
+                    format!("{}  ", entry.retired_at.format("%Y-%m-%d %H:%M")),
+                    Style::default().fg(fg).bg(bg).add_modifier(if is_sel {
+                        Modifier::BOLD
+                    } else {
+                        Modifier::empty()
+                    }),
+                ),
+                Span::styled(password, Style::default().fg(pwd_fg).bg(bg)),
+            ]))
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ── password history ", secret.name));
+
+    let mut list_state = ListState::default();
+    if count > 0 {
+        list_state.select(Some(selected));
+    }
+    f.render_stateful_widget(List::new(items).block(block), chunks[0], &mut list_state);
+
+    let status = match &app.status {
+        Some(msg) => msg.clone(),
+        None => {
+            let clip = app
+                .clipboard_secs_remaining()
+                .map(|s| format!("  [clipboard clears in {s}s]"))
+                .unwrap_or_default();
+            format!("[↑↓/jk] Navigate  [c] Copy  [r] Restore  [Space] Show  [Esc] Back{clip}")
+        }
+    };
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+}
@@ -0,0 +1,77 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::AppState;
+
+pub fn render(f: &mut Frame, app: &AppState) {
+    let vault = match &app.vault {
+        Some(v) => v,
+        None => return,
+    };
+
+    let area = f.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let meta = std::fs::metadata(&app.vault_path).ok();
+    let size = meta
+        .as_ref()
+        .map(|m| format!("{:.1} KiB", m.len() as f64 / 1024.0))
+        .unwrap_or_else(|| "—".to_string());
+    let modified = meta
+        .and_then(|m| m.modified().ok())
+        .map(|t| {
+            chrono::DateTime::<chrono::Local>::from(t)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| "—".to_string());
+    let kdf = vault
+        .kdf_params()
+        .map(|p| format!("Argon2id — {p}"))
+        .unwrap_or_else(|| "—".to_string());
+
+    let rows = [
+        ("Path    ", app.vault_path.display().to_string()),
+        ("Size    ", size),
+        ("Modified", modified),
+        ("Secrets ", vault.list().len().to_string()),
+        ("Trash   ", vault.trash().len().to_string()),
+        ("Cipher  ", "AES-256-GCM".to_string()),
+        ("KDF     ", kdf),
+    ];
+
+    let mut lines = vec![Line::from("")];
+    for (label, value) in rows {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {label} : "),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Tune the key derivation with `valt kdf`.",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Vault info ");
+
+    f.render_widget(Paragraph::new(lines).block(block), chunks[0]);
+    f.render_widget(
+        Paragraph::new("[Esc]  Back to list").style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+}
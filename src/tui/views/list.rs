@@ -1,11 +1,12 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
+use crate::core::{group::Node, EntryKind, Secret};
 use crate::tui::app::{AppState, AppView};
 
 pub fn render(f: &mut Frame, app: &AppState) {
@@ -22,9 +23,10 @@ pub fn render(f: &mut Frame, app: &AppState) {
         None => return,
     };
 
-    let secrets = vault.search(search_query);
+    let secrets = app.list_results(search_query);
     let count = secrets.len();
     let total = vault.list().len();
+    let groups = app.group_rows();
     let selected = (*selected_idx).min(count.saturating_sub(1));
 
     let area = f.area();
@@ -37,6 +39,17 @@ pub fn render(f: &mut Frame, app: &AppState) {
         ])
         .split(area);
 
+    let list_area = if groups.is_empty() {
+        chunks[1]
+    } else {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(28), Constraint::Min(1)])
+            .split(chunks[1]);
+        render_sidebar(f, app, &groups, total, columns[0]);
+        columns[1]
+    };
+
     let search_block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray))
@@ -91,10 +104,21 @@ pub fn render(f: &mut Frame, app: &AppState) {
                     Modifier::empty()
                 });
 
+            let badge_fg = if is_sel { Color::Black } else { Color::Red };
+            let badge = if s.breach_count.is_some() {
+                "⚠ "
+            } else {
+                "  "
+            };
+
             let line = Line::from(vec![
-                Span::styled(format!(" {:<28}", &s.name), name_style),
                 Span::styled(
-                    format!("{:<25}", s.url.as_deref().unwrap_or("")),
+                    format!(" {badge}"),
+                    Style::default().fg(badge_fg).bg(base_bg),
+                ),
+                Span::styled(format!("{:<28}", &s.name), name_style),
+                Span::styled(
+                    format!("{:<25}", kind_or_url(s)),
                     Style::default().fg(dim_fg).bg(base_bg),
                 ),
                 Span::styled(tags_str, Style::default().fg(tag_fg).bg(base_bg)),
@@ -103,9 +127,12 @@ pub fn render(f: &mut Frame, app: &AppState) {
         })
         .collect();
 
-    let list_block = Block::default()
+    let mut list_block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray));
+    if let Some(group) = app.group_filter() {
+        list_block = list_block.title(format!(" {group} ── {count} "));
+    }
 
     let mut list_state = ListState::default();
     if !secrets.is_empty() {
@@ -114,7 +141,7 @@ pub fn render(f: &mut Frame, app: &AppState) {
 
     f.render_stateful_widget(
         List::new(items).block(list_block),
-        chunks[1],
+        list_area,
         &mut list_state,
     );
 
@@ -125,7 +152,21 @@ pub fn render(f: &mut Frame, app: &AppState) {
             .clipboard_secs_remaining()
             .map(|s| format!("  [clipboard clears in {s}s]"))
             .unwrap_or_default();
-        format!("[↑↓/jk] Navigate  [↵] Open  [n] New  [d] Delete  [q] Quit  [?] Help{clip}")
+        let groups_hint = if groups.is_empty() {
+            ""
+        } else {
+            "[Tab] Groups  "
+        };
+        if app.sidebar.focused {
+            format!(
+                "[↑↓/jk] Group  [←→/hl] Fold / unfold  [Tab/↵] Back to the list  [q] Quit{clip}"
+            )
+        } else {
+            format!(
+                "[↑↓/jk] Navigate  [↵] Open  [n] New  [d] Delete  {}{groups_hint}[T] Trash  [q] Quit  [?] Help{clip}",
+                app.undo_hint()
+            )
+        }
     };
 
     f.render_widget(
@@ -133,3 +174,69 @@ pub fn render(f: &mut Frame, app: &AppState) {
         chunks[2],
     );
 }
+
+/// The group tree: "All" first, then every group indented by depth, with
+/// ▸ on folded groups and ▾ on open ones.
+fn render_sidebar(f: &mut Frame, app: &AppState, groups: &[Node], total: usize, area: Rect) {
+    let current = app.group_filter();
+    let selected = current
+        .and_then(|g| groups.iter().position(|n| n.path == g))
+        .map_or(0, |i| i + 1);
+
+    let row = |i: usize, label: String, count: usize| {
+        let style = if i == selected && app.sidebar.focused {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else if i == selected {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(label, style),
+            Span::styled(format!(" {count}"), Style::default().fg(Color::DarkGray)),
+        ]))
+    };
+
+    let mut items = vec![row(0, "All".to_string(), total)];
+    for (i, node) in groups.iter().enumerate() {
+        let marker = match (
+            node.has_children,
+            app.sidebar.collapsed.contains(&node.path),
+        ) {
+            (false, _) => " ",
+            (true, true) => "▸",
+            (true, false) => "▾",
+        };
+        let indent = "  ".repeat(node.depth);
+        items.push(row(
+            i + 1,
+            format!("{indent}{marker} {}", node.name),
+            node.count,
+        ));
+    }
+
+    let border = if app.sidebar.focused {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border))
+        .title(" Groups ");
+
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    f.render_stateful_widget(List::new(items).block(block), area, &mut state);
+}
+
+/// The URL of a login; other kinds show their type where they have none.
+fn kind_or_url(secret: &Secret) -> String {
+    match (&secret.url, secret.kind) {
+        (Some(url), _) => url.clone(),
+        (None, EntryKind::Login) => String::new(),
+        (None, kind) => format!("({})", kind.title()),
+    }
+}
@@ -1,5 +1,10 @@
+pub mod audit;
 pub mod detail;
 pub mod form;
 pub mod help;
+pub mod history;
+pub mod info;
 pub mod list;
+pub mod passwd;
+pub mod trash;
 pub mod unlock;
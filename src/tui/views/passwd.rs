@@ -0,0 +1,82 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::{AppState, AppView};
+
+const LABELS: [&str; 3] = ["Current password", "New password    ", "Confirm         "];
+
+pub fn render(f: &mut Frame, app: &AppState) {
+    let AppView::ChangePassword {
+        inputs,
+        focused,
+        error,
+    } = &app.view
+    else {
+        return;
+    };
+
+    let area = f.area();
+    let width = 60.min(area.width);
+    let height = 12.min(area.height);
+    let dialog = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, dialog);
+
+    let mut lines = vec![Line::from("")];
+    for (i, (label, input)) in LABELS.iter().zip(inputs.iter()).enumerate() {
+        let is_focused = i == *focused;
+        let label_style = if is_focused {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let mut spans = vec![
+            Span::styled(format!("  {label}  "), label_style),
+            Span::styled(
+                "•".repeat(input.chars().count()),
+                Style::default().fg(Color::Yellow),
+            ),
+        ];
+        if is_focused {
+            spans.push(Span::styled(
+                "█",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::SLOW_BLINK),
+            ));
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::from(""));
+    }
+
+    lines.push(Line::from(Span::styled(
+        "  The vault is re-encrypted under the new password.",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  ✗ {err}"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Blue))
+        .title(" Change master password ── [Enter] Next / Apply  [Esc] Cancel ");
+
+    f.render_widget(Paragraph::new(lines).block(block), dialog);
+}
@@ -0,0 +1,116 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::{AppState, AppView, Purge};
+
+pub fn render(f: &mut Frame, app: &AppState) {
+    let AppView::Trash {
+        selected_idx,
+        confirm,
+    } = &app.view
+    else {
+        return;
+    };
+
+    let vault = match &app.vault {
+        Some(v) => v,
+        None => return,
+    };
+
+    let trash = vault.trash();
+    let count = trash.len();
+    let selected = (*selected_idx).min(count.saturating_sub(1));
+
+    let area = f.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " Trash ── {count} secret{} ",
+            if count == 1 { "" } else { "s" }
+        ));
+
+    if trash.is_empty() {
+        let lines = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "  The trash is empty.",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ];
+        f.render_widget(Paragraph::new(lines).block(block), chunks[0]);
+    } else {
+        let items: Vec<ListItem> = trash
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let is_sel = i == selected;
+                let bg = if is_sel { Color::Cyan } else { Color::Reset };
+                let fg = if is_sel { Color::Black } else { Color::White };
+                let dim = if is_sel {
+                    Color::Black
+                } else {
+                    Color::DarkGray
+                };
+                let deleted = s
+                    .deleted_at
+                    .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                let purge = vault
+                    .purge_due(s.id)
+                    .map(|d| format!("purged after {}", d.format("%Y-%m-%d")))
+                    .unwrap_or_default();
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!(" {deleted}  "), Style::default().fg(dim).bg(bg)),
+                    Span::styled(
+                        format!("{:<28}", s.name),
+                        Style::default().fg(fg).bg(bg).add_modifier(if is_sel {
+                            Modifier::BOLD
+                        } else {
+                            Modifier::empty()
+                        }),
+                    ),
+                    Span::styled(purge, Style::default().fg(dim).bg(bg)),
+                ]))
+            })
+            .collect();
+
+        let mut list_state = ListState::default();
+        list_state.select(Some(selected));
+        f.render_stateful_widget(List::new(items).block(block), chunks[0], &mut list_state);
+    }
+
+    let (status, color) = match (confirm, &app.status) {
+        (Some(Purge::One(_)), _) => {
+            let name = trash.get(selected).map(|s| s.name.as_str()).unwrap_or("?");
+            (
+                format!("Purge '{name}' for good? [y] Yes  [any key] No"),
+                Color::Yellow,
+            )
+        }
+        (Some(Purge::All), _) => (
+            format!("Purge all {count} secret(s) for good? [y] Yes  [any key] No"),
+            Color::Yellow,
+        ),
+        (None, Some(msg)) => (msg.clone(), Color::DarkGray),
+        (None, None) => (
+            "[↑↓/jk] Navigate  [r/↵] Restore  [x] Purge  [E] Empty trash  [Esc] Back".to_string(),
+            Color::DarkGray,
+        ),
+    };
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().fg(color)),
+        chunks[1],
+    );
+}
@@ -39,9 +39,23 @@ pub fn render(f: &mut Frame, app: &AppState) {
     f.render_widget(Clear, dialog);
 
     let masked: String = "•".repeat(input.len());
-    let vault_path = app.vault_path.display().to_string();
+    let vault_path = match app.vault_label() {
+        Some(label) if app.vaults.len() > 1 => {
+            format!("[{label}] {}", app.vault_path.display())
+        }
+        _ => app.vault_path.display().to_string(),
+    };
 
-    let (status_line, path_color) = if app.vault_exists() {
+    let (status_line, path_color) = if let Some(msg) = &app.status {
+        // Why the vault was locked, after an idle timeout or `L`.
+        (
+            Line::from(Span::styled(
+                format!("  🔒 {msg}"),
+                Style::default().fg(Color::Yellow),
+            )),
+            Color::DarkGray,
+        )
+    } else if app.vault_exists() {
         (
             Line::from(Span::styled(
                 "  ✓ Vault found",
@@ -59,6 +73,31 @@ pub fn render(f: &mut Frame, app: &AppState) {
         )
     };
 
+    let key = |k: &'static str| {
+        Span::styled(
+            k,
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+    let mut hints = vec![
+        Span::styled("  ", Style::default()),
+        key("Enter"),
+        Span::styled(" Unlock", Style::default().fg(Color::DarkGray)),
+        Span::styled("  ·  ", Style::default().fg(Color::DarkGray)),
+    ];
+    if app.vaults.len() > 1 {
+        hints.push(key("Tab"));
+        hints.push(Span::styled(
+            " Switch vault",
+            Style::default().fg(Color::DarkGray),
+        ));
+        hints.push(Span::styled("  ·  ", Style::default().fg(Color::DarkGray)));
+    }
+    hints.push(key("Esc"));
+    hints.push(Span::styled(" Quit", Style::default().fg(Color::DarkGray)));
+
     let mut lines = vec![
         Line::from(""),
         Line::from(Span::styled(
@@ -90,24 +129,7 @@ pub fn render(f: &mut Frame, app: &AppState) {
             ),
         ]),
         Line::from(""),
-        Line::from(vec![
-            Span::styled("  ", Style::default()),
-            Span::styled(
-                "Enter",
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" Unlock", Style::default().fg(Color::DarkGray)),
-            Span::styled("  ·  ", Style::default().fg(Color::DarkGray)),
-            Span::styled(
-                "Esc",
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" Quit", Style::default().fg(Color::DarkGray)),
-        ]),
+        Line::from(hints),
     ];
 
     if let Some(err) = error {